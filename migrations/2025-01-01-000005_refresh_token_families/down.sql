@@ -0,0 +1,3 @@
+DROP TABLE security_events;
+ALTER TABLE refresh_tokens DROP COLUMN used_at, DROP COLUMN family_id;
+DROP TABLE refresh_token_families;
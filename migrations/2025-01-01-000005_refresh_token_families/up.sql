@@ -0,0 +1,31 @@
+CREATE TABLE refresh_token_families (
+    id             UUID PRIMARY KEY,
+    user_id        UUID        NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT now(),
+    revoked_at     TIMESTAMPTZ,
+    revoked_reason TEXT
+);
+
+-- Every existing token starts its own family.
+INSERT INTO refresh_token_families (id, user_id, created_at, revoked_at, revoked_reason)
+SELECT id, user_id, created_at, revoked_at,
+       CASE WHEN revoked_at IS NULL THEN NULL ELSE 'logout' END
+FROM refresh_tokens;
+
+ALTER TABLE refresh_tokens
+    ADD COLUMN family_id UUID REFERENCES refresh_token_families (id) ON DELETE CASCADE,
+    ADD COLUMN used_at   TIMESTAMPTZ;
+UPDATE refresh_tokens SET family_id = id;
+ALTER TABLE refresh_tokens ALTER COLUMN family_id SET NOT NULL;
+
+CREATE INDEX refresh_tokens_family_id_idx ON refresh_tokens (family_id);
+
+CREATE TABLE security_events (
+    id         UUID PRIMARY KEY,
+    user_id    UUID REFERENCES users (id) ON DELETE SET NULL,
+    kind       TEXT        NOT NULL,
+    detail     TEXT        NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX security_events_user_id_idx ON security_events (user_id);
@@ -0,0 +1,2 @@
+DELETE FROM role_permissions WHERE permission = 'signing_keys:manage';
+DROP TABLE signing_keys;
@@ -0,0 +1,14 @@
+-- Keys that sign access tokens issued by this service. The newest key that
+-- is not retired signs; retired keys stay published until purged.
+CREATE TABLE signing_keys (
+    kid         TEXT PRIMARY KEY,
+    algorithm   TEXT        NOT NULL,
+    -- AES-256-GCM sealed PKCS#1 PEM, nonce prepended
+    private_key BYTEA       NOT NULL,
+    public_n    TEXT        NOT NULL,
+    public_e    TEXT        NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    retired_at  TIMESTAMPTZ
+);
+
+INSERT INTO role_permissions (role, permission) VALUES ('admin', 'signing_keys:manage');
@@ -0,0 +1,19 @@
+CREATE TABLE user_totp (
+    user_id        UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    -- AES-256-GCM sealed secret, nonce prepended
+    secret         BYTEA       NOT NULL,
+    -- NULL until the user proves possession of the secret
+    enabled_at     TIMESTAMPTZ,
+    -- Last accepted time step; codes for this step or earlier are replays
+    last_used_step BIGINT,
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE mfa_recovery_codes (
+    id         UUID PRIMARY KEY,
+    user_id    UUID        NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    code_hash  TEXT        NOT NULL,
+    used_at    TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (user_id, code_hash)
+);
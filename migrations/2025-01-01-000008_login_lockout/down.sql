@@ -0,0 +1,6 @@
+DELETE FROM role_permissions WHERE permission = 'users:unlock';
+
+ALTER TABLE users
+    DROP COLUMN locked_until,
+    DROP COLUMN last_failed_login_at,
+    DROP COLUMN failed_login_count;
@@ -0,0 +1,7 @@
+ALTER TABLE users
+    -- Consecutive failed password logins; reset on success and on lockout
+    ADD COLUMN failed_login_count   INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN last_failed_login_at TIMESTAMPTZ,
+    ADD COLUMN locked_until         TIMESTAMPTZ;
+
+INSERT INTO role_permissions (role, permission) VALUES ('admin', 'users:unlock');
@@ -0,0 +1,467 @@
+use crate::applications::lifecycle::Lifecycle;
+use crate::common::i18n;
+use crate::config::env_settings::Settings;
+use crate::config::reload::{ConfigReloader, SettingsReceiver, project};
+use crate::domains::account::AccountTrait;
+use crate::domains::api_key::ApiKeyTrait;
+use crate::domains::authentication::{
+    AccessTokenVerifier, AuthenticationTrait, OidcAuthenticationTrait,
+};
+use crate::domains::authorization::AuthorizationTrait;
+use crate::domains::health::{HealthcheckTrait, LifecycleState};
+use crate::domains::lockout::LockoutTrait;
+use crate::domains::mfa::MfaTrait;
+use crate::domains::session::SessionTrait;
+use crate::domains::signing_key::SigningKeyTrait;
+use crate::domains::token::TokenIntrospectionTrait;
+use crate::infrastructures::cache::health::CacheHealthCheck;
+use crate::infrastructures::cache::local_cache::{
+    CacheRegistry, NamespaceConfig,
+};
+use crate::infrastructures::database;
+use crate::infrastructures::database::health::DatabaseHealthCheck;
+use crate::infrastructures::database::{DbPool, init_database_connection};
+use crate::infrastructures::jwt::JwtKeys;
+use crate::infrastructures::jwt::denylist::TokenDenylist;
+use crate::infrastructures::jwt::jwks::JwksVerifier;
+use crate::infrastructures::log::logger::follow_log_level;
+use crate::infrastructures::mail;
+use crate::infrastructures::oidc;
+use crate::infrastructures::otel::health::OtlpHealthCheck;
+use crate::infrastructures::otel::tracer::init_tracer_provider;
+use crate::infrastructures::session::CacheSessionStore;
+use crate::infrastructures::session::cookie::SessionCookie;
+use crate::services::v1::account::AccountService;
+use crate::services::v1::api_key::ApiKeyService;
+use crate::services::v1::authentication::AuthenticationService;
+use crate::services::v1::authorization::AuthorizationService;
+use crate::services::v1::healthcheck::{HealthCheckRegistry, HealthcheckService};
+use crate::services::v1::lockout::LockoutService;
+use crate::services::v1::mfa::MfaService;
+use crate::services::v1::oidc::OidcService;
+use crate::services::v1::session::SessionService;
+use crate::services::v1::signing_key::SigningKeyService;
+use crate::services::v1::token::TokenService;
+use crate::web::api::app_registry;
+use crate::web::api::app_state::AppState;
+use crate::web::api::router::{register_routers, register_startup_routers};
+use anyhow::{Context, Error};
+use axum::Router;
+use axum::extract::Request;
+use log::{error, info, warn};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::signal;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tower::{ServiceExt, service_fn};
+
+/// How often each instance reloads the signing key ring, picking up
+/// rotations made elsewhere and rotating when due.
+const SIGNING_KEY_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Application owns every runtime dependency built from a single `Settings`
+/// value: the HTTP server, the router and the shared state behind it.
+pub struct Application {
+    /// Router handed to the server once `run` starts
+    router: Router,
+    routes: watch::Sender<Router>,
+    server: JoinHandle<io::Result<()>>,
+    reloader: Option<ConfigReloader>,
+    lifecycle: Arc<Lifecycle>,
+    tracer_provider: SdkTracerProvider,
+    /// Upper bound for in-flight queries to finish once the server stopped
+    close_timeout: Duration,
+}
+
+impl Application {
+    /// Bind the listener and serve the health probes, which report
+    /// `starting`, then build all dependencies in startup order, failing on
+    /// the first one that cannot be initialized.
+    pub async fn build(settings: Settings) -> Result<Self, Error> {
+        settings.validate()?;
+        let lifecycle = Arc::new(Lifecycle::new());
+
+        let bind_address = settings.server.bind_address();
+        let listener = TcpListener::bind(&bind_address)
+            .await
+            .with_context(|| format!("failed to bind {bind_address}"))?;
+        info!("Listening on {bind_address}");
+        let probes = HealthcheckService::new(
+            HealthCheckRegistry::new(settings.health.check_timeout()),
+            lifecycle.subscribe(),
+        );
+        let (routes, server) = serve(
+            listener,
+            register_startup_routers(Arc::new(probes)),
+            drain(lifecycle.clone(), settings.server.drain_period()),
+        );
+
+        let abort = server.abort_handle();
+        let built = Self::init(settings, lifecycle, routes, server).await;
+        if built.is_err() {
+            abort.abort();
+        }
+        built
+    }
+
+    async fn init(
+        settings: Settings, lifecycle: Arc<Lifecycle>,
+        routes: watch::Sender<Router>, server: JoinHandle<io::Result<()>>,
+    ) -> Result<Self, Error> {
+        i18n::set_default_locale(&settings.i18n.default_locale);
+        let settings = Arc::new(settings);
+        let reloader = ConfigReloader::new(settings.clone());
+        follow_log_level(project(reloader.subscribe(), |s| {
+            s.server.log_level.clone()
+        }));
+
+        info!("Started initializing tracer provider");
+        let provider = init_tracer_provider(&settings)
+            .context("failed to initialize tracer provider")?;
+        opentelemetry::global::set_tracer_provider(provider.clone());
+        let tracer = Arc::new(opentelemetry::global::tracer("api"));
+        info!("Completed initializing tracer provider");
+
+        info!("Started initializing database connection");
+        init_database_connection(
+            settings.database.connection_url().expose(),
+            settings.database.max_connections,
+        )
+        .await
+        .context("failed to initialize database connection")?;
+        let db_pool: &'static DbPool = database::pool();
+        info!("Completed initializing database connection");
+
+        info!("Started initializing local cache");
+        CacheRegistry::init_with(namespace_configs(&settings));
+        let local_caches: Arc<CacheRegistry> = CacheRegistry::global().clone();
+        follow_cache_namespaces(reloader.subscribe(), local_caches.clone());
+        info!("Completed initializing local cache");
+
+        // Initialize services
+        let health_svc: Arc<dyn HealthcheckTrait> =
+            Arc::new(HealthcheckService::new(
+                health_checks(&settings, db_pool, local_caches.clone()),
+                lifecycle.subscribe(),
+            ));
+        let jwt_keys = Arc::new(JwtKeys::from_config(&settings.auth));
+        let signing_key_svc =
+            init_signing_keys(&settings, db_pool, jwt_keys.clone()).await?;
+        let mfa_svc: Arc<dyn MfaTrait> = Arc::new(MfaService::new(
+            db_pool,
+            local_caches.clone(),
+            &settings.auth,
+            settings
+                .cache
+                .namespace("state")
+                .map_or(120, |ns| ns.ttl_seconds),
+        ));
+        let lockout_svc: Arc<dyn LockoutTrait> = Arc::new(LockoutService::new(
+            db_pool,
+            local_caches.clone(),
+            &settings.auth.lockout,
+        ));
+        let auth_svc: Arc<dyn AuthenticationTrait> =
+            Arc::new(AuthenticationService::new(
+                db_pool,
+                jwt_keys.clone(),
+                mfa_svc.clone(),
+                lockout_svc.clone(),
+                settings.auth.refresh_token_ttl_seconds,
+            ));
+        let mailer = mail::from_config(&settings.mail)
+            .context("failed to initialize mail transport")?;
+        let account_svc: Arc<dyn AccountTrait> = Arc::new(AccountService::new(
+            db_pool,
+            mailer,
+            &settings.auth.account_tokens,
+        ));
+        let authz_svc: Arc<dyn AuthorizationTrait> =
+            Arc::new(AuthorizationService::new(db_pool, local_caches.clone()));
+        let api_key_svc: Arc<dyn ApiKeyTrait> =
+            Arc::new(ApiKeyService::new(db_pool));
+        let token_denylist = Arc::new(TokenDenylist::new(
+            local_caches.clone(),
+            Duration::from_secs(settings.auth.access_token_ttl_seconds),
+            settings.auth.token_denylist_capacity,
+        ));
+        let token_svc: Arc<dyn TokenIntrospectionTrait> =
+            Arc::new(TokenService::new(
+                db_pool,
+                jwt_keys.clone(),
+                token_denylist.clone(),
+                auth_svc.clone(),
+                api_key_svc.clone(),
+            ));
+        let session_svc: Arc<dyn SessionTrait> = Arc::new(SessionService::new(
+            Arc::new(CacheSessionStore::new(local_caches.clone())),
+            &settings.session,
+        ));
+        let session_cookie =
+            Arc::new(SessionCookie::from_config(&settings.session));
+        let oidc_svc =
+            init_oidc(&settings, local_caches.clone(), session_svc.clone())
+                .await?;
+        let token_verifiers = init_token_verifiers(&settings, jwt_keys.clone())?;
+
+        // Also reachable through `AppState::from_globals`.
+        app_registry::set_health(health_svc.clone());
+        app_registry::set_auth(auth_svc.clone());
+        app_registry::set_mfa(mfa_svc.clone());
+        app_registry::set_lockout(lockout_svc.clone());
+        app_registry::set_accounts(account_svc.clone());
+        app_registry::set_authorization(authz_svc.clone());
+        app_registry::set_api_keys(api_key_svc.clone());
+        app_registry::set_sessions(session_svc.clone());
+        app_registry::set_tokens(token_svc.clone());
+        app_registry::set_token_denylist(token_denylist.clone());
+        app_registry::set_tracer(tracer.clone());
+
+        let reload_enabled = settings.reload.enabled;
+        let close_timeout = settings.server.request_timeout();
+        let state = AppState::new(
+            reloader.subscribe(),
+            health_svc,
+            auth_svc,
+            mfa_svc,
+            lockout_svc,
+            account_svc,
+            authz_svc,
+            api_key_svc,
+            oidc_svc,
+            session_svc,
+            session_cookie,
+            jwt_keys,
+            signing_key_svc,
+            token_svc,
+            token_denylist,
+            token_verifiers,
+            db_pool,
+            tracer,
+            local_caches,
+        );
+        let router = register_routers(state);
+
+        Ok(Self {
+            router,
+            routes,
+            server,
+            reloader: reload_enabled.then_some(reloader),
+            lifecycle,
+            tracer_provider: provider,
+            close_timeout,
+        })
+    }
+
+    /// Serve the full router until a shutdown signal is received and the
+    /// drain period has passed, then release the tracer and database.
+    pub async fn run(self) -> Result<(), Error> {
+        if let Some(reloader) = self.reloader {
+            reloader.spawn();
+        }
+        // The startup router is dropped once in-flight probes finish.
+        drop(self.routes.send_replace(self.router));
+        self.lifecycle.advance(LifecycleState::Ready);
+        let served = self.server.await;
+        self.lifecycle.advance(LifecycleState::Stopped);
+
+        release(self.tracer_provider, self.close_timeout).await;
+        served.context("server task failed")??;
+        Ok(())
+    }
+}
+
+/// Serve `initial` on `listener` until `shutdown` resolves. Sending a router
+/// through the returned channel replaces it for every later request.
+fn serve(
+    listener: TcpListener, initial: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> (watch::Sender<Router>, JoinHandle<io::Result<()>>) {
+    let (routes, current) = watch::channel(initial);
+    let app = Router::new().fallback_service(service_fn(move |req: Request| {
+        let router = current.borrow().clone();
+        router.oneshot(req)
+    }));
+    // Peer addresses feed per-IP login throttling.
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown)
+            .await
+    });
+    (routes, server)
+}
+
+/// Resolves once a shutdown signal arrived and the drain period has passed.
+/// Readiness reports 503 meanwhile while requests are still accepted.
+async fn drain(lifecycle: Arc<Lifecycle>, period: Duration) {
+    shutdown_signal().await;
+    lifecycle.advance(LifecycleState::Draining);
+    info!("Draining for {}s before shutting down", period.as_secs());
+    tokio::time::sleep(period).await;
+}
+
+/// Flush pending spans and wait for database connections to be returned.
+async fn release(provider: SdkTracerProvider, close_timeout: Duration) {
+    info!("Started flushing tracer provider");
+    match tokio::task::spawn_blocking(move || provider.shutdown()).await {
+        Ok(Ok(())) => info!("Completed flushing tracer provider"),
+        Ok(Err(e)) => error!("failed to flush tracer provider: {e}"),
+        Err(e) => error!("tracer provider shutdown panicked: {e}"),
+    }
+
+    info!("Waiting for database connections to be returned");
+    match database::wait_for_idle_connections(close_timeout).await {
+        0 => info!("All database connections returned"),
+        in_use => warn!(
+            "{in_use} database connections still in use after {}s",
+            close_timeout.as_secs()
+        ),
+    }
+}
+
+/// Discover the OIDC provider and build the flow service, if enabled.
+async fn init_oidc(
+    settings: &Settings, caches: Arc<CacheRegistry>,
+    sessions: Arc<dyn SessionTrait>,
+) -> Result<Option<Arc<dyn OidcAuthenticationTrait>>, Error> {
+    if !settings.oidc.enabled {
+        return Ok(None);
+    }
+
+    info!("Started initializing OIDC client");
+    let http = oidc::http_client()?;
+    let client = oidc::discover_client(&settings.oidc, &http).await?;
+
+    let svc: Arc<dyn OidcAuthenticationTrait> = Arc::new(OidcService::new(
+        client,
+        http,
+        caches,
+        settings.oidc.scope_list(),
+        sessions,
+    ));
+    app_registry::set_oidc_auth(svc.clone());
+    info!("Completed initializing OIDC client");
+    Ok(Some(svc))
+}
+
+/// Load the persisted signing key ring, creating the first key if needed,
+/// and keep it synchronized in the background.
+async fn init_signing_keys(
+    settings: &Settings, db: &'static DbPool, jwt_keys: Arc<JwtKeys>,
+) -> Result<Arc<dyn SigningKeyTrait>, Error> {
+    info!("Started initializing signing keys");
+    let svc: Arc<dyn SigningKeyTrait> = Arc::new(SigningKeyService::new(
+        db,
+        jwt_keys.clone(),
+        &settings.auth.jwt_secret,
+        &settings.auth.signing_keys,
+    ));
+    svc.sync().await.context("failed to load signing keys")?;
+    app_registry::set_jwt_keys(jwt_keys);
+    app_registry::set_signing_keys(svc.clone());
+
+    let background = svc.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(SIGNING_KEY_SYNC_INTERVAL);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = background.sync().await {
+                warn!("signing key sync failed: {e}");
+            }
+        }
+    });
+    info!("Completed initializing signing keys");
+    Ok(svc)
+}
+
+/// Dependencies probed by the readiness endpoint.
+fn health_checks(
+    settings: &Settings, db: &'static DbPool, caches: Arc<CacheRegistry>,
+) -> HealthCheckRegistry {
+    HealthCheckRegistry::new(settings.health.check_timeout())
+        .register(Arc::new(DatabaseHealthCheck::new(db)))
+        .register(Arc::new(CacheHealthCheck::new(caches)))
+        .register(Arc::new(OtlpHealthCheck::new(&settings.otel.uri)))
+}
+
+/// Bearer token verifiers: this service's own keys first, then the external
+/// issuer's JWKS when configured.
+fn init_token_verifiers(
+    settings: &Settings, jwt_keys: Arc<JwtKeys>,
+) -> Result<Vec<Arc<dyn AccessTokenVerifier>>, Error> {
+    let mut verifiers: Vec<Arc<dyn AccessTokenVerifier>> = vec![jwt_keys];
+    if settings.auth.jwks.enabled {
+        let http = oidc::http_client()?;
+        verifiers.push(Arc::new(JwksVerifier::new(&settings.auth.jwks, http)));
+    }
+    app_registry::set_token_verifiers(verifiers.clone());
+    Ok(verifiers)
+}
+
+fn namespace_configs(settings: &Settings) -> Vec<NamespaceConfig> {
+    settings
+        .cache
+        .namespaces
+        .iter()
+        .map(|ns| NamespaceConfig::new(&ns.name, ns.ttl(), ns.max_capacity))
+        .collect()
+}
+
+/// Rebuild cache namespaces whose TTL or capacity changed on reload.
+fn follow_cache_namespaces(rx: SettingsReceiver, caches: Arc<CacheRegistry>) {
+    let mut namespaces = project(rx, namespace_configs);
+    tokio::spawn(async move {
+        while namespaces.changed().await.is_ok() {
+            let configs = namespaces.borrow_and_update().clone();
+            for cfg in configs {
+                let name = cfg.name.clone();
+                if caches.reconfigure_namespace(cfg).await {
+                    info!("cache namespace {name} reconfigured");
+                }
+            }
+        }
+    });
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(unix)]
+    let interrupt = async {
+        signal::unix::signal(signal::unix::SignalKind::interrupt())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    #[cfg(not(unix))]
+    let interrupt = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+        _ = interrupt => {},
+    }
+}
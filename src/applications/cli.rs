@@ -0,0 +1,27 @@
+use anyhow::{Error, bail};
+
+/// Command selected from the process arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Start the HTTP server (default)
+    Serve,
+    /// Print the merged, redacted effective configuration and exit
+    PrintConfig,
+}
+
+impl Command {
+    pub fn from_args(
+        args: impl IntoIterator<Item = String>,
+    ) -> Result<Self, Error> {
+        let mut command = Command::Serve;
+        for arg in args {
+            match arg.as_str() {
+                "--print-config" => command = Command::PrintConfig,
+                other => {
+                    bail!("unknown argument {other:?}; supported: --print-config")
+                },
+            }
+        }
+        Ok(command)
+    }
+}
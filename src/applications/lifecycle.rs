@@ -0,0 +1,52 @@
+use crate::domains::health::LifecycleState;
+use log::info;
+use tokio::sync::watch;
+
+/// Publishes the process lifecycle to the health probes. Transitions only move
+/// forward: Starting → Ready → Draining → Stopped.
+pub struct Lifecycle {
+    tx: watch::Sender<LifecycleState>,
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        Self {
+            tx: watch::Sender::new(LifecycleState::Starting),
+        }
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<LifecycleState> {
+        self.tx.subscribe()
+    }
+
+    /// Move to `next`; returns `false` if that would go backwards.
+    pub fn advance(&self, next: LifecycleState) -> bool {
+        let advanced = self.tx.send_if_modified(|state| {
+            if next <= *state {
+                return false;
+            }
+            *state = next;
+            true
+        });
+        if advanced {
+            info!("lifecycle state changed to {next:?}");
+        }
+        advanced
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_only_moves_forward() {
+        let lifecycle = Lifecycle::new();
+        let rx = lifecycle.subscribe();
+
+        assert!(lifecycle.advance(LifecycleState::Ready));
+        assert!(lifecycle.advance(LifecycleState::Draining));
+        assert!(!lifecycle.advance(LifecycleState::Ready));
+        assert_eq!(*rx.borrow(), LifecycleState::Draining);
+    }
+}
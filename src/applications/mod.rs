@@ -1 +1,3 @@
-
+pub mod bootstrap;
+pub mod cli;
+pub mod lifecycle;
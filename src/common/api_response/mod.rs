@@ -1,6 +1,11 @@
+use crate::common::errors::{AppError, FieldViolation};
+use crate::common::i18n;
+use crate::common::negotiation::parse_quality_list;
 use crate::constants::http::{
-    CONTENT_TYPE_JSON, CONTENT_TYPE_PROBLEM_JSON, HEADER_X_REQUEST_ID,
+    CONTENT_TYPE_JSON, CONTENT_TYPE_PROBLEM_JSON, HEADER_ACCEPT,
+    HEADER_ACCEPT_LANGUAGE, HEADER_X_REQUEST_ID,
 };
+use crate::middlewares::request_id_mw::RequestScope;
 use axum::{
     Json,
     body::Body,
@@ -208,7 +213,25 @@ impl<T> Response<T> {
     where
         T: Serialize,
     {
-        build_json_response(status, Some(&self.request_id), &self)
+        let locale = RequestScope::current().map(|s| s.locale);
+        self.render(status, locale)
+    }
+
+    /// Translates `message` when it is the source-locale text of `code`.
+    fn render(mut self, status: StatusCode, locale: Option<&str>) -> AxumResponse
+    where
+        T: Serialize,
+    {
+        if let (Some(locale), Some(code), Some(message)) =
+            (locale, &self.code, &mut self.message)
+        {
+            *message = i18n::localize(locale, code, message).to_string();
+        }
+        let mut resp = build_json_response(status, Some(&self.request_id), &self);
+        if let Some(locale) = locale {
+            set_content_language(&mut resp, locale);
+        }
+        resp
     }
 }
 
@@ -217,10 +240,12 @@ where
     T: Serialize,
 {
     fn into_response(self) -> AxumResponse {
-        build_json_response(StatusCode::OK, Some(&self.request_id), &self)
+        self.with_status(StatusCode::OK)
     }
 }
 
+/// RFC 9457 problem document. `code`, `request_id` and `errors` are
+/// extension members.
 #[derive(Debug, Clone, Serialize)]
 pub struct ProblemJson<'a> {
     #[serde(rename = "type")]
@@ -228,32 +253,104 @@ pub struct ProblemJson<'a> {
     pub title: &'a str,
     pub status: u16,
     pub detail: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
     pub instance: &'a str,
+    pub code: &'a str,
+    pub request_id: &'a str,
+    #[serde(rename = "errors", skip_serializing_if = "<[_]>::is_empty")]
+    pub violations: &'a [FieldViolation],
     #[serde(rename = "server_time")]
     pub server_time: i64,
     #[serde(rename = "server_time_iso")]
     pub server_time_iso: String,
 }
 
-/// Build an Axum `problem+json` response (like WriteProblemJSON in Go).
-pub fn write_problem_json(
-    status: StatusCode, typ: &str, title: &str, detail: &str, instance: &str,
-) -> AxumResponse {
-    let payload = ProblemJson {
-        typ,
-        title,
-        status: status.as_u16(),
-        detail,
-        instance,
-        server_time: now_millis(),
-        server_time_iso: now_rfc3339_nano(),
+/// Wire format of error responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// The `Response` envelope
+    #[default]
+    Envelope,
+    /// `application/problem+json` (RFC 9457)
+    Problem,
+}
+
+impl ErrorFormat {
+    /// Problem documents when `Accept` ranks `application/problem+json` at
+    /// least as high as `application/json`, the envelope otherwise.
+    pub fn from_accept(accept: Option<&str>) -> Self {
+        let (mut problem_q, mut json_q) = (0.0_f32, 0.0_f32);
+        for (media_type, q) in parse_quality_list(accept) {
+            if media_type.eq_ignore_ascii_case(CONTENT_TYPE_PROBLEM_JSON) {
+                problem_q = problem_q.max(q);
+            } else if media_type.eq_ignore_ascii_case(CONTENT_TYPE_JSON) {
+                json_q = json_q.max(q);
+            }
+        }
+        match problem_q > 0.0 && problem_q >= json_q {
+            true => ErrorFormat::Problem,
+            false => ErrorFormat::Envelope,
+        }
+    }
+}
+
+/// The single renderer for error responses, from handlers and middleware
+/// alike, in the format negotiated for the request. The format depends on
+/// `Accept`, so shared caches must key on it.
+pub fn render_error(scope: &RequestScope, err: &AppError) -> AxumResponse {
+    let kind = err.kind();
+    let status = kind.status();
+    let code = kind.code().unwrap_or_default();
+
+    let mut resp = match scope.format {
+        ErrorFormat::Envelope => {
+            let mut resp =
+                Response::<Value>::new_with_request_id(scope.request_id.as_str())
+                    .with_code(code)
+                    .with_message(err.message());
+            if !err.details().is_empty() {
+                resp = resp.with_meta_kv("violations", json!(err.details()));
+            }
+            resp.render(status, Some(scope.locale))
+        },
+        ErrorFormat::Problem => {
+            let payload = ProblemJson {
+                typ: &kind.problem_type(),
+                title: i18n::localize(scope.locale, code, kind.message()),
+                status: status.as_u16(),
+                detail: i18n::localize(scope.locale, code, err.message()),
+                instance: &scope.path,
+                code,
+                request_id: &scope.request_id,
+                violations: err.details(),
+                server_time: now_millis(),
+                server_time_iso: now_rfc3339_nano(),
+            };
+            let mut resp =
+                build_json_response(status, Some(&scope.request_id), &payload);
+            resp.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(CONTENT_TYPE_PROBLEM_JSON),
+            );
+            set_content_language(&mut resp, scope.locale);
+            resp
+        },
     };
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        header::CONTENT_TYPE,
-        HeaderValue::from_static(CONTENT_TYPE_PROBLEM_JSON),
+    resp.headers_mut()
+        .append(header::VARY, HeaderValue::from_static(HEADER_ACCEPT));
+    resp
+}
+
+/// Marks a response as written in `locale`; shared caches must key it on
+/// `Accept-Language`.
+pub fn set_content_language(resp: &mut AxumResponse, locale: &str) {
+    if let Ok(v) = HeaderValue::from_str(locale) {
+        resp.headers_mut().insert(header::CONTENT_LANGUAGE, v);
+    }
+    resp.headers_mut().append(
+        header::VARY,
+        HeaderValue::from_static(HEADER_ACCEPT_LANGUAGE),
     );
-    (status, headers, Json(payload)).into_response()
 }
 
 fn build_json_response<T: Serialize>(
@@ -275,3 +372,63 @@ fn build_json_response<T: Serialize>(
 
     (status, headers, Json(payload)).into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::errors::CError;
+
+    #[test]
+    fn test_error_format_from_accept() {
+        let cases = [
+            (None, ErrorFormat::Envelope),
+            (Some("*/*"), ErrorFormat::Envelope),
+            (Some("application/json"), ErrorFormat::Envelope),
+            (Some("application/problem+json"), ErrorFormat::Problem),
+            (
+                Some("application/json;q=0.5, application/problem+json"),
+                ErrorFormat::Problem,
+            ),
+            (
+                Some("application/json, application/problem+json;q=0.9"),
+                ErrorFormat::Envelope,
+            ),
+            (Some("application/problem+json;q=0"), ErrorFormat::Envelope),
+        ];
+        for (accept, expected) in cases {
+            assert_eq!(ErrorFormat::from_accept(accept), expected, "{accept:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_problem_json() {
+        let scope = RequestScope {
+            request_id: "req-1".to_string(),
+            path: "/api/v1/admin/users/42/unlock".to_string(),
+            format: ErrorFormat::Problem,
+            locale: "es",
+        };
+        let res = render_error(&scope, &AppError::new(CError::UserNotFound));
+
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            CONTENT_TYPE_PROBLEM_JSON
+        );
+        assert_eq!(res.headers()[HEADER_X_REQUEST_ID], "req-1");
+        assert_eq!(res.headers()[header::CONTENT_LANGUAGE], "es");
+        let vary: Vec<_> = res.headers().get_all(header::VARY).iter().collect();
+        assert_eq!(vary, ["Accept-Language", "Accept"]);
+
+        let body = axum::body::to_bytes(res.into_body(), 4096).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["type"], "/api/v1/meta/errors#user-not-found");
+        assert_eq!(body["title"], "usuario no encontrado");
+        assert_eq!(body["detail"], "usuario no encontrado");
+        assert_eq!(body["status"], 404);
+        assert_eq!(body["instance"], "/api/v1/admin/users/42/unlock");
+        assert_eq!(body["code"], "400108");
+        assert_eq!(body["request_id"], "req-1");
+        assert!(body.get("errors").is_none());
+    }
+}
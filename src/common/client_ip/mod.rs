@@ -0,0 +1,132 @@
+//! Client address used for throttling. Forwarding headers are only believed
+//! when they were added by a proxy listed in `server.trusted_proxies`.
+
+use crate::constants::http::{HEADER_X_FORWARDED_FOR, HEADER_X_REAL_IP};
+use http::HeaderMap;
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<IpNet>);
+
+impl TrustedProxies {
+    /// Build from `server.trusted_proxies`; entries that do not parse were
+    /// already reported by config validation and are skipped.
+    pub fn new(entries: &[String]) -> Self {
+        Self(entries.iter().filter_map(|e| parse(e).ok()).collect())
+    }
+
+    fn contains(&self, ip: &IpAddr) -> bool {
+        self.0.iter().any(|net| net.contains(ip))
+    }
+
+    /// Address of the client behind `peer`. When `peer` is a trusted proxy,
+    /// `X-Forwarded-For` is walked from the right and the first hop not in
+    /// the list is the client; `X-Real-IP` is used when there is no
+    /// `X-Forwarded-For`.
+    pub fn client_ip(
+        &self, peer: Option<IpAddr>, headers: &HeaderMap,
+    ) -> Option<IpAddr> {
+        let peer = peer?;
+        if !self.contains(&peer) {
+            return Some(peer);
+        }
+
+        let hops: Vec<&str> = headers
+            .get_all(HEADER_X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .collect();
+        if hops.is_empty() {
+            return headers
+                .get(HEADER_X_REAL_IP)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok())
+                .or(Some(peer));
+        }
+
+        let mut client = peer;
+        for hop in hops.into_iter().rev() {
+            // A hop that does not parse was not written by a proxy we trust.
+            let Ok(ip) = hop.parse::<IpAddr>() else {
+                break;
+            };
+            client = ip;
+            if !self.contains(&ip) {
+                break;
+            }
+        }
+        Some(client)
+    }
+}
+
+/// An address (`10.0.0.1`) or a network (`10.0.0.0/8`).
+pub fn parse(entry: &str) -> Result<IpNet, String> {
+    let entry = entry.trim();
+    entry
+        .parse::<IpNet>()
+        .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("{entry:?} is not an IP address or CIDR network"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn ip(s: &str) -> Option<IpAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_untrusted_peer_ignores_forwarding_headers() {
+        let trusted = TrustedProxies::default();
+        let h =
+            headers(&[("x-forwarded-for", "1.1.1.1"), ("x-real-ip", "2.2.2.2")]);
+        assert_eq!(trusted.client_ip(ip("203.0.113.9"), &h), ip("203.0.113.9"));
+        assert_eq!(trusted.client_ip(None, &h), None);
+    }
+
+    #[test]
+    fn test_trusted_peer_takes_rightmost_untrusted_hop() {
+        let trusted =
+            TrustedProxies::new(&["10.0.0.0/8".to_string(), "192.0.2.7".into()]);
+        let peer = ip("10.0.0.2");
+
+        // The left-most value is whatever the client sent.
+        let h =
+            headers(&[("x-forwarded-for", "1.1.1.1, 203.0.113.9, 192.0.2.7")]);
+        assert_eq!(trusted.client_ip(peer, &h), ip("203.0.113.9"));
+
+        let h = headers(&[
+            ("x-forwarded-for", "1.1.1.1"),
+            ("x-forwarded-for", "203.0.113.9"),
+        ]);
+        assert_eq!(trusted.client_ip(peer, &h), ip("203.0.113.9"));
+
+        let h = headers(&[("x-forwarded-for", "garbage, 10.0.0.3")]);
+        assert_eq!(trusted.client_ip(peer, &h), ip("10.0.0.3"));
+
+        let h = headers(&[("x-real-ip", "203.0.113.9")]);
+        assert_eq!(trusted.client_ip(peer, &h), ip("203.0.113.9"));
+
+        assert_eq!(trusted.client_ip(peer, &HeaderMap::new()), peer);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(parse("10.0.0.0/8").is_ok());
+        assert!(parse("::1").is_ok());
+        assert!(parse("proxy.internal").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::common::api_response::render_error;
+use crate::common::errors::CError;
+use crate::infrastructures::database::DbError;
+use crate::middlewares::request_id_mw::RequestScope;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::response::{IntoResponse, Response as AxumResponse};
+use core::fmt;
+use log::{error, info};
+use serde::Serialize;
+
+pub type AppResult<T> = std::result::Result<T, AppError>;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// A rejected input field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldViolation {
+    pub field: String,
+    /// Name of the failed rule, e.g. `required` or `min_length`
+    pub rule: String,
+    pub message: String,
+}
+
+impl FieldViolation {
+    pub fn new(
+        field: impl Into<String>, rule: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            rule: rule.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// AppError wraps a `CError` kind with what is known about the failure.
+/// Only the kind, the message and the details reach the client; the context
+/// and the source chain are logged.
+pub struct AppError {
+    kind: CError,
+    message: Option<String>,
+    details: Vec<FieldViolation>,
+    context: Option<String>,
+    source: Option<BoxError>,
+}
+
+impl AppError {
+    pub fn new(kind: CError) -> Self {
+        Self {
+            kind,
+            message: None,
+            details: Vec::new(),
+            context: None,
+            source: None,
+        }
+    }
+
+    /// Client-facing message replacing the kind's default. Custom messages
+    /// bypass the locale bundles, so production paths use the catalog text.
+    #[cfg(test)]
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    pub fn with_details(mut self, details: Vec<FieldViolation>) -> Self {
+        self.details = details;
+        self
+    }
+
+    /// Internal-only description of what was being done, e.g. the ids involved.
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+
+    pub fn with_source(
+        mut self, source: impl std::error::Error + Send + Sync + 'static,
+    ) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    pub fn kind(&self) -> CError {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_deref().unwrap_or(self.kind.message())
+    }
+
+    pub fn details(&self) -> &[FieldViolation] {
+        &self.details
+    }
+
+    /// Message, context and every cause, for logs.
+    pub fn report(&self) -> String {
+        let mut out = format!(
+            "[{}] {}",
+            self.kind.code().unwrap_or_default(),
+            self.message()
+        );
+        if let Some(context) = &self.context {
+            out.push_str(&format!(" ({context})"));
+        }
+        let mut source = std::error::Error::source(self);
+        while let Some(cause) = source {
+            out.push_str(&format!(": {cause}"));
+            source = cause.source();
+        }
+        out
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl fmt::Debug for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.report())
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<CError> for AppError {
+    fn from(kind: CError) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl From<DbError> for AppError {
+    fn from(e: DbError) -> Self {
+        Self::new(CError::InvalidDatabaseClient).with_source(e)
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    /// Malformed input is the client's fault; I/O failures are ours.
+    fn from(e: serde_json::Error) -> Self {
+        let kind = match e.classify() {
+            serde_json::error::Category::Io => CError::GenericInternalServer,
+            _ => CError::GenericBadRequest,
+        };
+        Self::new(kind).with_source(e)
+    }
+}
+
+/// Extractor rejections are the client's fault. Their text echoes input and
+/// deserializer internals, so clients get the catalog message and the
+/// rejection is kept as the source, for logs.
+macro_rules! bad_request_from_rejection {
+    ($($rejection:ty),*) => {$(
+        impl From<$rejection> for AppError {
+            fn from(e: $rejection) -> Self {
+                Self::new(CError::GenericBadRequest).with_source(e)
+            }
+        }
+    )*};
+}
+
+bad_request_from_rejection!(JsonRejection, QueryRejection, PathRejection);
+
+impl From<config::ConfigError> for AppError {
+    fn from(e: config::ConfigError) -> Self {
+        Self::new(CError::GenericInternalServer).with_source(e)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> AxumResponse {
+        let scope = RequestScope::current_or_new();
+        match self.kind.status().is_server_error() {
+            true => {
+                error!("request {} failed: {}", scope.request_id, self.report())
+            },
+            false => {
+                info!("request {} rejected: {}", scope.request_id, self.report())
+            },
+        }
+        render_error(&scope, &self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Cause(&'static str, Option<Box<Cause>>);
+
+    impl fmt::Display for Cause {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.0)
+        }
+    }
+
+    impl std::error::Error for Cause {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            self.1.as_deref().map(|e| e as _)
+        }
+    }
+
+    #[test]
+    fn test_report_includes_context_and_chain() {
+        let err = AppError::new(CError::GenericInternalServer)
+            .with_context("loading user 42")
+            .with_source(Cause(
+                "query failed",
+                Some(Box::new(Cause("connection reset", None))),
+            ));
+
+        assert_eq!(err.to_string(), "internal server error");
+        assert_eq!(
+            err.report(),
+            "[500000] internal server error (loading user 42): query failed: \
+             connection reset"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_response_exposes_only_safe_parts() {
+        let res = AppError::new(CError::GenericBadRequest)
+            .with_message("invalid request body")
+            .with_details(vec![FieldViolation::new(
+                "email",
+                "required",
+                "email is required",
+            )])
+            .with_context("secret internal state")
+            .with_source(Cause("parser detail", None))
+            .into_response();
+
+        let body = axum::body::to_bytes(res.into_body(), 4096).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(!text.contains("secret internal state"));
+        assert!(!text.contains("parser detail"));
+
+        let body: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(body["code"], "400000");
+        assert_eq!(body["message"], "invalid request body");
+        assert_eq!(body["meta"]["violations"][0]["field"], "email");
+        assert_eq!(body["meta"]["violations"][0]["rule"], "required");
+    }
+}
@@ -0,0 +1,171 @@
+//! Published description of every `CError`, served at
+//! `/api/v1/meta/errors` and rendered into `docs/errors.md`.
+
+use crate::common::errors::{CError, Retry};
+use serde::Serialize;
+
+const _: () = assert!(
+    in_declaration_order(),
+    "CError::ALL must list every variant once, in declaration order"
+);
+const _: () = assert!(
+    CError::ALL.len() == CError::GenericServiceUnavailable.index() + 1,
+    "CError::ALL must end with the last variant"
+);
+const _: () = assert!(codes_unique(), "CError codes must be unique");
+const _: () = assert!(slugs_unique(), "CError slugs must be unique");
+const _: () = assert!(
+    codes_match_status(),
+    "CError codes must start with the class of their HTTP status"
+);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorDescriptor {
+    pub code: &'static str,
+    pub status: u16,
+    pub message: &'static str,
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub retry: Retry,
+}
+
+impl From<CError> for ErrorDescriptor {
+    fn from(e: CError) -> Self {
+        Self {
+            code: e.code().unwrap_or_default(),
+            status: e.status().as_u16(),
+            message: e.message(),
+            problem_type: e.problem_type(),
+            retry: e.retry(),
+        }
+    }
+}
+
+pub fn catalog() -> Vec<ErrorDescriptor> {
+    CError::ALL.into_iter().map(ErrorDescriptor::from).collect()
+}
+
+/// Markdown reference with one anchor per slug, so problem `type` fragments
+/// resolve in the rendered document too.
+#[cfg(test)]
+pub fn render_markdown() -> String {
+    let mut out = String::from(
+        "# Error codes\n\n\
+         Generated from `CError`; regenerate with \
+         `UPDATE_ERROR_DOCS=1 cargo test catalog`.\n\n\
+         | Code | Status | Type | Message | Retry |\n\
+         |------|--------|------|---------|-------|\n",
+    );
+    for e in CError::ALL {
+        out.push_str(&format!(
+            "| <a id=\"{slug}\"></a>`{code}` | {status} | `{slug}` | {message} | {retry} |\n",
+            slug = e.slug(),
+            code = e.code().unwrap_or_default(),
+            status = e.status().as_u16(),
+            message = e.message(),
+            retry = match e.retry() {
+                Retry::Never => "never",
+                Retry::Backoff => "with backoff",
+                Retry::AfterDelay => "after delay",
+            },
+        ));
+    }
+    out
+}
+
+const fn in_declaration_order() -> bool {
+    let mut i = 0;
+    while i < CError::ALL.len() {
+        if CError::ALL[i].index() != i {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn code_of(e: CError) -> &'static str {
+    match e.code() {
+        Some(code) => code,
+        None => "",
+    }
+}
+
+const fn codes_unique() -> bool {
+    let mut i = 0;
+    while i < CError::ALL.len() {
+        let mut j = i + 1;
+        while j < CError::ALL.len() {
+            if str_eq(code_of(CError::ALL[i]), code_of(CError::ALL[j])) {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn slugs_unique() -> bool {
+    let mut i = 0;
+    while i < CError::ALL.len() {
+        let mut j = i + 1;
+        while j < CError::ALL.len() {
+            if str_eq(CError::ALL[i].slug(), CError::ALL[j].slug()) {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn codes_match_status() -> bool {
+    let mut i = 0;
+    while i < CError::ALL.len() {
+        let code = code_of(CError::ALL[i]).as_bytes();
+        let class = (CError::ALL[i].status().as_u16() / 100) as u8;
+        if code.is_empty() || code[0] != b'0' + class || class < 4 {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCS_PATH: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/docs/errors.md");
+
+    #[test]
+    fn test_docs_are_current() {
+        let rendered = render_markdown();
+        if std::env::var_os("UPDATE_ERROR_DOCS").is_some() {
+            std::fs::write(DOCS_PATH, &rendered).unwrap();
+        }
+        let on_disk = std::fs::read_to_string(DOCS_PATH).unwrap_or_default();
+        assert_eq!(
+            on_disk, rendered,
+            "docs/errors.md is stale; rerun with UPDATE_ERROR_DOCS=1"
+        );
+    }
+}
@@ -1,11 +1,24 @@
 #![allow(non_camel_case_types)]
 #![allow(clippy::enum_variant_names)]
 
+mod app_error;
+pub mod catalog;
+
+pub use app_error::{AppError, AppResult, FieldViolation};
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response as AxumResponse};
 use core::fmt;
+use serde::Serialize;
 
 pub type Result<T> = std::result::Result<T, CError>;
 
-/// All application error kinds.
+/// Problem `type` URIs are this prefix followed by the error's slug.
+pub const PROBLEM_TYPE_BASE: &str = "/api/v1/meta/errors#";
+
+/// All application error kinds. New variants go at the end, into
+/// [`CError::ALL`] and [`CError::index`]; `catalog` rejects duplicate codes
+/// at compile time.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub enum CError {
@@ -17,9 +30,96 @@ pub enum CError {
     GenericPermission,
     GenericUnknownAPIPath,
     InvalidDatabaseClient,
+    // Authentication
+    InvalidOidcState,
+    OidcProviderFailure,
+    ApiKeyNotFound,
+    SessionNotFound,
+    InvalidMfaCode,
+    MfaNotEnrolled,
+    MfaAlreadyEnabled,
+    MfaRequired,
+    TooManyLoginAttempts,
+    UserNotFound,
+    InvalidAccountToken,
+    EmailAlreadyVerified,
+    WeakPassword,
+    // Lifecycle
+    GenericServiceUnavailable,
+}
+
+/// Whether a client may repeat a request that failed with a given error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Retry {
+    /// Repeating the same request fails the same way.
+    Never,
+    /// Transient; retry with exponential backoff.
+    Backoff,
+    /// Retry once the limit resets, honouring `Retry-After` when sent.
+    AfterDelay,
 }
 
 impl CError {
+    /// Every variant, in declaration order.
+    pub const ALL: [CError; 21] = [
+        // Generic
+        CError::GenericBadRequest,
+        CError::GenericInternalServer,
+        CError::GenericRequestTimedOut,
+        CError::GenericUnauthorized,
+        CError::GenericPermission,
+        CError::GenericUnknownAPIPath,
+        CError::InvalidDatabaseClient,
+        // Authentication
+        CError::InvalidOidcState,
+        CError::OidcProviderFailure,
+        CError::ApiKeyNotFound,
+        CError::SessionNotFound,
+        CError::InvalidMfaCode,
+        CError::MfaNotEnrolled,
+        CError::MfaAlreadyEnabled,
+        CError::MfaRequired,
+        CError::TooManyLoginAttempts,
+        CError::UserNotFound,
+        CError::InvalidAccountToken,
+        CError::EmailAlreadyVerified,
+        CError::WeakPassword,
+        // Lifecycle
+        CError::GenericServiceUnavailable,
+    ];
+
+    /// Position in declaration order. The match is exhaustive, so a new
+    /// variant does not compile until it is numbered here.
+    pub const fn index(self) -> usize {
+        match self {
+            // Generic
+            CError::GenericBadRequest => 0,
+            CError::GenericInternalServer => 1,
+            CError::GenericRequestTimedOut => 2,
+            CError::GenericUnauthorized => 3,
+            CError::GenericPermission => 4,
+            CError::GenericUnknownAPIPath => 5,
+            CError::InvalidDatabaseClient => 6,
+            // Authentication
+            CError::InvalidOidcState => 7,
+            CError::OidcProviderFailure => 8,
+            CError::ApiKeyNotFound => 9,
+            CError::SessionNotFound => 10,
+            CError::InvalidMfaCode => 11,
+            CError::MfaNotEnrolled => 12,
+            CError::MfaAlreadyEnabled => 13,
+            CError::MfaRequired => 14,
+            CError::TooManyLoginAttempts => 15,
+            CError::UserNotFound => 16,
+            CError::InvalidAccountToken => 17,
+            CError::EmailAlreadyVerified => 18,
+            CError::WeakPassword => 19,
+            // Lifecycle
+            CError::GenericServiceUnavailable => 20,
+        }
+    }
+
     pub const fn code(self) -> Option<&'static str> {
         Some(match self {
             // Generic
@@ -30,9 +130,116 @@ impl CError {
             CError::GenericInternalServer => "500000",
             CError::GenericRequestTimedOut => "500004",
             CError::InvalidDatabaseClient => "500005",
+            // Authentication
+            CError::InvalidOidcState => "400100",
+            CError::OidcProviderFailure => "500100",
+            CError::ApiKeyNotFound => "400101",
+            CError::SessionNotFound => "400102",
+            CError::InvalidMfaCode => "400103",
+            CError::MfaNotEnrolled => "400104",
+            CError::MfaAlreadyEnabled => "400105",
+            CError::MfaRequired => "400106",
+            CError::TooManyLoginAttempts => "400107",
+            CError::UserNotFound => "400108",
+            CError::InvalidAccountToken => "400109",
+            CError::EmailAlreadyVerified => "400110",
+            CError::WeakPassword => "400111",
+            // Lifecycle
+            CError::GenericServiceUnavailable => "500003",
         })
     }
 
+    /// Stable identifier used in problem `type` URIs.
+    pub const fn slug(self) -> &'static str {
+        match self {
+            // Generic
+            CError::GenericBadRequest => "bad-request",
+            CError::GenericInternalServer => "internal-server-error",
+            CError::GenericRequestTimedOut => "request-timed-out",
+            CError::GenericUnauthorized => "unauthorized",
+            CError::GenericPermission => "permission-denied",
+            CError::GenericUnknownAPIPath => "unknown-api-path",
+            CError::InvalidDatabaseClient => "database-unavailable",
+            // Authentication
+            CError::InvalidOidcState => "invalid-oidc-state",
+            CError::OidcProviderFailure => "oidc-provider-failure",
+            CError::ApiKeyNotFound => "api-key-not-found",
+            CError::SessionNotFound => "session-not-found",
+            CError::InvalidMfaCode => "invalid-mfa-code",
+            CError::MfaNotEnrolled => "mfa-not-enrolled",
+            CError::MfaAlreadyEnabled => "mfa-already-enabled",
+            CError::MfaRequired => "mfa-required",
+            CError::TooManyLoginAttempts => "too-many-login-attempts",
+            CError::UserNotFound => "user-not-found",
+            CError::InvalidAccountToken => "invalid-account-token",
+            CError::EmailAlreadyVerified => "email-already-verified",
+            CError::WeakPassword => "weak-password",
+            // Lifecycle
+            CError::GenericServiceUnavailable => "service-unavailable",
+        }
+    }
+
+    pub fn problem_type(self) -> String {
+        format!("{PROBLEM_TYPE_BASE}{}", self.slug())
+    }
+
+    /// HTTP status a handler responds with when it fails with this error.
+    pub const fn status(self) -> StatusCode {
+        match self {
+            CError::GenericBadRequest
+            | CError::InvalidOidcState
+            | CError::InvalidAccountToken
+            | CError::WeakPassword => StatusCode::BAD_REQUEST,
+            CError::GenericUnauthorized | CError::InvalidMfaCode => {
+                StatusCode::UNAUTHORIZED
+            },
+            CError::GenericPermission | CError::MfaRequired => {
+                StatusCode::FORBIDDEN
+            },
+            CError::GenericUnknownAPIPath
+            | CError::ApiKeyNotFound
+            | CError::SessionNotFound
+            | CError::MfaNotEnrolled
+            | CError::UserNotFound => StatusCode::NOT_FOUND,
+            CError::MfaAlreadyEnabled | CError::EmailAlreadyVerified => {
+                StatusCode::CONFLICT
+            },
+            CError::TooManyLoginAttempts => StatusCode::TOO_MANY_REQUESTS,
+            CError::GenericInternalServer | CError::InvalidDatabaseClient => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+            CError::OidcProviderFailure => StatusCode::BAD_GATEWAY,
+            CError::GenericRequestTimedOut => StatusCode::GATEWAY_TIMEOUT,
+            CError::GenericServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    pub const fn retry(self) -> Retry {
+        match self {
+            CError::GenericInternalServer
+            | CError::GenericRequestTimedOut
+            | CError::InvalidDatabaseClient
+            | CError::OidcProviderFailure
+            | CError::GenericServiceUnavailable => Retry::Backoff,
+            CError::TooManyLoginAttempts => Retry::AfterDelay,
+            CError::GenericBadRequest
+            | CError::GenericUnauthorized
+            | CError::GenericPermission
+            | CError::GenericUnknownAPIPath
+            | CError::InvalidOidcState
+            | CError::ApiKeyNotFound
+            | CError::SessionNotFound
+            | CError::InvalidMfaCode
+            | CError::MfaNotEnrolled
+            | CError::MfaAlreadyEnabled
+            | CError::MfaRequired
+            | CError::UserNotFound
+            | CError::InvalidAccountToken
+            | CError::EmailAlreadyVerified
+            | CError::WeakPassword => Retry::Never,
+        }
+    }
+
     /// Exact human-readable message text from your Go error declarations.
     pub const fn message(self) -> &'static str {
         match self {
@@ -44,6 +251,22 @@ impl CError {
             CError::GenericPermission => "invalid permission error",
             CError::GenericUnknownAPIPath => "unknown api path",
             CError::InvalidDatabaseClient => "invalid database client",
+            // Authentication
+            CError::InvalidOidcState => "invalid or expired oidc state",
+            CError::OidcProviderFailure => "oidc provider error",
+            CError::ApiKeyNotFound => "api key not found",
+            CError::SessionNotFound => "session not found",
+            CError::InvalidMfaCode => "invalid mfa code",
+            CError::MfaNotEnrolled => "mfa not enrolled",
+            CError::MfaAlreadyEnabled => "mfa already enabled",
+            CError::MfaRequired => "mfa is required for this account",
+            CError::TooManyLoginAttempts => "too many failed login attempts",
+            CError::UserNotFound => "user not found",
+            CError::InvalidAccountToken => "invalid or expired token",
+            CError::EmailAlreadyVerified => "email already verified",
+            CError::WeakPassword => "password does not meet requirements",
+            // Lifecycle
+            CError::GenericServiceUnavailable => "service unavailable",
         }
     }
 }
@@ -56,6 +279,14 @@ impl fmt::Display for CError {
 
 impl std::error::Error for CError {}
 
+/// Renders the standard envelope with the numeric code, so handlers can
+/// return `Result<_, CError>` and use `?`.
+impl IntoResponse for CError {
+    fn into_response(self) -> AxumResponse {
+        AppError::from(self).into_response()
+    }
+}
+
 pub fn code_for_option(err: Option<CError>) -> &'static str {
     match err {
         None => "OK",
@@ -69,3 +300,40 @@ pub fn message_for_option(err: Option<CError>) -> &'static str {
         Some(e) => e.message(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::http::HEADER_X_REQUEST_ID;
+    use crate::middlewares::request_id_mw::RequestIdLayer;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_error_renders_envelope_with_request_id() {
+        async fn failing() -> Result<&'static str> {
+            Err(CError::UserNotFound)
+        }
+        let app = Router::new().route("/", get(failing)).layer(RequestIdLayer);
+
+        let res = app
+            .oneshot(
+                Request::get("/")
+                    .header(HEADER_X_REQUEST_ID, "req-1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let body = axum::body::to_bytes(res.into_body(), 4096).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["request_id"], "req-1");
+        assert_eq!(body["code"], "400108");
+        assert_eq!(body["message"], "user not found");
+    }
+}
@@ -0,0 +1,124 @@
+//! Message bundles under `locales/`, keyed by envelope or error code, and
+//! `Accept-Language` negotiation.
+
+use crate::common::negotiation::parse_quality_list;
+use once_cell::sync::{Lazy, OnceCell};
+use std::collections::HashMap;
+
+/// Locale the code base is written in. Its bundle holds the exact texts
+/// handlers and `CError::message()` produce.
+pub const SOURCE_LOCALE: &str = "en";
+
+/// Every bundled locale.
+pub const LOCALES: &[&str] = &["en", "es", "fr"];
+
+type Bundle = HashMap<String, String>;
+
+static BUNDLES: Lazy<HashMap<&'static str, Bundle>> = Lazy::new(|| {
+    [
+        ("en", include_str!("../../../locales/en.json")),
+        ("es", include_str!("../../../locales/es.json")),
+        ("fr", include_str!("../../../locales/fr.json")),
+    ]
+    .into_iter()
+    .map(|(locale, raw)| {
+        let bundle = serde_json::from_str(raw)
+            .unwrap_or_else(|e| panic!("locales/{locale}.json: {e}"));
+        (locale, bundle)
+    })
+    .collect()
+});
+
+static DEFAULT_LOCALE: OnceCell<&'static str> = OnceCell::new();
+
+/// Install the locale used when `Accept-Language` matches none of
+/// `LOCALES`. First call wins; unknown locales are ignored.
+pub fn set_default_locale(locale: &str) {
+    if let Some(known) = LOCALES.iter().find(|l| l.eq_ignore_ascii_case(locale)) {
+        let _ = DEFAULT_LOCALE.set(known);
+    }
+}
+
+pub fn default_locale() -> &'static str {
+    DEFAULT_LOCALE.get().copied().unwrap_or(SOURCE_LOCALE)
+}
+
+/// Best bundled locale for an `Accept-Language` value. `es-MX` is served by
+/// `es`; ties keep header order.
+pub fn negotiate(accept_language: Option<&str>) -> &'static str {
+    for (tag, _) in parse_quality_list(accept_language) {
+        if tag == "*" {
+            return default_locale();
+        }
+        let primary = tag.split('-').next().unwrap_or_default();
+        if let Some(locale) =
+            LOCALES.iter().find(|l| l.eq_ignore_ascii_case(primary))
+        {
+            return locale;
+        }
+    }
+    default_locale()
+}
+
+/// Translation of `text` into `locale`. Only the source-locale text stored
+/// under `key` is translated, so messages a handler customized pass through
+/// unchanged.
+pub fn localize<'a>(locale: &str, key: &str, text: &'a str) -> &'a str {
+    let source = BUNDLES.get(SOURCE_LOCALE).and_then(|b| b.get(key));
+    if source.map(String::as_str) != Some(text) {
+        return text;
+    }
+    BUNDLES
+        .get(locale)
+        .and_then(|b| b.get(key))
+        .map(String::as_str)
+        .unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::errors::CError;
+
+    #[test]
+    fn test_bundles_are_complete() {
+        let source = &BUNDLES[SOURCE_LOCALE];
+        for e in CError::ALL {
+            let code = e.code().unwrap_or_default();
+            assert_eq!(source.get(code).map(String::as_str), Some(e.message()));
+        }
+        for locale in LOCALES {
+            let bundle = &BUNDLES[locale];
+            for key in source.keys() {
+                assert!(bundle.contains_key(key), "{locale} lacks {key}");
+            }
+            assert_eq!(bundle.len(), source.len(), "{locale} has stray keys");
+        }
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let cases = [
+            (None, "en"),
+            (Some("fr"), "fr"),
+            (Some("es-MX,es;q=0.9,en;q=0.8"), "es"),
+            (Some("de, fr;q=0.5"), "fr"),
+            (Some("en;q=0.2, FR-ca"), "fr"),
+            (Some("es;q=0, de"), "en"),
+            (Some("de"), "en"),
+        ];
+        for (accept, expected) in cases {
+            assert_eq!(negotiate(accept), expected, "{accept:?}");
+        }
+    }
+
+    #[test]
+    fn test_localize_keeps_custom_messages() {
+        assert_eq!(
+            localize("es", "400108", "user not found"),
+            "usuario no encontrado"
+        );
+        assert_eq!(localize("es", "400000", "Invalid input"), "Invalid input");
+        assert_eq!(localize("es", "UNKNOWN", "whatever"), "whatever");
+    }
+}
@@ -1,2 +1,6 @@
 pub mod api_response;
+pub mod client_ip;
 pub mod errors;
+pub mod i18n;
+pub mod negotiation;
+pub mod validation;
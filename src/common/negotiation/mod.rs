@@ -0,0 +1,48 @@
+//! Parsing of quality-weighted header values such as `Accept` and
+//! `Accept-Language`.
+
+/// Entries of a comma-separated header value with their `q` weight, best
+/// first; ties keep header order. Entries with `q=0` are refused by the
+/// client and left out. Parameters other than `q` are ignored.
+pub fn parse_quality_list(value: Option<&str>) -> Vec<(&str, f32)> {
+    let mut entries: Vec<(&str, f32)> = value
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|entry| {
+            let mut params = entry.split(';');
+            let item = params.next()?.trim();
+            let q = params
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!item.is_empty() && q > 0.0).then_some((item, q))
+        })
+        .collect();
+    entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quality_list() {
+        let cases: Vec<(Option<&str>, Vec<(&str, f32)>)> = vec![
+            (None, vec![]),
+            (Some(""), vec![]),
+            (Some("en"), vec![("en", 1.0)]),
+            (
+                Some("es-MX,es;q=0.9, en;q=0.8"),
+                vec![("es-MX", 1.0), ("es", 0.9), ("en", 0.8)],
+            ),
+            (Some("de;q=0.5, fr;q=0.5"), vec![("de", 0.5), ("fr", 0.5)]),
+            (Some("a;q=0.2, b"), vec![("b", 1.0), ("a", 0.2)]),
+            (Some("a;q=0, b;q=bad"), vec![("b", 1.0)]),
+            (Some("text/html;level=1;q=0.7"), vec![("text/html", 0.7)]),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(parse_quality_list(value), expected, "{value:?}");
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! Declarative input validation. Request types list their rules in
+//! `Validate::validate`; the `Validated*` extractors run them after
+//! deserializing.
+
+use crate::common::errors::{AppError, AppResult, CError, FieldViolation};
+use core::fmt::Display;
+use regex::Regex;
+use uuid::Uuid;
+
+pub trait Validate {
+    fn validate(&self, v: &mut Validator);
+}
+
+/// Path parameters that need no rules beyond parsing.
+impl Validate for Uuid {
+    fn validate(&self, _: &mut Validator) {}
+}
+
+impl Validate for String {
+    fn validate(&self, _: &mut Validator) {}
+}
+
+/// Collects the violations of one value, nested values included.
+#[derive(Debug, Default)]
+pub struct Validator {
+    prefix: String,
+    violations: Vec<FieldViolation>,
+}
+
+impl Validator {
+    /// `Ok` when `value` satisfies all its rules, otherwise a
+    /// `GenericBadRequest` listing every violation.
+    pub fn check(value: &impl Validate) -> AppResult<()> {
+        let mut v = Validator::default();
+        value.validate(&mut v);
+        match v.violations.is_empty() {
+            true => Ok(()),
+            false => Err(AppError::new(CError::GenericBadRequest)
+                .with_details(v.violations)),
+        }
+    }
+
+    pub fn field<'a, T: ?Sized>(
+        &'a mut self, name: &str, value: &'a T,
+    ) -> Field<'a, T> {
+        let name = self.path(name);
+        Field {
+            validator: self,
+            name,
+            value,
+            failed: false,
+        }
+    }
+
+    /// Validate a nested struct; its fields are reported as `name.field`.
+    #[cfg(test)]
+    pub fn nested(&mut self, name: &str, value: &impl Validate) {
+        let path = self.path(name);
+        let parent = std::mem::replace(&mut self.prefix, path);
+        value.validate(self);
+        self.prefix = parent;
+    }
+
+    /// Validate each element; fields are reported as `name[i].field`.
+    #[cfg(test)]
+    pub fn each<T: Validate>(&mut self, name: &str, values: &[T]) {
+        for (i, value) in values.iter().enumerate() {
+            self.nested(&format!("{name}[{i}]"), value);
+        }
+    }
+
+    fn path(&self, name: &str) -> String {
+        match self.prefix.is_empty() {
+            true => name.to_string(),
+            false => format!("{}.{name}", self.prefix),
+        }
+    }
+}
+
+/// Rules for one field. Only the first failing rule is reported.
+pub struct Field<'a, T: ?Sized> {
+    validator: &'a mut Validator,
+    name: String,
+    value: &'a T,
+    failed: bool,
+}
+
+impl<T: ?Sized> Field<'_, T> {
+    fn rule(mut self, rule: &str, ok: bool, message: impl Display) -> Self {
+        if !self.failed && !ok {
+            self.failed = true;
+            self.validator.violations.push(FieldViolation::new(
+                self.name.as_str(),
+                rule,
+                format!("{} {message}", self.name),
+            ));
+        }
+        self
+    }
+
+    /// Rule implemented by `f`, which returns why the value is rejected.
+    #[cfg(test)]
+    pub fn custom(
+        self, rule: &str, f: impl FnOnce(&T) -> Result<(), String>,
+    ) -> Self {
+        match self.failed {
+            true => self,
+            false => match f(self.value) {
+                Ok(()) => self,
+                Err(message) => self.rule(rule, false, message),
+            },
+        }
+    }
+}
+
+impl<T: AsRef<str> + ?Sized> Field<'_, T> {
+    fn text(&self) -> &str {
+        self.value.as_ref()
+    }
+
+    /// Not empty or whitespace only.
+    pub fn required(self) -> Self {
+        let ok = !self.text().trim().is_empty();
+        self.rule("required", ok, "is required")
+    }
+
+    /// Between `min` and `max` characters, inclusive.
+    pub fn length(self, min: usize, max: usize) -> Self {
+        let len = self.text().chars().count();
+        self.rule(
+            "length",
+            (min..=max).contains(&len),
+            format_args!("must be between {min} and {max} characters"),
+        )
+    }
+
+    pub fn email(self) -> Self {
+        let ok = self.text().parse::<lettre::Address>().is_ok();
+        self.rule("email", ok, "must be a valid email address")
+    }
+
+    /// Matches `pattern`; `expected` describes it, e.g. "6 digits".
+    pub fn matches(self, pattern: &Regex, expected: &str) -> Self {
+        let ok = pattern.is_match(self.text());
+        self.rule("pattern", ok, format_args!("must be {expected}"))
+    }
+}
+
+impl<T: PartialOrd + Display> Field<'_, T> {
+    /// Between `min` and `max`, inclusive.
+    pub fn range(self, min: T, max: T) -> Self {
+        let ok = *self.value >= min && *self.value <= max;
+        self.rule("range", ok, format_args!("must be between {min} and {max}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Address {
+        city: String,
+        zip: String,
+    }
+
+    impl Validate for Address {
+        fn validate(&self, v: &mut Validator) {
+            v.field("city", &self.city).required();
+            v.field("zip", &self.zip)
+                .matches(&Regex::new(r"^\d{5}$").unwrap(), "5 digits");
+        }
+    }
+
+    struct Signup {
+        name: String,
+        email: String,
+        age: u32,
+        address: Address,
+        previous: Vec<Address>,
+    }
+
+    impl Validate for Signup {
+        fn validate(&self, v: &mut Validator) {
+            v.field("name", &self.name).required().length(2, 32);
+            v.field("email", &self.email).email();
+            v.field("age", &self.age).range(18, 130);
+            v.nested("address", &self.address);
+            v.each("previous", &self.previous);
+        }
+    }
+
+    #[test]
+    fn test_reports_first_failure_per_field_with_paths() {
+        let signup = Signup {
+            name: "".to_string(),
+            email: "not-an-email".to_string(),
+            age: 12,
+            address: Address {
+                city: "Lyon".to_string(),
+                zip: "690".to_string(),
+            },
+            previous: vec![Address {
+                city: " ".to_string(),
+                zip: "75001".to_string(),
+            }],
+        };
+
+        let err = Validator::check(&signup).unwrap_err();
+        assert_eq!(err.kind(), CError::GenericBadRequest);
+        let got: Vec<_> = err
+            .details()
+            .iter()
+            .map(|d| (d.field.as_str(), d.rule.as_str()))
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("name", "required"),
+                ("email", "email"),
+                ("age", "range"),
+                ("address.zip", "pattern"),
+                ("previous[0].city", "required"),
+            ]
+        );
+        assert_eq!(err.details()[2].message, "age must be between 18 and 130");
+    }
+
+    #[test]
+    fn test_custom_rule() {
+        struct Range {
+            from: u32,
+            to: u32,
+        }
+
+        impl Validate for Range {
+            fn validate(&self, v: &mut Validator) {
+                v.field("to", &self.to).custom("after_from", |to| {
+                    match *to > self.from {
+                        true => Ok(()),
+                        false => Err("must be after from".to_string()),
+                    }
+                });
+            }
+        }
+
+        assert!(Validator::check(&Range { from: 1, to: 2 }).is_ok());
+        let err = Validator::check(&Range { from: 2, to: 2 }).unwrap_err();
+        assert_eq!(err.details()[0].rule, "after_from");
+    }
+}
@@ -1,31 +1,68 @@
+use crate::config::secrets::{self, Secret, SecretResolver};
 use once_cell::sync::Lazy;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use url::Url;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct HTTPConfig {
     /// Service name
     pub name: String,
+    /// Service's HTTP bind host
+    pub host: String,
     /// Service's HTTP port
     pub http_port: u16,
     /// Service's HTTP request timeout duration in seconds
     pub request_timeout_duration: u64,
     /// Service's log level
     pub log_level: String,
+    /// Mount the `/api/v1/admin` endpoints
+    pub admin_enabled: bool,
+    /// Origins allowed by CORS; `*` allows any origin
+    pub cors_allowed_origins: Vec<String>,
+    /// Seconds to keep serving after a shutdown signal while the readiness
+    /// probe reports 503, so load balancers stop routing here first
+    pub drain_seconds: u64,
+    /// Reverse proxies (addresses or CIDR networks) whose `X-Forwarded-For`
+    /// and `X-Real-IP` headers name the client; empty trusts none
+    pub trusted_proxies: Vec<String>,
 }
 
 impl Default for HTTPConfig {
     fn default() -> Self {
         Self {
             name: "example-service".to_string(),
+            host: "0.0.0.0".to_string(),
             http_port: 8080,
             request_timeout_duration: 10,
             log_level: "info".to_string(),
+            admin_enabled: false,
+            cors_allowed_origins: vec!["*".to_string()],
+            drain_seconds: 5,
+            trusted_proxies: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl HTTPConfig {
+    /// Socket address the HTTP listener binds to.
+    pub fn bind_address(&self) -> String {
+        format!("{}:{}", self.host, self.http_port)
+    }
+
+    /// Per-request timeout applied by `TimeoutLayer`.
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_duration)
+    }
+
+    pub fn drain_period(&self) -> Duration {
+        Duration::from_secs(self.drain_seconds)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct OtelConfig {
     pub(crate) uri: String,
@@ -39,10 +76,12 @@ impl Default for OtelConfig {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct DatabaseConfig {
     pub url: String,
+    /// Overrides the password in `url`; may be a secret reference
+    pub password: Secret,
     pub max_connections: u32,
 }
 
@@ -50,19 +89,40 @@ impl Default for DatabaseConfig {
     fn default() -> Self {
         Self {
             url: "".to_string(),
+            password: Secret::default(),
             max_connections: 0,
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl DatabaseConfig {
+    /// `url` with `password` applied, if one is set.
+    pub fn connection_url(&self) -> Secret {
+        if self.password.is_empty() {
+            return Secret::new(self.url.clone());
+        }
+        let Ok(mut url) = Url::parse(&self.url) else {
+            return Secret::new(self.url.clone());
+        };
+        match url.set_password(Some(self.password.expose())) {
+            Ok(()) => Secret::new(url.to_string()),
+            Err(()) => Secret::new(self.url.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct OIDCConfig {
     pub enabled: bool,
+    /// Issuer base URL used for discovery
+    pub issuer_url: String,
     pub client_id: String,
-    pub client_secret: String,
+    pub client_secret: Secret,
+    /// Space- or comma-separated scopes requested in addition to `openid`
     pub scopes: String,
     pub redirect_url: String,
+    /// Keycloak realm; when set the issuer is `{issuer_url}/realms/{realm}`
     pub realm: String,
 }
 
@@ -70,8 +130,9 @@ impl Default for OIDCConfig {
     fn default() -> Self {
         Self {
             enabled: false,
+            issuer_url: "".to_string(),
             client_id: "".to_string(),
-            client_secret: "".to_string(),
+            client_secret: Secret::default(),
             scopes: "".to_string(),
             redirect_url: "".to_string(),
             realm: "".to_string(),
@@ -79,7 +140,390 @@ impl Default for OIDCConfig {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+impl OIDCConfig {
+    /// Issuer URL the provider metadata is discovered from.
+    pub fn issuer(&self) -> String {
+        let base = self.issuer_url.trim_end_matches('/');
+        if self.realm.is_empty() {
+            base.to_string()
+        } else {
+            format!("{base}/realms/{}", self.realm)
+        }
+    }
+
+    /// Requested scopes, excluding the implicit `openid`.
+    pub fn scope_list(&self) -> Vec<String> {
+        self.scopes
+            .split([' ', ','])
+            .filter(|s| !s.is_empty() && *s != "openid")
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Seals the persisted token signing keys (at least 32 bytes); may be a
+    /// secret reference
+    pub jwt_secret: Secret,
+    /// `iss` claim of issued access tokens
+    pub issuer: String,
+    /// `aud` claim of issued access tokens
+    pub audience: String,
+    /// Access token lifetime in seconds
+    pub access_token_ttl_seconds: u64,
+    /// Refresh token lifetime in seconds
+    pub refresh_token_ttl_seconds: u64,
+    /// Revoked access token ids held until the tokens expire, unless
+    /// `cache.namespaces` declares `revoked_tokens`. Past this, revocations
+    /// are evicted early and those tokens are accepted again.
+    pub token_denylist_capacity: u64,
+    /// Rotation of the keys that sign issued access tokens
+    pub signing_keys: SigningKeysConfig,
+    /// Second factors for password logins
+    pub mfa: MfaConfig,
+    /// Throttling of failed password logins
+    pub lockout: LockoutConfig,
+    /// Password reset and email verification links
+    pub account_tokens: AccountTokensConfig,
+    /// Bearer tokens from an external issuer, verified against its JWKS
+    pub jwks: JwksConfig,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: Secret::default(),
+            issuer: "example-service".to_string(),
+            audience: "example-service".to_string(),
+            access_token_ttl_seconds: 900,
+            refresh_token_ttl_seconds: 30 * 24 * 3600,
+            token_denylist_capacity: 100_000,
+            signing_keys: SigningKeysConfig::default(),
+            mfa: MfaConfig::default(),
+            lockout: LockoutConfig::default(),
+            account_tokens: AccountTokensConfig::default(),
+            jwks: JwksConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SigningKeysConfig {
+    /// Age in seconds after which the active signing key is replaced
+    pub rotation_interval_seconds: u64,
+    /// How long a retired key stays published for verification, in seconds;
+    /// must cover the access token lifetime
+    pub retention_seconds: u64,
+}
+
+impl Default for SigningKeysConfig {
+    fn default() -> Self {
+        Self {
+            rotation_interval_seconds: 30 * 24 * 3600,
+            retention_seconds: 24 * 3600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MfaConfig {
+    /// Issuer shown in authenticator apps
+    pub issuer: String,
+    /// Users holding any of these roles must complete MFA to log in,
+    /// enrolling on first login if needed
+    pub required_roles: Vec<String>,
+    /// Wrong codes allowed per login before the MFA challenge is discarded
+    pub max_attempts: u32,
+}
+
+impl Default for MfaConfig {
+    fn default() -> Self {
+        Self {
+            issuer: "example-service".to_string(),
+            required_roles: vec!["admin".to_string()],
+            max_attempts: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockoutConfig {
+    /// Consecutive failed logins after which an account is locked
+    pub max_failures: u32,
+    /// How long a locked account stays locked, in seconds
+    pub lockout_seconds: u64,
+    /// Delay after the first failed login, doubled for each further failure
+    pub backoff_base_seconds: u64,
+    /// Upper bound of the delay between failed logins, in seconds
+    pub backoff_max_seconds: u64,
+    /// Failed logins from one client IP, across all accounts, before the IP
+    /// is refused until its window ends
+    pub ip_max_failures: u32,
+    /// Window in seconds over which failed logins per client IP are counted
+    pub ip_window_seconds: u64,
+}
+
+impl Default for LockoutConfig {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            lockout_seconds: 900,
+            backoff_base_seconds: 1,
+            backoff_max_seconds: 60,
+            ip_max_failures: 50,
+            ip_window_seconds: 900,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountTokensConfig {
+    /// Lifetime of a password reset token in seconds
+    pub password_reset_ttl_seconds: u64,
+    /// Lifetime of an email verification token in seconds
+    pub email_verification_ttl_seconds: u64,
+    /// Page that completes a password reset; the token is appended as the
+    /// `token` query parameter
+    pub password_reset_url: String,
+    /// Page that completes an email verification; the token is appended as
+    /// the `token` query parameter
+    pub email_verification_url: String,
+}
+
+impl Default for AccountTokensConfig {
+    fn default() -> Self {
+        Self {
+            password_reset_ttl_seconds: 3600,
+            email_verification_ttl_seconds: 24 * 3600,
+            password_reset_url: "http://localhost:3000/reset-password"
+                .to_string(),
+            email_verification_url: "http://localhost:3000/verify-email"
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JwksConfig {
+    pub enabled: bool,
+    /// JWKS document URL (e.g., `{issuer}/protocol/openid-connect/certs`)
+    pub url: String,
+    /// Expected `iss` claim
+    pub issuer: String,
+    /// Expected `aud` claim
+    pub audience: String,
+    /// How long fetched keys are trusted before re-fetching, in seconds
+    pub cache_ttl_seconds: u64,
+}
+
+impl Default for JwksConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: "".to_string(),
+            issuer: "".to_string(),
+            audience: "".to_string(),
+            cache_ttl_seconds: 300,
+        }
+    }
+}
+
+impl JwksConfig {
+    pub fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.cache_ttl_seconds)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheNamespaceConfig {
+    /// Namespace name (e.g., "state", "session")
+    pub name: String,
+    /// Entry time-to-live in seconds
+    pub ttl_seconds: u64,
+    pub max_capacity: u64,
+}
+
+impl CacheNamespaceConfig {
+    pub fn new(name: &str, ttl_seconds: u64, max_capacity: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            ttl_seconds,
+            max_capacity,
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_seconds)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub namespaces: Vec<CacheNamespaceConfig>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            namespaces: vec![
+                CacheNamespaceConfig::new("state", 120, 10_000),
+                CacheNamespaceConfig::new("session", 3600, 100_000),
+                CacheNamespaceConfig::new("permissions", 60, 10_000),
+            ],
+        }
+    }
+}
+
+impl CacheConfig {
+    pub fn namespace(&self, name: &str) -> Option<&CacheNamespaceConfig> {
+        self.namespaces.iter().find(|ns| ns.name == name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub cookie_name: String,
+    /// HMAC key used to sign the session cookie (at least 32 bytes); may be a
+    /// secret reference
+    pub cookie_secret: Secret,
+    /// `SameSite` attribute of the cookie: `Strict`, `Lax` or `None`
+    pub same_site: String,
+    /// Sessions end after this many seconds without a request (sliding)
+    pub idle_timeout_seconds: u64,
+    /// Sessions end this many seconds after login regardless of activity
+    pub absolute_timeout_seconds: u64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            cookie_name: "sid".to_string(),
+            cookie_secret: Secret::default(),
+            same_site: "Lax".to_string(),
+            idle_timeout_seconds: 1800,
+            absolute_timeout_seconds: 12 * 3600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReloadConfig {
+    /// Watch the TOML config files and republish changed settings
+    pub enabled: bool,
+    /// How often the config files are checked for changes, in seconds
+    pub interval_seconds: u64,
+}
+
+impl Default for ReloadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_seconds: 5,
+        }
+    }
+}
+
+impl ReloadConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_seconds)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MailConfig {
+    /// `smtp`, `file` (one `.eml` per message in `file_dir`) or `memory`
+    pub transport: String,
+    /// `From` address of outgoing mail, e.g. `Example <no-reply@example.com>`
+    pub from: String,
+    pub smtp: SmtpConfig,
+    /// Directory the `file` transport writes to
+    pub file_dir: String,
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        Self {
+            transport: "file".to_string(),
+            from: "no-reply@example.com".to_string(),
+            smtp: SmtpConfig::default(),
+            file_dir: "mail".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    /// `starttls`, `tls` or `none`
+    pub tls: String,
+    /// Leave empty for relays that do not authenticate
+    pub username: String,
+    pub password: Secret,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 587,
+            tls: "starttls".to_string(),
+            username: "".to_string(),
+            password: Secret::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// Time each readiness check may take before it counts as failed, in
+    /// milliseconds
+    pub check_timeout_ms: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            check_timeout_ms: 2000,
+        }
+    }
+}
+
+impl HealthConfig {
+    pub fn check_timeout(&self) -> Duration {
+        Duration::from_millis(self.check_timeout_ms)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct I18nConfig {
+    /// Locale served when `Accept-Language` matches no bundled locale
+    pub default_locale: String,
+}
+
+impl Default for I18nConfig {
+    fn default() -> Self {
+        Self {
+            default_locale: "en".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Settings {
     #[serde(default)]
     pub server: HTTPConfig,
@@ -87,40 +531,106 @@ pub struct Settings {
     pub otel: OtelConfig,
     #[serde(default)]
     pub database: DatabaseConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub reload: ReloadConfig,
+    #[serde(default)]
+    pub oidc: OIDCConfig,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub mail: MailConfig,
+    #[serde(default)]
+    pub health: HealthConfig,
+    #[serde(default)]
+    pub i18n: I18nConfig,
 }
 
 impl Settings {
     pub fn new() -> Result<Self, config::ConfigError> {
-        let _ = dotenvy::dotenv();
-
-        let profile = detect_profile();
-
-        let builder = config::Config::builder()
-            .add_source(
-                config::File::with_name(".env")
-                    .format(config::FileFormat::Ini)
-                    .required(false),
-            )
-            .add_source(
-                config::File::with_name("conf/config.toml")
-                    .format(config::FileFormat::Toml)
-                    .required(false),
-            )
-            .add_source(
-                config::File::with_name(&format!(".{profile}.env"))
-                    .format(config::FileFormat::Ini)
-                    .required(false),
-            )
-            .add_source(
-                config::File::with_name(&format!("conf/{profile}.config.toml"))
-                    .format(config::FileFormat::Toml)
-                    .required(false),
-            )
-            // Use double underscore to nest (e.g., SERVER__HTTP_PORT=8080)
-            .add_source(config::Environment::default().separator("__"));
-
-        builder.build()?.try_deserialize()
+        Self::from_config(build_config(&detect_profile())?)
     }
+
+    /// Deserialize a built config and resolve every secret reference through
+    /// the installed `SecretResolver`.
+    pub(crate) fn from_config(
+        config: config::Config,
+    ) -> Result<Self, config::ConfigError> {
+        let mut settings: Settings = config.try_deserialize()?;
+        settings.resolve_secrets(secrets::resolver())?;
+        Ok(settings)
+    }
+
+    fn resolve_secrets(
+        &mut self, resolver: &SecretResolver,
+    ) -> Result<(), config::ConfigError> {
+        let fields = [
+            ("database.password", &mut self.database.password),
+            ("oidc.client_secret", &mut self.oidc.client_secret),
+            ("auth.jwt_secret", &mut self.auth.jwt_secret),
+            ("session.cookie_secret", &mut self.session.cookie_secret),
+            ("mail.smtp.password", &mut self.mail.smtp.password),
+        ];
+        for (key, secret) in fields {
+            secret.resolve(resolver).map_err(|e| {
+                config::ConfigError::Message(format!("{key}: {e}"))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the layered configuration for `profile`. Later sources override
+/// earlier ones; every resolved value remembers its origin.
+pub(crate) fn build_config(
+    profile: &str,
+) -> Result<config::Config, config::ConfigError> {
+    let _ = dotenvy::dotenv();
+
+    let builder = config::Config::builder()
+        .add_source(
+            config::File::with_name(".env")
+                .format(config::FileFormat::Ini)
+                .required(false),
+        )
+        .add_source(
+            config::File::with_name("conf/config.toml")
+                .format(config::FileFormat::Toml)
+                .required(false),
+        )
+        .add_source(
+            config::File::with_name(&format!(".{profile}.env"))
+                .format(config::FileFormat::Ini)
+                .required(false),
+        )
+        .add_source(
+            config::File::with_name(&format!("conf/{profile}.config.toml"))
+                .format(config::FileFormat::Toml)
+                .required(false),
+        )
+        // Use double underscore to nest (e.g., SERVER__HTTP_PORT=8080)
+        .add_source(
+            config::Environment::default()
+                .separator("__")
+                .list_separator(",")
+                .with_list_parse_key("server.cors_allowed_origins")
+                .with_list_parse_key("server.trusted_proxies")
+                .with_list_parse_key("auth.mfa.required_roles")
+                .try_parsing(true),
+        );
+
+    builder.build()
+}
+
+/// TOML files watched for hot reload, in the same order as `build_config`.
+pub(crate) fn reloadable_files(profile: &str) -> Vec<PathBuf> {
+    vec![
+        PathBuf::from("conf/config.toml"),
+        PathBuf::from(format!("conf/{profile}.config.toml")),
+    ]
 }
 
 pub static SERVICE_CONFIGURATION: Lazy<Settings> =
@@ -128,7 +638,7 @@ pub static SERVICE_CONFIGURATION: Lazy<Settings> =
 
 /// Detects the active configuration profile.
 /// Precedence: APP_ENV > RUST_ENV > ENV > (debug=dev, release=prod)
-fn detect_profile() -> String {
+pub(crate) fn detect_profile() -> String {
     let from = |k: &str| std::env::var(k).ok().map(|v| v.to_ascii_lowercase());
 
     from("APP_ENV")
@@ -1 +1,5 @@
+pub mod effective;
 pub mod env_settings;
+pub mod reload;
+pub mod secrets;
+pub mod validation;
@@ -0,0 +1,281 @@
+use crate::config::effective::flatten_settings;
+use crate::config::env_settings::{
+    Settings, build_config, detect_profile, reloadable_files,
+};
+use anyhow::Error;
+use log::{error, info, warn};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// Receiving side of the published settings. `borrow()` always yields the
+/// latest applied value.
+pub type SettingsReceiver = watch::Receiver<Arc<Settings>>;
+
+/// Keys read once at startup. Changes are reported but not applied.
+pub const RESTART_REQUIRED_KEYS: &[&str] = &[
+    "server.name",
+    "server.host",
+    "server.http_port",
+    "server.admin_enabled",
+    "server.drain_seconds",
+    "server.trusted_proxies",
+];
+
+/// Sections whose every key is read once at startup.
+pub const RESTART_REQUIRED_SECTIONS: &[&str] = &[
+    "database", "otel", "oidc", "auth", "session", "reload", "mail", "health",
+    "i18n",
+];
+
+fn requires_restart(key: &str) -> bool {
+    RESTART_REQUIRED_KEYS.contains(&key)
+        || key.split_once('.').is_some_and(|(section, _)| {
+            RESTART_REQUIRED_SECTIONS.contains(&section)
+        })
+}
+
+/// Outcome of a single reload.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// Keys whose new values were published
+    pub applied: Vec<String>,
+    /// Keys that changed on disk but only take effect after a restart
+    pub requires_restart: Vec<String>,
+}
+
+/// ConfigReloader polls the TOML config files and republishes `Settings`
+/// through a watch channel when they change.
+///
+/// Polling (rather than inotify) keeps working when the files are swapped
+/// through symlinks, as Kubernetes does for mounted ConfigMaps.
+pub struct ConfigReloader {
+    profile: String,
+    files: Vec<PathBuf>,
+    tx: watch::Sender<Arc<Settings>>,
+}
+
+impl ConfigReloader {
+    pub fn new(initial: Arc<Settings>) -> Self {
+        let profile = detect_profile();
+        let files = reloadable_files(&profile);
+        let (tx, _) = watch::channel(initial);
+        Self { profile, files, tx }
+    }
+
+    pub fn subscribe(&self) -> SettingsReceiver {
+        self.tx.subscribe()
+    }
+
+    /// Re-read every source, validate the result and publish the values that
+    /// can change at runtime. Invalid configs are rejected as a whole.
+    pub fn reload(&self) -> Result<ReloadReport, Error> {
+        let next = Settings::from_config(build_config(&self.profile)?)?;
+        next.validate()?;
+
+        let current = self.tx.borrow().clone();
+        let (published, report) = plan_reload(&current, next);
+        if !report.applied.is_empty() {
+            self.tx.send_replace(Arc::new(published));
+        }
+        Ok(report)
+    }
+
+    /// Poll the watched files on `reload.interval_seconds` until the process
+    /// exits.
+    pub fn spawn(self) -> JoinHandle<()> {
+        let interval = self.tx.borrow().reload.interval();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut last = self.fingerprint();
+            loop {
+                ticker.tick().await;
+                let current = self.fingerprint();
+                if current == last {
+                    continue;
+                }
+                last = current;
+
+                match self.reload() {
+                    Ok(report) => log_report(&report),
+                    Err(e) => error!(
+                        "configuration reload rejected, keeping previous settings: {e}"
+                    ),
+                }
+            }
+        })
+    }
+
+    fn fingerprint(&self) -> Vec<Option<(SystemTime, u64)>> {
+        self.files
+            .iter()
+            .map(|p| {
+                std::fs::metadata(p)
+                    .ok()
+                    .and_then(|m| Some((m.modified().ok()?, m.len())))
+            })
+            .collect()
+    }
+}
+
+fn log_report(report: &ReloadReport) {
+    if !report.applied.is_empty() {
+        info!("configuration reloaded: {}", report.applied.join(", "));
+    }
+    if !report.requires_restart.is_empty() {
+        warn!(
+            "configuration changes require restart: {}",
+            report.requires_restart.join(", ")
+        );
+    }
+}
+
+/// Split the changes between `current` and `next` into applied and
+/// restart-only keys. The returned settings keep the current values for every
+/// restart-only key so subscribers always see what is actually running.
+fn plan_reload(current: &Settings, next: Settings) -> (Settings, ReloadReport) {
+    let before = flatten_settings(current);
+    let after = flatten_settings(&next);
+
+    let mut report = ReloadReport::default();
+    for (key, value) in &after {
+        if before.get(key) == Some(value) {
+            continue;
+        }
+        if requires_restart(key) {
+            report.requires_restart.push(key.clone());
+        } else {
+            report.applied.push(key.clone());
+        }
+    }
+
+    (restore_restart_required(current, next), report)
+}
+
+/// `next` with every restart-required key put back to its `current` value.
+/// Must cover the same keys as `RESTART_REQUIRED_KEYS` and
+/// `RESTART_REQUIRED_SECTIONS`.
+fn restore_restart_required(current: &Settings, mut next: Settings) -> Settings {
+    next.server.name = current.server.name.clone();
+    next.server.host = current.server.host.clone();
+    next.server.http_port = current.server.http_port;
+    next.server.admin_enabled = current.server.admin_enabled;
+    next.server.drain_seconds = current.server.drain_seconds;
+    next.server.trusted_proxies = current.server.trusted_proxies.clone();
+
+    next.database = current.database.clone();
+    next.otel = current.otel.clone();
+    next.oidc = current.oidc.clone();
+    next.auth = current.auth.clone();
+    next.session = current.session.clone();
+    next.reload = current.reload.clone();
+    next.mail = current.mail.clone();
+    next.health = current.health.clone();
+    next.i18n = current.i18n.clone();
+    next
+}
+
+/// Derive a channel carrying a single projection of the settings. It only
+/// notifies when the projected value actually changes.
+pub fn project<T, F>(mut rx: SettingsReceiver, f: F) -> watch::Receiver<T>
+where
+    T: PartialEq + Send + Sync + 'static,
+    F: Fn(&Settings) -> T + Send + 'static,
+{
+    let (tx, out) = watch::channel(f(&rx.borrow_and_update()));
+    tokio::spawn(async move {
+        while rx.changed().await.is_ok() {
+            let next = f(&rx.borrow_and_update());
+            tx.send_if_modified(|cur| {
+                if *cur == next {
+                    return false;
+                }
+                *cur = next;
+                true
+            });
+        }
+    });
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::secrets::Secret;
+
+    #[test]
+    fn test_plan_reload_holds_back_restart_only_keys() {
+        let current = Settings::default();
+        let mut next = Settings::default();
+        next.server.http_port = 9090;
+        next.server.log_level = "debug".to_string();
+        next.server.request_timeout_duration = 30;
+
+        let (published, report) = plan_reload(&current, next);
+
+        assert_eq!(
+            report.applied,
+            vec!["server.log_level", "server.request_timeout_duration"]
+        );
+        assert_eq!(report.requires_restart, vec!["server.http_port"]);
+        assert_eq!(published.server.http_port, current.server.http_port);
+        assert_eq!(published.server.log_level, "debug");
+    }
+
+    /// Give every leaf a different value of the same type.
+    fn perturb(value: &mut serde_json::Value) {
+        use serde_json::Value;
+        match value {
+            Value::Object(map) => map.values_mut().for_each(perturb),
+            Value::Bool(b) => *b = !*b,
+            Value::Number(n) => {
+                *value = match n.as_u64() {
+                    Some(u) => Value::from(u + 1),
+                    None => Value::from(n.as_f64().unwrap_or_default() + 1.0),
+                }
+            },
+            Value::String(s) => s.push('x'),
+            Value::Array(items) => items.push(Value::String("x".into())),
+            Value::Null => {},
+        }
+    }
+
+    #[test]
+    fn test_plan_reload_keeps_every_restart_required_key() {
+        let current = Settings::default();
+        let mut raw = serde_json::to_value(&current).unwrap();
+        for section in RESTART_REQUIRED_SECTIONS {
+            perturb(raw.get_mut(section).unwrap());
+        }
+        for key in RESTART_REQUIRED_KEYS {
+            let pointer = format!("/{}", key.replace('.', "/"));
+            perturb(raw.pointer_mut(&pointer).unwrap());
+        }
+        let next: Settings = serde_json::from_value(raw).unwrap();
+
+        let (published, report) = plan_reload(&current, next);
+
+        assert!(report.applied.is_empty(), "{:?}", report.applied);
+        assert!(!report.requires_restart.is_empty());
+        assert_eq!(flatten_settings(&published), flatten_settings(&current));
+    }
+
+    #[test]
+    fn test_plan_reload_keeps_secret_values() {
+        let mut current = Settings::default();
+        current.auth.jwt_secret = Secret::new("a".repeat(48));
+        current.database.password = Secret::new("db-password");
+        current.oidc.client_secret = Secret::new("oidc-secret");
+        let mut next = current.clone();
+        next.server.log_level = "debug".to_string();
+
+        let (published, report) = plan_reload(&current, next);
+
+        assert_eq!(report.applied, vec!["server.log_level"]);
+        assert_eq!(published.auth.jwt_secret.expose(), "a".repeat(48));
+        assert_eq!(published.database.password.expose(), "db-password");
+        assert_eq!(published.oidc.client_secret.expose(), "oidc-secret");
+    }
+}
@@ -0,0 +1,235 @@
+use core::fmt;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+const REDACTED: &str = "********";
+
+/// Secret is a string value that is zeroed on drop and never printed by
+/// `Debug`, `Display` or `Serialize`. Use `expose()` at the point of use.
+///
+/// Before resolution the inner value may be a reference such as
+/// `file:///run/secrets/db_password` or `env://OTHER_VAR`.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret(Zeroizing<String>);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(Zeroizing::new(value.into()))
+    }
+
+    pub fn expose(&self) -> &str {
+        self.0.as_str()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Replace a `scheme://` reference with the value it points to. Values
+    /// without a registered scheme are kept verbatim.
+    pub fn resolve(
+        &mut self, resolver: &SecretResolver,
+    ) -> Result<(), SecretError> {
+        if let Some(value) = resolver.resolve(self.expose())? {
+            *self = value;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({REDACTED})")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+        if self.is_empty() {
+            ser.serialize_str("")
+        } else {
+            ser.serialize_str(REDACTED)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        Ok(Self::new(String::deserialize(de)?))
+    }
+}
+
+/// Errors raised while resolving a secret reference.
+#[derive(Debug)]
+pub enum SecretError {
+    NotFound(String),
+    Unreadable { reference: String, reason: String },
+}
+
+impl fmt::Display for SecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretError::NotFound(r) => write!(f, "secret {r} not found"),
+            SecretError::Unreadable { reference, reason } => {
+                write!(f, "secret {reference} could not be read: {reason}")
+            },
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+/// SecretProvider resolves references for one URI scheme (e.g. `file`).
+/// `path` is the part after `scheme://`.
+pub trait SecretProvider: Send + Sync {
+    fn scheme(&self) -> &str;
+    fn fetch(&self, path: &str) -> Result<Secret, SecretError>;
+}
+
+/// Reads `file://<absolute path>`; a single trailing newline is dropped.
+pub struct FileSecretProvider;
+
+impl SecretProvider for FileSecretProvider {
+    fn scheme(&self) -> &str {
+        "file"
+    }
+
+    fn fetch(&self, path: &str) -> Result<Secret, SecretError> {
+        let raw = Zeroizing::new(std::fs::read_to_string(path).map_err(|e| {
+            SecretError::Unreadable {
+                reference: format!("file://{path}"),
+                reason: e.to_string(),
+            }
+        })?);
+        let value = raw.strip_suffix('\n').unwrap_or(&raw);
+        Ok(Secret::new(value.strip_suffix('\r').unwrap_or(value)))
+    }
+}
+
+/// Reads `env://<VARIABLE>` from the process environment.
+pub struct EnvSecretProvider;
+
+impl SecretProvider for EnvSecretProvider {
+    fn scheme(&self) -> &str {
+        "env"
+    }
+
+    fn fetch(&self, path: &str) -> Result<Secret, SecretError> {
+        std::env::var(path)
+            .map(Secret::new)
+            .map_err(|_| SecretError::NotFound(format!("env://{path}")))
+    }
+}
+
+/// SecretResolver dispatches references to the provider registered for their
+/// scheme. The default resolver understands `file://` and `env://`.
+pub struct SecretResolver {
+    providers: Vec<Box<dyn SecretProvider>>,
+}
+
+impl Default for SecretResolver {
+    fn default() -> Self {
+        Self::empty()
+            .with_provider(FileSecretProvider)
+            .with_provider(EnvSecretProvider)
+    }
+}
+
+impl SecretResolver {
+    pub fn empty() -> Self {
+        Self {
+            providers: Vec::new(),
+        }
+    }
+
+    /// Register a provider; it replaces any provider with the same scheme.
+    pub fn with_provider(
+        mut self, provider: impl SecretProvider + 'static,
+    ) -> Self {
+        self.providers.retain(|p| p.scheme() != provider.scheme());
+        self.providers.push(Box::new(provider));
+        self
+    }
+
+    /// Resolve `raw` if it is a reference to a registered scheme, otherwise
+    /// return `None`.
+    pub fn resolve(&self, raw: &str) -> Result<Option<Secret>, SecretError> {
+        let Some((scheme, path)) = raw.split_once("://") else {
+            return Ok(None);
+        };
+        match self.providers.iter().find(|p| p.scheme() == scheme) {
+            Some(provider) => provider.fetch(path).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+static RESOLVER: OnceCell<Arc<SecretResolver>> = OnceCell::new();
+
+/// The process-wide resolver, with the `file://` and `env://` providers.
+pub fn resolver() -> &'static Arc<SecretResolver> {
+    RESOLVER.get_or_init(|| Arc::new(SecretResolver::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Stand-in for a remote (Vault-style) secret store.
+    struct FakeVaultProvider(HashMap<&'static str, &'static str>);
+
+    impl SecretProvider for FakeVaultProvider {
+        fn scheme(&self) -> &str {
+            "vault"
+        }
+
+        fn fetch(&self, path: &str) -> Result<Secret, SecretError> {
+            self.0
+                .get(path)
+                .map(|v| Secret::new(*v))
+                .ok_or_else(|| SecretError::NotFound(format!("vault://{path}")))
+        }
+    }
+
+    #[test]
+    fn test_resolves_registered_schemes_only() {
+        let path = std::env::temp_dir().join("rs-axum-secret-test");
+        std::fs::write(&path, "from-file\n").unwrap();
+
+        let resolver = SecretResolver::default().with_provider(
+            FakeVaultProvider(HashMap::from([("kv/db#password", "from-vault")])),
+        );
+
+        let mut file = Secret::new(format!("file://{}", path.display()));
+        file.resolve(&resolver).unwrap();
+        assert_eq!(file.expose(), "from-file");
+
+        let mut vault = Secret::new("vault://kv/db#password");
+        vault.resolve(&resolver).unwrap();
+        assert_eq!(vault.expose(), "from-vault");
+
+        let mut plain = Secret::new("https://not-a-secret");
+        plain.resolve(&resolver).unwrap();
+        assert_eq!(plain.expose(), "https://not-a-secret");
+
+        assert!(Secret::new("vault://missing").resolve(&resolver).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_secret_is_never_printed() {
+        let secret = Secret::new("hunter2");
+        assert_eq!(format!("{secret:?}"), "Secret(********)");
+        assert_eq!(format!("{secret}"), "********");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"********\"");
+    }
+}
@@ -99,6 +99,7 @@ pub const HEADER_X_FORWARDED_FOR: &str = "X-Forwarded-For";
 pub const HEADER_X_FORWARDED_HOST: &str = "X-Forwarded-Host";
 pub const HEADER_X_FORWARDED_PROTO: &str = "X-Forwarded-Proto";
 pub const HEADER_X_FORWARDED_SCHEME: &str = "X-Forwarded-Scheme";
+pub const HEADER_X_REAL_IP: &str = "X-Real-IP";
 
 // Common X- headers
 pub const HEADER_X_API_KEY: &str = "X-API-Key";
@@ -0,0 +1,30 @@
+use crate::common;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+/// Self-service account recovery and email verification through single-use,
+/// time-limited links sent by mail.
+#[async_trait]
+pub trait AccountTrait: Send + Sync {
+    /// Mail a password reset link to every active account registered with
+    /// `email`. Unknown addresses succeed silently so callers cannot probe
+    /// for accounts.
+    async fn request_password_reset(
+        &self, email: String,
+    ) -> common::errors::Result<()>;
+
+    /// Set a new password with a reset token. Clears any login lockout and
+    /// revokes every refresh token of the account.
+    async fn reset_password(
+        &self, token: String, new_password: String,
+    ) -> common::errors::Result<()>;
+
+    /// Mail a verification link to the user's current email address.
+    async fn request_email_verification(
+        &self, user_id: Uuid,
+    ) -> common::errors::Result<()>;
+
+    /// Mark the address a verification token was sent to as verified, if it
+    /// is still the user's email.
+    async fn verify_email(&self, token: String) -> common::errors::Result<()>;
+}
@@ -0,0 +1,88 @@
+use crate::common;
+use crate::domains::authentication::AuthenticatedPrincipal;
+use crate::domains::listing::{
+    FilterField, FilterKind, FilterOp, ListParams, Listing, Page, Sort,
+    SortDirection,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// API key metadata; never includes the key itself.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApiKeyInfo {
+    pub id: Uuid,
+    pub name: String,
+    /// Public part of the key, safe to display
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    /// Subject of the principal that created the key
+    pub created_by: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A newly created key. `key` is returned only once and cannot be recovered.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub info: ApiKeyInfo,
+    pub key: String,
+}
+
+/// Sorts and filters accepted when listing keys. `status` is one of
+/// `active`, `revoked` or `expired`.
+pub struct ApiKeyListing;
+
+impl Listing for ApiKeyListing {
+    const SORTABLE: &'static [&'static str] =
+        &["created_at", "name", "expires_at", "last_used_at"];
+    const FILTERABLE: &'static [FilterField] = &[
+        FilterField {
+            name: "status",
+            ops: &[FilterOp::Eq],
+            kind: FilterKind::OneOf(&["active", "revoked", "expired"]),
+        },
+        FilterField {
+            name: "created_by",
+            ops: &[FilterOp::Eq, FilterOp::Ne],
+            kind: FilterKind::Text,
+        },
+        FilterField {
+            name: "created_at",
+            ops: &[FilterOp::Gt, FilterOp::Gte, FilterOp::Lt, FilterOp::Lte],
+            kind: FilterKind::Timestamp,
+        },
+    ];
+    const DEFAULT_SORT: Sort = Sort {
+        field: "created_at",
+        direction: SortDirection::Desc,
+    };
+}
+
+/// API keys for machine clients, sent in the `X-API-Key` header.
+#[async_trait]
+pub trait ApiKeyTrait: Send + Sync {
+    /// Create a key granting `scopes` directly as permissions.
+    async fn create(
+        &self, name: String, scopes: Vec<String>, expires_in: Option<u64>,
+        created_by: String,
+    ) -> common::errors::Result<CreatedApiKey>;
+
+    /// Keys including revoked and expired ones, see `ApiKeyListing`.
+    async fn list(
+        &self, params: &ListParams,
+    ) -> common::errors::Result<Page<ApiKeyInfo>>;
+
+    /// Revoke a key. Fails with `CError::ApiKeyNotFound` for unknown or
+    /// already revoked keys.
+    async fn revoke(&self, id: Uuid) -> common::errors::Result<()>;
+
+    /// Resolve a presented key into a principal, recording its use.
+    async fn authenticate(
+        &self, key: &str,
+    ) -> common::errors::Result<AuthenticatedPrincipal>;
+}
@@ -1,4 +1,135 @@
+use crate::common;
+use crate::domains::mfa::MfaChallenge;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 
+/// Tokens returned by a successful login or refresh.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuthTokens {
+    pub access_token: String,
+    pub token_type: String,
+    /// Access token lifetime in seconds
+    pub expires_in: u64,
+    pub refresh_token: String,
+    /// Refresh token lifetime in seconds
+    pub refresh_expires_in: u64,
+}
+
+/// Result of a correct username and password.
+#[derive(Debug, Clone)]
+pub enum LoginOutcome {
+    Tokens(AuthTokens),
+    /// A second factor must be verified before tokens are issued
+    MfaRequired(MfaChallenge),
+    /// Too many failed logins for the account or client IP; retry after
+    /// this many seconds
+    Throttled(u64),
+}
+
+/// Tokens issued after a completed MFA challenge.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MfaLogin {
+    #[serde(flatten)]
+    pub tokens: AuthTokens,
+    /// Returned once when the challenge completed a required enrollment
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recovery_codes: Vec<String>,
+}
+
+/// Caller identity established from a validated bearer token or API key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuthenticatedPrincipal {
+    /// `sub` claim
+    pub subject: String,
+    /// `iss` claim
+    pub issuer: String,
+    pub username: Option<String>,
+    /// Roles granted by the issuer; resolved to permissions on demand
+    pub roles: Vec<String>,
+    /// Permissions granted directly to the credential (API key scopes)
+    pub scopes: Vec<String>,
+    /// `jti` claim, API key id or session handle
+    pub token_id: Option<String>,
+    /// Expiry as a Unix timestamp; `None` for credentials that never expire
+    pub expires_at: Option<i64>,
+}
+
+/// Verifies bearer access tokens. Implementations check signature, `exp`,
+/// `iss` and `aud` and fail with `CError::GenericUnauthorized`.
 #[async_trait]
-pub trait AuthenticationTrait: Send + Sync {}
+pub trait AccessTokenVerifier: Send + Sync {
+    async fn verify(
+        &self, token: &str,
+    ) -> common::errors::Result<AuthenticatedPrincipal>;
+}
+
+/// Username/password authentication backed by the `users` table.
+#[async_trait]
+pub trait AuthenticationTrait: Send + Sync {
+    /// Verify credentials and issue an access/refresh token pair, or an MFA
+    /// challenge when the account has or requires a second factor. Failed
+    /// attempts are throttled per account and per `client_ip`.
+    async fn login(
+        &self, username: String, password: String, client_ip: String,
+    ) -> common::errors::Result<LoginOutcome>;
+
+    /// Complete a login halted by an MFA challenge. Wrong codes count as
+    /// failed logins for the account and `client_ip`.
+    async fn verify_mfa(
+        &self, mfa_token: String, code: String, client_ip: String,
+    ) -> common::errors::Result<MfaLogin>;
+
+    /// Revoke `refresh_token` and every token rotated from the same login.
+    /// Unknown tokens are ignored.
+    async fn logout(&self, refresh_token: String) -> common::errors::Result<()>;
+
+    /// Exchange a valid, unused `refresh_token` for a new token pair. Each
+    /// refresh token is single-use: presenting one again revokes its whole
+    /// family and records a security event.
+    async fn refresh(
+        &self, refresh_token: String,
+    ) -> common::errors::Result<AuthTokens>;
+}
+
+/// Where to send the browser to start an OIDC login. The `state` it carries
+/// is part of `url`.
+#[derive(Debug, Clone)]
+pub struct OidcAuthorization {
+    pub url: String,
+}
+
+/// Identity asserted by a validated ID token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+}
+
+/// Session established by a successful OIDC callback.
+#[derive(Debug, Clone)]
+pub struct OidcLogin {
+    pub session_id: String,
+    pub identity: OidcIdentity,
+    /// Seconds until the session expires if left idle
+    pub expires_in: u64,
+    /// Local path to send the browser back to, if one was requested
+    pub return_to: Option<String>,
+}
+
+/// OpenID Connect authorization-code flow with PKCE.
+#[async_trait]
+pub trait OidcAuthenticationTrait: Send + Sync {
+    /// Issue state, nonce and PKCE verifier and build the authorization URL.
+    async fn authorize(
+        &self, return_to: Option<String>,
+    ) -> common::errors::Result<OidcAuthorization>;
+
+    /// Exchange the authorization code, validate the ID token and establish a
+    /// session, replacing `current_session` if the browser already had one.
+    /// `state` is single-use.
+    async fn complete(
+        &self, code: String, state: String, current_session: Option<String>,
+    ) -> common::errors::Result<OidcLogin>;
+}
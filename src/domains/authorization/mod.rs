@@ -0,0 +1,13 @@
+use crate::common;
+use async_trait::async_trait;
+use std::collections::HashSet;
+
+/// Resolves roles to the permissions they grant.
+#[async_trait]
+pub trait AuthorizationTrait: Send + Sync {
+    /// Union of the permissions granted to `roles`. Unknown roles grant
+    /// nothing.
+    async fn permissions_for(
+        &self, roles: &[String],
+    ) -> common::errors::Result<HashSet<String>>;
+}
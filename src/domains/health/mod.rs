@@ -1,11 +1,78 @@
+use crate::common;
 use crate::common::api_response::BaseOutput;
 use async_trait::async_trait;
-use crate::common;
+use serde::Serialize;
+use std::time::Duration;
 
 #[async_trait]
 pub trait HealthcheckTrait: Send + Sync {
     async fn health(&self) -> common::errors::Result<BaseOutput>;
     async fn live(&self) -> common::errors::Result<BaseOutput>;
-    async fn ready(&self) -> common::errors::Result<BaseOutput>;
-    async fn started(&self) -> common::errors::Result<BaseOutput>;
+    async fn ready(&self) -> common::errors::Result<Readiness>;
+    async fn started(&self) -> common::errors::Result<LifecycleState>;
+}
+
+/// A dependency probed by the readiness endpoint.
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    /// Component name reported in the readiness body
+    fn name(&self) -> &'static str;
+
+    /// Whether a failure makes the service unready rather than degraded
+    fn critical(&self) -> bool {
+        true
+    }
+
+    /// Overrides the registry's default timeout for this check
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    async fn check(&self) -> anyhow::Result<()>;
+}
+
+/// Ordered from best to worst.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    #[default]
+    Up,
+    Degraded,
+    Down,
+}
+
+/// Result of a single `HealthCheck`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentHealth {
+    pub name: &'static str,
+    pub status: HealthStatus,
+    pub critical: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Overall readiness: `down` when a critical check failed or the service is
+/// not `ready`, `degraded` when only non-critical checks failed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Readiness {
+    pub status: HealthStatus,
+    pub state: LifecycleState,
+    pub components: Vec<ComponentHealth>,
+}
+
+/// Process lifecycle, in the order it is traversed.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LifecycleState {
+    #[default]
+    Starting,
+    Ready,
+    /// Shutdown was requested; in-flight and late requests are still served
+    Draining,
+    Stopped,
 }
@@ -0,0 +1,125 @@
+//! Paging, sorting and filtering of list endpoints, independent of how the
+//! parameters arrive or which store answers them.
+
+use crate::common::api_response::Pagination;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sort {
+    pub field: &'static str,
+    pub direction: SortDirection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl FilterOp {
+    pub fn parse(op: &str) -> Option<Self> {
+        Some(match op {
+            "eq" => FilterOp::Eq,
+            "ne" => FilterOp::Ne,
+            "gt" => FilterOp::Gt,
+            "gte" => FilterOp::Gte,
+            "lt" => FilterOp::Lt,
+            "lte" => FilterOp::Lte,
+            _ => return None,
+        })
+    }
+}
+
+/// What a filter value must parse as.
+#[derive(Debug, Clone, Copy)]
+pub enum FilterKind {
+    Text,
+    /// RFC 3339
+    Timestamp,
+    /// One of a fixed set of words
+    OneOf(&'static [&'static str]),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterValue {
+    Text(String),
+    Timestamp(DateTime<Utc>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    pub field: &'static str,
+    pub op: FilterOp,
+    pub value: FilterValue,
+}
+
+/// A field clients may filter on, and how.
+#[derive(Debug, Clone, Copy)]
+pub struct FilterField {
+    pub name: &'static str,
+    pub ops: &'static [FilterOp],
+    pub kind: FilterKind,
+}
+
+/// Largest `page` a request may ask for, so it fits `Pagination::page`.
+pub const MAX_PAGE: u32 = i32::MAX as u32;
+
+/// Allowlists of one listable resource. Only these fields reach the store.
+pub trait Listing {
+    const SORTABLE: &'static [&'static str];
+    const FILTERABLE: &'static [FilterField];
+    /// Applied when the request names no sort
+    const DEFAULT_SORT: Sort;
+    const DEFAULT_PER_PAGE: u32 = 20;
+    const MAX_PER_PAGE: u32 = 100;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListParams {
+    /// 1-based
+    pub page: u32,
+    pub per_page: u32,
+    pub sort: Vec<Sort>,
+    pub filters: Vec<Filter>,
+}
+
+impl ListParams {
+    pub fn offset(&self) -> i64 {
+        i64::from(self.page.saturating_sub(1)) * self.limit()
+    }
+
+    pub fn limit(&self) -> i64 {
+        i64::from(self.per_page)
+    }
+
+    pub fn total_pages(&self, total: i64) -> i64 {
+        (total + self.limit() - 1) / self.limit()
+    }
+
+    pub fn pagination(&self, total: i64) -> Pagination {
+        Pagination {
+            page: i32::try_from(self.page).unwrap_or(i32::MAX),
+            per_page: i32::try_from(self.per_page).unwrap_or(i32::MAX),
+            total,
+            total_pages: i32::try_from(self.total_pages(total))
+                .unwrap_or(i32::MAX),
+        }
+    }
+}
+
+/// One page of results and the number of matches across all pages.
+#[derive(Debug, Clone, Default)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+}
@@ -0,0 +1,27 @@
+use crate::common;
+use async_trait::async_trait;
+use uuid::Uuid;
+
+/// Throttling of failed logins, per account and per client IP. Wrong
+/// passwords and wrong MFA codes both count; a login only clears the count
+/// once every factor succeeded.
+#[async_trait]
+pub trait LockoutTrait: Send + Sync {
+    /// Seconds the caller must wait before `user_id` (when the username is
+    /// known) may attempt a login from `ip`, or `None` when allowed now.
+    async fn retry_after(
+        &self, user_id: Option<Uuid>, ip: &str,
+    ) -> common::errors::Result<Option<u64>>;
+
+    /// Count a wrong password or MFA code, locking the account once it reaches the
+    /// configured number of consecutive failures.
+    async fn record_failure(
+        &self, user_id: Option<Uuid>, ip: &str,
+    ) -> common::errors::Result<()>;
+
+    /// Clear the account's failure count after a completed login.
+    async fn record_success(&self, user_id: Uuid) -> common::errors::Result<()>;
+
+    /// Lift a lockout and clear the account's failure count.
+    async fn unlock(&self, user_id: Uuid) -> common::errors::Result<()>;
+}
@@ -0,0 +1,86 @@
+use crate::common;
+use crate::domains::authentication::AuthenticatedPrincipal;
+use async_trait::async_trait;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Returned by `/auth/login` instead of tokens when a second factor is due.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MfaChallenge {
+    /// Single-use handle for `/auth/mfa/verify`
+    pub mfa_token: String,
+    /// Accepted second factors
+    pub methods: Vec<String>,
+    /// The account must enroll TOTP (with `mfa_token`) before verifying
+    pub enrollment_required: bool,
+    /// Seconds until `mfa_token` expires
+    pub expires_in: u64,
+}
+
+/// Secret to load into an authenticator app.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TotpEnrollment {
+    /// Base32 secret for manual entry
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+/// A completed MFA challenge.
+#[derive(Debug, Clone)]
+pub struct MfaRedemption {
+    pub user_id: Uuid,
+    /// Issued when the challenge also completed a required enrollment
+    pub recovery_codes: Vec<String>,
+}
+
+/// Who is managing a TOTP enrollment.
+pub enum MfaSubject {
+    /// A caller authenticated with one of this service's access tokens
+    Principal(AuthenticatedPrincipal),
+    /// A login halted by a challenge with `enrollment_required`
+    Pending(String),
+}
+
+/// TOTP (RFC 6238) second factor with single-use recovery codes.
+#[async_trait]
+pub trait MfaTrait: Send + Sync {
+    /// Challenge issued after a correct password, or `None` when the user has
+    /// no second factor and none is required.
+    async fn challenge(
+        &self, user_id: Uuid,
+    ) -> common::errors::Result<Option<MfaChallenge>>;
+
+    /// Check a TOTP or recovery code against a challenge. The challenge is
+    /// discarded on success or after too many wrong codes.
+    async fn redeem(
+        &self, mfa_token: &str, code: &str,
+    ) -> common::errors::Result<MfaRedemption>;
+
+    /// User a still-valid challenge was issued to.
+    async fn challenged_user(&self, mfa_token: &str) -> Option<Uuid>;
+
+    /// Start (or restart) enrollment with a fresh secret. Fails with
+    /// `CError::MfaAlreadyEnabled` once enrollment was activated.
+    async fn enroll(
+        &self, subject: MfaSubject,
+    ) -> common::errors::Result<TotpEnrollment>;
+
+    /// Confirm enrollment with a current code and issue recovery codes.
+    async fn activate(
+        &self, user_id: Uuid, code: &str,
+    ) -> common::errors::Result<Vec<String>>;
+
+    /// Replace all recovery codes, given a current TOTP code.
+    async fn regenerate_recovery_codes(
+        &self, user_id: Uuid, code: &str,
+    ) -> common::errors::Result<Vec<String>>;
+
+    /// Remove the second factor, given a current TOTP code. Fails with
+    /// `CError::MfaRequired` for users whose roles mandate MFA.
+    async fn disable(
+        &self, user_id: Uuid, code: &str,
+    ) -> common::errors::Result<()>;
+
+    /// Local user id of a principal, if it was issued by this service.
+    fn local_user(&self, principal: &AuthenticatedPrincipal) -> Option<Uuid>;
+}
@@ -1,2 +1,11 @@
+pub mod account;
+pub mod api_key;
 pub mod authentication;
+pub mod authorization;
 pub mod health;
+pub mod listing;
+pub mod lockout;
+pub mod mfa;
+pub mod session;
+pub mod signing_key;
+pub mod token;
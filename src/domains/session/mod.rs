@@ -0,0 +1,93 @@
+use crate::common;
+use crate::domains::authentication::OidcIdentity;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// A browser session. `id` is the bearer secret carried (signed) in the
+/// session cookie and must never be returned by the API; use `handle()`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub subject: String,
+    pub issuer: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    pub created_at: i64,
+    pub last_seen_at: i64,
+    /// Idle expiry; pushed forward on every use, up to `absolute_expires_at`
+    pub expires_at: i64,
+    pub absolute_expires_at: i64,
+}
+
+impl Session {
+    /// Stable, non-secret identifier used to list and revoke sessions.
+    pub fn handle(&self) -> String {
+        let digest = Sha256::digest(self.id.as_bytes());
+        digest[..8].iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+/// Public view of a session.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SessionInfo {
+    pub handle: String,
+    pub created_at: i64,
+    pub last_seen_at: i64,
+    pub expires_at: i64,
+    /// Whether this is the session making the request
+    pub current: bool,
+}
+
+impl SessionInfo {
+    pub fn from_session(session: &Session, current: Option<&str>) -> Self {
+        Self {
+            handle: session.handle(),
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            expires_at: session.expires_at,
+            current: current == Some(session.id.as_str()),
+        }
+    }
+}
+
+/// Storage for sessions, indexed by id and by subject.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn save(&self, session: &Session) -> common::errors::Result<()>;
+
+    async fn load(&self, id: &str) -> common::errors::Result<Option<Session>>;
+
+    async fn remove(&self, id: &str) -> common::errors::Result<Option<Session>>;
+
+    /// Every stored session of `subject`.
+    async fn list(&self, subject: &str) -> common::errors::Result<Vec<Session>>;
+}
+
+/// Session lifecycle: creation on login, sliding expiry and revocation.
+#[async_trait]
+pub trait SessionTrait: Send + Sync {
+    /// Start a session for `identity`. The session in `replaces`, if any, is
+    /// ended so a login always rotates the session id.
+    async fn create(
+        &self, identity: &OidcIdentity, replaces: Option<&str>,
+    ) -> common::errors::Result<Session>;
+
+    /// Look up a live session and extend its idle expiry. Fails with
+    /// `CError::GenericUnauthorized` for unknown or expired sessions.
+    async fn resume(&self, id: &str) -> common::errors::Result<Session>;
+
+    /// End a session (logout). Unknown ids are ignored.
+    async fn end(&self, id: &str) -> common::errors::Result<()>;
+
+    /// Live sessions of `subject`, most recent first.
+    async fn list(&self, subject: &str) -> common::errors::Result<Vec<Session>>;
+
+    /// End the session of `subject` identified by `handle`.
+    async fn revoke(
+        &self, subject: &str, handle: &str,
+    ) -> common::errors::Result<()>;
+
+    /// End every session of `subject` and return how many were ended.
+    async fn revoke_all(&self, subject: &str) -> common::errors::Result<usize>;
+}
@@ -0,0 +1,29 @@
+use crate::common;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Signing key metadata; never includes private material.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SigningKeyInfo {
+    pub kid: String,
+    pub algorithm: String,
+    /// Whether this key signs newly issued tokens
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+/// Lifecycle of the keys that sign this service's access tokens.
+#[async_trait]
+pub trait SigningKeyTrait: Send + Sync {
+    /// Reload the key ring from storage, purge keys retired longer than the
+    /// retention period and rotate when the active key is due. Picks up
+    /// rotations made by other instances.
+    async fn sync(&self) -> common::errors::Result<()>;
+
+    /// Generate a new active key now, retiring the current one.
+    async fn rotate(&self) -> common::errors::Result<SigningKeyInfo>;
+
+    async fn list(&self) -> common::errors::Result<Vec<SigningKeyInfo>>;
+}
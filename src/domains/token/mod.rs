@@ -0,0 +1,55 @@
+use crate::common;
+use async_trait::async_trait;
+use serde::Serialize;
+
+/// RFC 7662 introspection response. Inactive tokens carry `active: false`
+/// only, whatever the reason.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TokenIntrospection {
+    pub active: bool,
+    /// Space-separated permissions granted directly to the token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// `access_token`, `refresh_token` or `api_key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+}
+
+impl TokenIntrospection {
+    pub fn inactive() -> Self {
+        Self::default()
+    }
+}
+
+/// Token introspection (RFC 7662) and revocation (RFC 7009) for the access
+/// tokens, refresh tokens and API keys this service accepts.
+#[async_trait]
+pub trait TokenIntrospectionTrait: Send + Sync {
+    /// Describe `token`. `token_type_hint` only changes the lookup order.
+    async fn introspect(
+        &self, token: &str, token_type_hint: Option<&str>,
+    ) -> common::errors::Result<TokenIntrospection>;
+
+    /// Revoke an access token issued by this service until it expires, or a
+    /// refresh token together with its family. Unknown tokens, and tokens
+    /// of other types, are ignored.
+    async fn revoke(
+        &self, token: &str, token_type_hint: Option<&str>,
+    ) -> common::errors::Result<()>;
+}
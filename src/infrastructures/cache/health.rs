@@ -0,0 +1,68 @@
+use crate::domains::health::HealthCheck;
+use crate::infrastructures::cache::local_cache::CacheRegistry;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+const HEALTH_NAMESPACE: &str = "health";
+/// Room for the probes of concurrent readiness and admin checks.
+const HEALTH_CAPACITY: u64 = 64;
+
+/// Writes and takes back an entry in a dedicated namespace. Each probe uses
+/// its own key, so concurrent checks cannot read each other's value.
+pub struct CacheHealthCheck {
+    caches: Arc<CacheRegistry>,
+}
+
+impl CacheHealthCheck {
+    pub fn new(caches: Arc<CacheRegistry>) -> Self {
+        caches.ensure_namespace(
+            HEALTH_NAMESPACE,
+            Duration::from_secs(60),
+            HEALTH_CAPACITY,
+        );
+        Self { caches }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for CacheHealthCheck {
+    fn name(&self) -> &'static str {
+        "cache"
+    }
+
+    async fn check(&self) -> anyhow::Result<()> {
+        let probe = Uuid::new_v4().to_string();
+        self.caches
+            .put_json(HEALTH_NAMESPACE, probe.clone(), &probe)
+            .await?;
+        match self
+            .caches
+            .take_json::<String>(HEALTH_NAMESPACE, &probe)
+            .await
+        {
+            Some(read) if read == probe => Ok(()),
+            _ => anyhow::bail!("cache did not return the value just written"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_concurrent_probes_do_not_collide() {
+        let check =
+            Arc::new(CacheHealthCheck::new(CacheRegistry::init().clone()));
+
+        let probes = (0..16).map(|_| {
+            let check = check.clone();
+            tokio::spawn(async move { check.check().await })
+        });
+        for probe in probes.collect::<Vec<_>>() {
+            probe.await.unwrap().unwrap();
+        }
+    }
+}
@@ -6,7 +6,7 @@ use serde_json::Value;
 use std::{sync::Arc, time::Duration};
 
 /// Configuration for a single cache namespace (e.g., "state", "session").
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct NamespaceConfig {
     pub name: String,
     pub ttl: Duration,
@@ -29,6 +29,8 @@ impl NamespaceConfig {
 pub struct CacheRegistry {
     // namespace -> Cache<String, serde_json::Value>
     caches: DashMap<String, Cache<String, Value>>,
+    // namespace -> config the cache was built with
+    configs: DashMap<String, NamespaceConfig>,
 }
 
 static REGISTRY: OnceCell<Arc<CacheRegistry>> = OnceCell::new();
@@ -39,6 +41,7 @@ impl CacheRegistry {
         REGISTRY.get_or_init(|| {
             Arc::new(Self {
                 caches: DashMap::new(),
+                configs: DashMap::new(),
             })
         })
     }
@@ -74,9 +77,37 @@ impl CacheRegistry {
             .max_capacity(max_capacity)
             .build();
         // race-safe insert if absent
+        self.configs.entry(ns.clone()).or_insert_with(|| {
+            NamespaceConfig::new(ns.clone(), ttl, max_capacity)
+        });
         let _ = self.caches.entry(ns).or_insert(cache);
     }
 
+    /// Apply a new TTL/capacity to a namespace, creating it if missing.
+    /// Moka caches are immutable once built, so a changed namespace is rebuilt
+    /// and its live entries are carried over (with a fresh TTL).
+    /// Returns `true` when the namespace was created or rebuilt.
+    pub async fn reconfigure_namespace(&self, cfg: NamespaceConfig) -> bool {
+        if self.configs.get(&cfg.name).is_some_and(|c| *c == cfg) {
+            return false;
+        }
+        let Some(old) = self.caches.get(&cfg.name).map(|c| c.clone()) else {
+            self.ensure_namespace(cfg.name, cfg.ttl, cfg.max_capacity);
+            return true;
+        };
+
+        let cache = Cache::builder()
+            .time_to_live(cfg.ttl)
+            .max_capacity(cfg.max_capacity)
+            .build();
+        for (k, v) in old.iter() {
+            cache.insert(k.as_ref().clone(), v).await;
+        }
+        self.caches.insert(cfg.name.clone(), cache);
+        self.configs.insert(cfg.name.clone(), cfg);
+        true
+    }
+
     /// Store any JSON-serializable value under `<ns>/<key>`.
     pub async fn put_json<V: Serialize>(
         &self, ns: &str, key: impl Into<String>, value: &V,
@@ -116,6 +147,18 @@ impl CacheRegistry {
         cache.get(key).await
     }
 
+    /// Remove a key and return its deserialized value, if any. Use this for
+    /// single-use entries so concurrent readers cannot both consume it.
+    pub async fn take_json<T: DeserializeOwned>(
+        &self, ns: &str, key: &str,
+    ) -> Option<T> {
+        let cache = self.caches.get(ns)?.clone();
+        cache
+            .remove(key)
+            .await
+            .and_then(|v| serde_json::from_value(v).ok())
+    }
+
     /// Remove a key (no error if namespace or key missing).
     pub async fn invalidate(&self, ns: &str, key: &str) {
         if let Some(cache) = self.caches.get(ns) {
@@ -123,6 +166,13 @@ impl CacheRegistry {
         }
     }
 
+    /// Approximate entry count and the capacity of a namespace.
+    pub fn occupancy(&self, ns: &str) -> Option<(u64, u64)> {
+        let entries = self.caches.get(ns)?.entry_count();
+        let capacity = self.configs.get(ns)?.max_capacity;
+        Some((entries, capacity))
+    }
+
     /// Check if a key exists (cheap get without deserializing).
     pub async fn contains_key(&self, ns: &str, key: &str) -> bool {
         if let Some(cache) = self.caches.get(ns) {
@@ -0,0 +1,130 @@
+use argon2::Argon2;
+use argon2::password_hash::{
+    PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng,
+};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rand::RngCore;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+/// Hash a password with Argon2id and a random salt (PHC string format).
+pub fn hash_password(
+    password: &str,
+) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+/// Check `password` against a PHC hash. Malformed hashes never verify.
+pub fn verify_password(password: &str, phc_hash: &str) -> bool {
+    PasswordHash::new(phc_hash).is_ok_and(|parsed| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok()
+    })
+}
+
+/// `len` random bytes from the OS CSPRNG, URL-safe base64 encoded.
+pub fn random_token(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Lowercase hex SHA-256 digest, used to store tokens at rest.
+pub fn sha256_hex(value: &str) -> String {
+    format!("{:x}", Sha256::digest(value.as_bytes()))
+}
+
+/// Compare two strings without short-circuiting on the first difference.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// AES-256-GCM key derived from a configured secret, for sealing data at
+/// rest.
+pub struct Sealer {
+    key: LessSafeKey,
+}
+
+impl Sealer {
+    pub fn new(secret: &str) -> Self {
+        let digest = Zeroizing::new(Sha256::digest(secret.as_bytes()));
+        let key = UnboundKey::new(&AES_256_GCM, digest.as_slice())
+            .expect("SHA-256 output is a valid AES-256 key");
+        Self {
+            key: LessSafeKey::new(key),
+        }
+    }
+
+    /// Encrypt `plaintext` bound to `aad`; the random nonce is prepended.
+    pub fn seal(&self, aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let mut sealed = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(aad),
+                &mut sealed,
+            )
+            .expect("AES-GCM sealing cannot fail for in-memory buffers");
+        [nonce.as_slice(), &sealed].concat()
+    }
+
+    /// Decrypt data produced by `seal` with the same `aad`. `None` when the
+    /// data was tampered with or sealed under another secret.
+    pub fn open(&self, aad: &[u8], sealed: &[u8]) -> Option<Zeroizing<Vec<u8>>> {
+        if sealed.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+        let mut buf = Zeroizing::new(ciphertext.to_vec());
+        let len = self
+            .key
+            .open_in_place(nonce, Aad::from(aad), &mut buf)
+            .ok()?
+            .len();
+        buf.truncate(len);
+        Some(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_roundtrip() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("battery staple", &hash));
+        assert!(!verify_password("correct horse", "not-a-hash"));
+    }
+
+    #[test]
+    fn test_seal_roundtrip() {
+        let sealer = Sealer::new("0123456789abcdef0123456789abcdef");
+        let sealed = sealer.seal(b"kid-1", b"private key");
+        assert_eq!(
+            sealer.open(b"kid-1", &sealed).unwrap().as_slice(),
+            b"private key"
+        );
+        assert!(sealer.open(b"kid-2", &sealed).is_none());
+        assert!(
+            Sealer::new("another secret")
+                .open(b"kid-1", &sealed)
+                .is_none()
+        );
+    }
+}
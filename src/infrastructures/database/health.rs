@@ -0,0 +1,28 @@
+use crate::domains::health::HealthCheck;
+use crate::infrastructures::database::DbPool;
+use async_trait::async_trait;
+use diesel_async::RunQueryDsl;
+
+/// Checks out a pooled connection and runs `SELECT 1`.
+pub struct DatabaseHealthCheck {
+    db: &'static DbPool,
+}
+
+impl DatabaseHealthCheck {
+    pub fn new(db: &'static DbPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for DatabaseHealthCheck {
+    fn name(&self) -> &'static str {
+        "database"
+    }
+
+    async fn check(&self) -> anyhow::Result<()> {
+        let mut conn = self.db.get().await?;
+        diesel::sql_query("SELECT 1").execute(&mut conn).await?;
+        Ok(())
+    }
+}
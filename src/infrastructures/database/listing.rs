@@ -0,0 +1,42 @@
+//! Translation of `ListParams` into Diesel query fragments. Macros rather
+//! than generic functions, since each column has its own expression types.
+
+/// `$query.filter($column <op> $value)` for a `FilterOp`.
+macro_rules! filter_by_op {
+    ($query:expr, $column:expr, $op:expr, $value:expr) => {{
+        use $crate::domains::listing::FilterOp;
+        match $op {
+            FilterOp::Eq => $query.filter($column.eq($value)),
+            FilterOp::Ne => $query.filter($column.ne($value)),
+            FilterOp::Gt => $query.filter($column.gt($value)),
+            FilterOp::Gte => $query.filter($column.ge($value)),
+            FilterOp::Lt => $query.filter($column.lt($value)),
+            FilterOp::Lte => $query.filter($column.le($value)),
+        }
+    }};
+}
+
+/// Order a boxed query by `$sort`, mapping each sort key to its column.
+/// Keys without a column are skipped; the `Listing` allowlist keeps them out.
+macro_rules! order_by_sort {
+    ($query:expr, $sort:expr, { $($key:literal => $column:expr),+ $(,)? }) => {{
+        use $crate::domains::listing::SortDirection;
+        let mut query = $query;
+        for sort in $sort {
+            query = match (sort.field, sort.direction) {
+                $(
+                    ($key, SortDirection::Asc) => {
+                        query.then_order_by($column.asc())
+                    },
+                    ($key, SortDirection::Desc) => {
+                        query.then_order_by($column.desc())
+                    },
+                )+
+                _ => query,
+            };
+        }
+        query
+    }};
+}
+
+pub(crate) use {filter_by_op, order_by_sort};
@@ -1,5 +1,12 @@
-use std::time::Duration;
+pub mod health;
+pub mod listing;
+pub mod models;
+pub mod schema;
+#[cfg(test)]
+pub mod testing;
+
 use once_cell::sync::OnceCell;
+use std::time::{Duration, Instant};
 
 use bb8::RunError;
 use diesel_async::{
@@ -47,6 +54,25 @@ pub async fn conn() -> Result<DbConn, DbError> {
     pool().get().await
 }
 
+/// Wait up to `timeout` for checked-out connections to be returned and
+/// report how many are still in use. This does not close anything: the pool
+/// lives in a `'static` cell that services borrow from, so its connections
+/// are only closed when the process exits.
+pub async fn wait_for_idle_connections(timeout: Duration) -> u32 {
+    let Some(pool) = DB_POOL.get() else {
+        return 0;
+    };
+    let deadline = Instant::now() + timeout;
+    loop {
+        let state = pool.state();
+        let in_use = state.connections - state.idle_connections;
+        if in_use == 0 || Instant::now() >= deadline {
+            return in_use;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,8 +90,7 @@ mod tests {
     #[tokio::test(flavor = "multi_thread")]
     #[serial]
     async fn test_new_database_connection() {
-        let url: &str =
-            "";
+        let url: &str = "";
         init_database_connection(&url, 5).await.expect("init ok");
 
         let mut c = conn().await.expect("get pooled connection");
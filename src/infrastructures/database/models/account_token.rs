@@ -0,0 +1,31 @@
+use crate::infrastructures::database::schema::account_tokens;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+/// A single-use password reset or email verification token; only the
+/// SHA-256 hash of the token is stored.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = account_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AccountToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub purpose: String,
+    pub token_hash: String,
+    pub email: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = account_tokens)]
+pub struct NewAccountToken<'a> {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub purpose: &'a str,
+    pub token_hash: &'a str,
+    pub email: &'a str,
+    pub expires_at: DateTime<Utc>,
+}
@@ -0,0 +1,34 @@
+use crate::infrastructures::database::schema::{mfa_recovery_codes, user_totp};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+/// A user's TOTP secret, stored sealed. Enrollment is pending until
+/// `enabled_at` is set.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = user_totp)]
+#[diesel(primary_key(user_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UserTotp {
+    pub user_id: Uuid,
+    pub secret: Vec<u8>,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = user_totp)]
+pub struct NewUserTotp<'a> {
+    pub user_id: Uuid,
+    pub secret: &'a [u8],
+}
+
+/// Single-use recovery code; only its SHA-256 hash is stored.
+#[derive(Debug, Insertable)]
+#[diesel(table_name = mfa_recovery_codes)]
+pub struct NewRecoveryCode {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub code_hash: String,
+}
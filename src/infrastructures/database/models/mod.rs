@@ -0,0 +1,7 @@
+pub mod account_token;
+pub mod api_key;
+pub mod mfa;
+pub mod refresh_token;
+pub mod security_event;
+pub mod signing_key;
+pub mod user;
@@ -0,0 +1,52 @@
+use crate::infrastructures::database::schema::{
+    refresh_token_families, refresh_tokens,
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+/// A refresh token; only the SHA-256 hash of the token is stored.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = refresh_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub family_id: Uuid,
+    /// Set when the token is exchanged; presenting it again is reuse
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = refresh_tokens)]
+pub struct NewRefreshToken<'a> {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: &'a str,
+    pub expires_at: DateTime<Utc>,
+    pub family_id: Uuid,
+}
+
+/// The chain of refresh tokens descending from one login. Revoking the family
+/// revokes every token in it.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable)]
+#[diesel(table_name = refresh_token_families)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RefreshTokenFamily {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub revoked_reason: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = refresh_token_families)]
+pub struct NewRefreshTokenFamily {
+    pub id: Uuid,
+    pub user_id: Uuid,
+}
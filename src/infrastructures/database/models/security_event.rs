@@ -0,0 +1,13 @@
+use crate::infrastructures::database::schema::security_events;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+/// Audit record of a security-relevant event (e.g. refresh token reuse).
+#[derive(Debug, Insertable)]
+#[diesel(table_name = security_events)]
+pub struct NewSecurityEvent<'a> {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub kind: &'a str,
+    pub detail: &'a str,
+}
@@ -0,0 +1,19 @@
+use crate::infrastructures::database::schema::signing_keys;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+/// A token signing key. The private key is stored sealed; the public
+/// components are the base64url RSA modulus and exponent.
+#[derive(Debug, Clone, Queryable, Selectable, Identifiable, Insertable)]
+#[diesel(table_name = signing_keys)]
+#[diesel(primary_key(kid))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SigningKeyRow {
+    pub kid: String,
+    pub algorithm: String,
+    pub private_key: Vec<u8>,
+    pub public_n: String,
+    pub public_e: String,
+    pub created_at: DateTime<Utc>,
+    pub retired_at: Option<DateTime<Utc>>,
+}
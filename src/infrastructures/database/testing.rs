@@ -0,0 +1,53 @@
+//! Database helpers for tests. Tests that need Postgres are `#[ignore]`d by
+//! default; run them with `cargo test -- --ignored` and `TEST_DATABASE_URL`
+//! pointing at a database migrated with `migrations/`.
+
+use crate::infrastructures::crypto::hash_password;
+use crate::infrastructures::database::schema::users;
+use crate::infrastructures::database::{DbConn, DbPool};
+use diesel::prelude::*;
+use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+use diesel_async::pooled_connection::bb8::Pool;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+/// A pool for one test. Connections are driven by the runtime that opened
+/// them, so tests do not share a pool.
+pub async fn test_pool() -> &'static DbPool {
+    let url = std::env::var("TEST_DATABASE_URL")
+        .expect("TEST_DATABASE_URL must name a migrated database");
+    let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(url);
+    let pool = Pool::builder()
+        .max_size(2)
+        .build(manager)
+        .await
+        .expect("connect to TEST_DATABASE_URL");
+    Box::leak(Box::new(pool))
+}
+
+/// Insert an active user with a unique username and `password`.
+pub async fn insert_user(
+    conn: &mut DbConn, email: Option<&str>, password: &str,
+) -> Uuid {
+    let id = Uuid::new_v4();
+    diesel::insert_into(users::table)
+        .values((
+            users::id.eq(id),
+            users::username.eq(format!("user-{id}")),
+            users::email.eq(email),
+            users::password_hash.eq(hash_password(password).unwrap()),
+        ))
+        .execute(conn)
+        .await
+        .expect("insert user");
+    id
+}
+
+/// A pool that only connects when used, for tests whose code paths never
+/// reach the database.
+pub fn unconnected_pool() -> &'static DbPool {
+    let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(
+        "postgres://unused@127.0.0.1:1/unused",
+    );
+    Box::leak(Box::new(Pool::builder().build_unchecked(manager)))
+}
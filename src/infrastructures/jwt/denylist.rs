@@ -0,0 +1,76 @@
+use crate::infrastructures::cache::local_cache::CacheRegistry;
+use chrono::Utc;
+use log::warn;
+use std::sync::Arc;
+use std::time::Duration;
+
+const REVOKED_NAMESPACE: &str = "revoked_tokens";
+
+/// Ids (`jti`) of revoked access tokens, held in the "revoked_tokens" cache
+/// namespace until the tokens expire. The cache is local, so a revocation
+/// only takes effect on the instance that handled it.
+///
+/// The namespace is bounded: once it is full, the cache evicts entries before
+/// they expire and the evicted tokens are accepted again until their own
+/// expiry. Each revocation made while the namespace is full is logged.
+pub struct TokenDenylist {
+    caches: Arc<CacheRegistry>,
+}
+
+impl TokenDenylist {
+    /// `ttl` must cover the longest access token lifetime. `capacity` bounds
+    /// the namespace unless the configuration already declared it.
+    pub fn new(caches: Arc<CacheRegistry>, ttl: Duration, capacity: u64) -> Self {
+        caches.ensure_namespace(REVOKED_NAMESPACE, ttl, capacity);
+        Self { caches }
+    }
+
+    /// Deny the token `jti` until `expires_at` (Unix timestamp).
+    pub async fn revoke(&self, jti: &str, expires_at: i64) {
+        if expires_at <= Utc::now().timestamp() {
+            return;
+        }
+        if let Some((entries, capacity)) =
+            self.caches.occupancy(REVOKED_NAMESPACE)
+            && entries >= capacity
+        {
+            warn!(
+                "token denylist is full ({capacity} entries); older \
+                 revocations may be evicted and their tokens accepted again"
+            );
+        }
+        let _ = self
+            .caches
+            .put_json(REVOKED_NAMESPACE, jti, &expires_at)
+            .await;
+    }
+
+    pub async fn is_revoked(&self, jti: &str) -> bool {
+        self.caches
+            .get_json::<i64>(REVOKED_NAMESPACE, jti)
+            .await
+            .is_some_and(|expires_at| expires_at > Utc::now().timestamp())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_revoked_until_expiry() {
+        let denylist = TokenDenylist::new(
+            CacheRegistry::init().clone(),
+            Duration::from_secs(60),
+            1_000,
+        );
+        let now = Utc::now().timestamp();
+
+        denylist.revoke("live", now + 60).await;
+        denylist.revoke("expired", now - 1).await;
+
+        assert!(denylist.is_revoked("live").await);
+        assert!(!denylist.is_revoked("expired").await);
+        assert!(!denylist.is_revoked("unknown").await);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::common;
+use crate::common::errors::CError;
+use crate::config::env_settings::JwksConfig;
+use crate::domains::authentication::{
+    AccessTokenVerifier, AuthenticatedPrincipal,
+};
+use async_trait::async_trait;
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, decode_header};
+use log::{debug, warn};
+use openidconnect::reqwest;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Minimum spacing between fetches triggered by an unknown `kid`, so forged
+/// tokens cannot be used to hammer the issuer.
+const MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Claims read from externally issued access tokens.
+#[derive(Debug, Deserialize)]
+struct ExternalClaims {
+    sub: String,
+    iss: String,
+    exp: i64,
+    #[serde(default)]
+    jti: Option<String>,
+    #[serde(default)]
+    preferred_username: Option<String>,
+    #[serde(default)]
+    roles: Vec<String>,
+    /// Keycloak puts realm roles here
+    #[serde(default)]
+    realm_access: Option<RealmAccess>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RealmAccess {
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+struct CachedKeys {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+/// JwksVerifier validates tokens signed by an external issuer against its
+/// published JWKS. Keys are cached for `cache_ttl_seconds` and re-fetched
+/// early when a token names an unknown `kid` (key rotation).
+pub struct JwksVerifier {
+    url: String,
+    issuer: String,
+    audience: String,
+    ttl: Duration,
+    http: reqwest::Client,
+    cache: RwLock<Option<CachedKeys>>,
+}
+
+impl JwksVerifier {
+    pub fn new(cfg: &JwksConfig, http: reqwest::Client) -> Self {
+        Self {
+            url: cfg.url.clone(),
+            issuer: cfg.issuer.clone(),
+            audience: cfg.audience.clone(),
+            ttl: cfg.cache_ttl(),
+            http,
+            cache: RwLock::new(None),
+        }
+    }
+
+    async fn fetch(&self) -> Result<JwkSet, String> {
+        let body = self
+            .http
+            .get(&self.url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("failed to fetch JWKS from {}: {e}", self.url))?
+            .bytes()
+            .await
+            .map_err(|e| format!("failed to read JWKS from {}: {e}", self.url))?;
+        serde_json::from_slice(&body)
+            .map_err(|e| format!("invalid JWKS document at {}: {e}", self.url))
+    }
+
+    /// Decoding key for `kid`, fetching the JWKS when the cache is stale or
+    /// does not know the key.
+    async fn key_for(&self, kid: Option<&str>) -> Result<DecodingKey, String> {
+        {
+            let cache = self.cache.read().await;
+            if let Some(cached) = cache.as_ref() {
+                let age = cached.fetched_at.elapsed();
+                if age < self.ttl {
+                    if let Some(jwk) = find_key(&cached.keys, kid) {
+                        return decoding_key(jwk);
+                    }
+                    if age < MIN_REFETCH_INTERVAL {
+                        return Err(format!("unknown signing key {kid:?}"));
+                    }
+                }
+            }
+        }
+
+        let mut cache = self.cache.write().await;
+        let keys = self.fetch().await.inspect_err(|e| warn!("{e}"))?;
+        let key = find_key(&keys, kid)
+            .ok_or_else(|| format!("unknown signing key {kid:?}"))
+            .and_then(decoding_key);
+        *cache = Some(CachedKeys {
+            keys,
+            fetched_at: Instant::now(),
+        });
+        key
+    }
+}
+
+/// Match by `kid`; a token without one is accepted only when the set holds a
+/// single key.
+fn find_key<'a>(keys: &'a JwkSet, kid: Option<&str>) -> Option<&'a Jwk> {
+    match kid {
+        Some(kid) => keys.find(kid),
+        None if keys.keys.len() == 1 => keys.keys.first(),
+        None => None,
+    }
+}
+
+fn decoding_key(jwk: &Jwk) -> Result<DecodingKey, String> {
+    DecodingKey::from_jwk(jwk).map_err(|e| format!("unusable JWK: {e}"))
+}
+
+#[async_trait]
+impl AccessTokenVerifier for JwksVerifier {
+    async fn verify(
+        &self, token: &str,
+    ) -> common::errors::Result<AuthenticatedPrincipal> {
+        let header = decode_header(token).map_err(|e| {
+            debug!("access token rejected: {e}");
+            CError::GenericUnauthorized
+        })?;
+        // Only asymmetric algorithms; an HMAC token signed with a public key
+        // must never verify.
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            debug!(
+                "access token rejected: {:?} not allowed for JWKS",
+                header.alg
+            );
+            return Err(CError::GenericUnauthorized);
+        }
+
+        let key = self.key_for(header.kid.as_deref()).await.map_err(|e| {
+            debug!("access token rejected: {e}");
+            CError::GenericUnauthorized
+        })?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        let claims = decode::<ExternalClaims>(token, &key, &validation)
+            .map_err(|e| {
+                debug!("access token rejected: {e}");
+                CError::GenericUnauthorized
+            })?
+            .claims;
+
+        let mut roles = claims.roles;
+        if let Some(realm) = claims.realm_access {
+            roles.extend(realm.roles);
+        }
+        Ok(AuthenticatedPrincipal {
+            subject: claims.sub,
+            issuer: claims.iss,
+            username: claims.preferred_username,
+            roles,
+            token_id: claims.jti,
+            scopes: Vec::new(),
+            expires_at: Some(claims.exp),
+        })
+    }
+}
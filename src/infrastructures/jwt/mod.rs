@@ -0,0 +1,228 @@
+pub mod denylist;
+pub mod jwks;
+pub mod signing_key;
+
+use crate::common;
+use crate::common::errors::CError;
+use crate::config::env_settings::AuthConfig;
+use crate::domains::authentication::{
+    AccessTokenVerifier, AuthenticatedPrincipal,
+};
+use crate::infrastructures::jwt::signing_key::SigningKey;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode,
+    decode_header, encode,
+};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// Claims carried by access tokens issued by this service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessClaims {
+    pub sub: String,
+    pub iss: String,
+    pub aud: String,
+    pub exp: i64,
+    pub iat: i64,
+    pub jti: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+/// A ring member with its keys parsed once.
+struct RingKey {
+    kid: String,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    jwk: Jwk,
+    retired_at: Option<DateTime<Utc>>,
+}
+
+/// JwtKeys signs and verifies the service's own access tokens with a ring of
+/// RS256 keys: the newest unretired key signs, and every key in the ring
+/// verifies tokens naming it in their `kid` header.
+pub struct JwtKeys {
+    /// Newest first
+    ring: RwLock<Vec<RingKey>>,
+    issuer: String,
+    audience: String,
+    access_ttl: u64,
+}
+
+impl JwtKeys {
+    /// An empty ring; nothing can be issued until keys are installed.
+    pub fn from_config(cfg: &AuthConfig) -> Self {
+        Self {
+            ring: RwLock::new(Vec::new()),
+            issuer: cfg.issuer.clone(),
+            audience: cfg.audience.clone(),
+            access_ttl: cfg.access_token_ttl_seconds,
+        }
+    }
+
+    /// Replace the ring with `keys`.
+    pub fn install(
+        &self, keys: &[SigningKey],
+    ) -> Result<(), jsonwebtoken::errors::Error> {
+        let mut ring = keys
+            .iter()
+            .map(|key| {
+                Ok((
+                    key.created_at,
+                    RingKey {
+                        kid: key.kid.clone(),
+                        encoding: key.encoding_key()?,
+                        decoding: key.decoding_key()?,
+                        jwk: key.jwk(),
+                        retired_at: key.retired_at,
+                    },
+                ))
+            })
+            .collect::<Result<Vec<_>, jsonwebtoken::errors::Error>>()?;
+        ring.sort_by_key(|(created_at, _)| std::cmp::Reverse(*created_at));
+        *self.ring.write().expect("key ring lock poisoned") =
+            ring.into_iter().map(|(_, key)| key).collect();
+        Ok(())
+    }
+
+    /// Public keys of the whole ring.
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self
+                .ring
+                .read()
+                .expect("key ring lock poisoned")
+                .iter()
+                .map(|key| key.jwk.clone())
+                .collect(),
+        }
+    }
+
+    /// `iss` claim of issued tokens.
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// Access token lifetime in seconds.
+    pub fn access_ttl(&self) -> u64 {
+        self.access_ttl
+    }
+
+    /// Issue a signed access token for `subject`.
+    pub fn issue(
+        &self, subject: &str, username: Option<&str>, roles: &[String],
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = Utc::now().timestamp();
+        let claims = AccessClaims {
+            sub: subject.to_string(),
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            exp: now + self.access_ttl as i64,
+            iat: now,
+            jti: Uuid::new_v4().to_string(),
+            username: username.map(str::to_string),
+            roles: roles.to_vec(),
+        };
+
+        let ring = self.ring.read().expect("key ring lock poisoned");
+        let key = ring
+            .iter()
+            .find(|key| key.retired_at.is_none())
+            .ok_or(ErrorKind::InvalidKeyFormat)?;
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(key.kid.clone());
+        encode(&header, &claims, &key.encoding)
+    }
+
+    /// Verify signature, `exp`, `iss` and `aud` and return the claims. The
+    /// token's `kid` selects the key.
+    pub fn verify(
+        &self, token: &str,
+    ) -> Result<AccessClaims, jsonwebtoken::errors::Error> {
+        let kid = decode_header(token)?.kid.ok_or(ErrorKind::InvalidToken)?;
+        let ring = self.ring.read().expect("key ring lock poisoned");
+        let key = ring
+            .iter()
+            .find(|key| key.kid == kid)
+            .ok_or(ErrorKind::InvalidToken)?;
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        decode::<AccessClaims>(token, &key.decoding, &validation)
+            .map(|data| data.claims)
+    }
+}
+
+#[async_trait]
+impl AccessTokenVerifier for JwtKeys {
+    async fn verify(
+        &self, token: &str,
+    ) -> common::errors::Result<AuthenticatedPrincipal> {
+        let claims = JwtKeys::verify(self, token).map_err(|e| {
+            debug!("access token rejected: {e}");
+            CError::GenericUnauthorized
+        })?;
+        Ok(AuthenticatedPrincipal {
+            subject: claims.sub,
+            issuer: claims.iss,
+            username: claims.username,
+            roles: claims.roles,
+            token_id: Some(claims.jti),
+            scopes: Vec::new(),
+            expires_at: Some(claims.exp),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_issue_and_verify() {
+        let cfg = AuthConfig::default();
+        let first = SigningKey::generate().unwrap();
+        let keys = JwtKeys::from_config(&cfg);
+        keys.install(std::slice::from_ref(&first)).unwrap();
+        let token = keys
+            .issue("user-1", Some("alice"), &["admin".to_string()])
+            .unwrap();
+        assert_eq!(decode_header(&token).unwrap().kid, Some(first.kid.clone()));
+
+        let claims = keys.verify(&token).unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(claims.username.as_deref(), Some("alice"));
+        assert_eq!(claims.roles, vec!["admin"]);
+
+        let other = JwtKeys::from_config(&AuthConfig {
+            audience: "someone-else".into(),
+            ..cfg
+        });
+        other.install(std::slice::from_ref(&first)).unwrap();
+        assert!(other.verify(&token).is_err());
+
+        // After rotation the retired key still verifies but no longer signs.
+        let mut retired = first;
+        retired.retired_at = Some(Utc::now());
+        let second = SigningKey::generate().unwrap();
+        let second_kid = second.kid.clone();
+        keys.install(&[retired, second]).unwrap();
+        assert!(keys.verify(&token).is_ok());
+        let token = keys.issue("user-1", None, &[]).unwrap();
+        assert_eq!(decode_header(&token).unwrap().kid, Some(second_kid));
+        assert_eq!(keys.jwks().keys.len(), 2);
+
+        keys.install(&[]).unwrap();
+        assert!(keys.verify(&token).is_err());
+        assert!(keys.issue("user-1", None, &[]).is_err());
+    }
+}
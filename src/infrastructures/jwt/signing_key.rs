@@ -0,0 +1,102 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, Jwk, KeyAlgorithm, PublicKeyUse,
+    RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{DecodingKey, EncodingKey};
+use rsa::RsaPrivateKey;
+use rsa::pkcs1::{EncodeRsaPrivateKey, LineEnding};
+use rsa::traits::PublicKeyParts;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+/// Algorithm of every key in the ring.
+pub const SIGNING_ALGORITHM: &str = "RS256";
+const RSA_KEY_BITS: usize = 2048;
+
+/// An RSA key pair that signs access tokens, identified by its RFC 7638
+/// thumbprint.
+pub struct SigningKey {
+    pub kid: String,
+    private_pem: Zeroizing<String>,
+    /// Base64url modulus
+    pub n: String,
+    /// Base64url public exponent
+    pub e: String,
+    pub created_at: DateTime<Utc>,
+    /// Retired keys no longer sign but still verify
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+impl SigningKey {
+    /// Generate a fresh key pair. CPU heavy; call from a blocking context.
+    pub fn generate() -> Result<Self, rsa::Error> {
+        let private = RsaPrivateKey::new(&mut rand::rngs::OsRng, RSA_KEY_BITS)?;
+        let pem = private
+            .to_pkcs1_pem(LineEnding::LF)
+            .map_err(rsa::Error::from)?;
+        let n = URL_SAFE_NO_PAD.encode(private.n().to_bytes_be());
+        let e = URL_SAFE_NO_PAD.encode(private.e().to_bytes_be());
+        Ok(Self {
+            kid: thumbprint(&n, &e),
+            private_pem: Zeroizing::new(pem.to_string()),
+            n,
+            e,
+            created_at: Utc::now(),
+            retired_at: None,
+        })
+    }
+
+    /// Rebuild a key loaded from storage.
+    pub fn from_parts(
+        kid: String, private_pem: Zeroizing<String>, n: String, e: String,
+        created_at: DateTime<Utc>, retired_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            kid,
+            private_pem,
+            n,
+            e,
+            created_at,
+            retired_at,
+        }
+    }
+
+    /// PKCS#1 PEM of the private key.
+    pub fn private_pem(&self) -> &str {
+        &self.private_pem
+    }
+
+    pub fn encoding_key(&self) -> jsonwebtoken::errors::Result<EncodingKey> {
+        EncodingKey::from_rsa_pem(self.private_pem.as_bytes())
+    }
+
+    pub fn decoding_key(&self) -> jsonwebtoken::errors::Result<DecodingKey> {
+        DecodingKey::from_rsa_components(&self.n, &self.e)
+    }
+
+    /// Public half as a JWK for `/.well-known/jwks.json`.
+    pub fn jwk(&self) -> Jwk {
+        Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(KeyAlgorithm::RS256),
+                key_id: Some(self.kid.clone()),
+                ..Default::default()
+            },
+            algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n: self.n.clone(),
+                e: self.e.clone(),
+            }),
+        }
+    }
+}
+
+/// RFC 7638 JWK thumbprint: members in lexicographic order, no whitespace.
+fn thumbprint(n: &str, e: &str) -> String {
+    let canonical = format!(r#"{{"e":"{e}","kty":"RSA","n":"{n}"}}"#);
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+}
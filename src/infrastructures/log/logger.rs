@@ -1,42 +1,50 @@
-use crate::config::env_settings::SERVICE_CONFIGURATION;
 use serde::Serialize;
+use tokio::sync::watch;
 
 #[derive(Serialize)]
 pub struct LoggerExtraFields {
     pub request_id: String,
 }
 
-pub fn setup_logger() {
-    let mut log_level: log::LevelFilter = log::LevelFilter::Debug;
-
-    let setting_level = &SERVICE_CONFIGURATION.server.log_level.clone();
-
-    match setting_level.as_str() {
-        "trace" => {
-            log_level = log::LevelFilter::Trace;
-        },
-        "debug" => {
-            log_level = log::LevelFilter::Debug;
-        },
-        "warn" => {
-            log_level = log::LevelFilter::Warn;
-        },
-        "error" => {
-            log_level = log::LevelFilter::Error;
-        },
-        _ => {
-            log_level = log::LevelFilter::Info;
-        },
-    };
-
-    env_logger::builder()
-        .filter_level(log_level)
+/// Install the global logger using an explicit level name (e.g. "debug").
+///
+/// The logger itself accepts every level; the active level is enforced through
+/// `log::set_max_level` so it can be changed at runtime.
+pub fn setup_logger_with_level(setting_level: &str) {
+    let installed = env_logger::builder()
+        .filter_level(log::LevelFilter::Trace)
         .format_timestamp_micros()
         .format(ecs_logger::format)
         .format_module_path(false)
         .target(env_logger::Target::Stdout)
         .target(env_logger::Target::Stderr)
-        .init();
+        .try_init();
+
+    if installed.is_ok() {
+        log::set_max_level(parse_level(setting_level));
+    }
+}
+
+/// Map a configured level name to a filter, defaulting to `Info`.
+pub fn parse_level(setting_level: &str) -> log::LevelFilter {
+    match setting_level.to_ascii_lowercase().as_str() {
+        "trace" => log::LevelFilter::Trace,
+        "debug" => log::LevelFilter::Debug,
+        "warn" => log::LevelFilter::Warn,
+        "error" => log::LevelFilter::Error,
+        _ => log::LevelFilter::Info,
+    }
+}
+
+/// Apply log level changes published on `rx` until the sender is dropped.
+pub fn follow_log_level(mut rx: watch::Receiver<String>) {
+    tokio::spawn(async move {
+        while rx.changed().await.is_ok() {
+            let level = parse_level(&rx.borrow_and_update());
+            log::set_max_level(level);
+            log::info!("log level changed to {level}");
+        }
+    });
 }
 
 #[cfg(test)]
@@ -46,7 +54,9 @@ mod tests {
 
     #[test]
     fn test_logger() {
-        setup_logger();
+        setup_logger_with_level("debug");
         info!("test log info");
+        assert_eq!(parse_level("WARN"), log::LevelFilter::Warn);
+        assert_eq!(parse_level("verbose"), log::LevelFilter::Info);
     }
 }
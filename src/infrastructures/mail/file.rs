@@ -0,0 +1,74 @@
+use crate::infrastructures::mail::{Mail, Mailer, message};
+use anyhow::{Context, Error};
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::message::Mailbox;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Writes each message to `{dir}/{timestamp}-{id}.eml` instead of sending
+/// it; for development and tests.
+pub struct FileMailer {
+    from: Mailbox,
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(from: Mailbox, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            from,
+            dir: dir.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> Result<(), Error> {
+        let formatted = message(&self.from, mail)?.formatted();
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .with_context(|| {
+                format!("failed to create {}", self.dir.display())
+            })?;
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+            Uuid::new_v4()
+        ));
+        tokio::fs::write(&path, formatted)
+            .await
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_mailer_writes_eml() {
+        let dir = std::env::temp_dir().join(format!("mail-{}", Uuid::new_v4()));
+        let mailer =
+            FileMailer::new("no-reply@example.com".parse().unwrap(), &dir);
+
+        mailer
+            .send(Mail {
+                to: "user@example.com".to_string(),
+                subject: "Hello".to_string(),
+                body: "token: abc".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let mut entries = std::fs::read_dir(&dir).unwrap();
+        let written =
+            std::fs::read_to_string(entries.next().unwrap().unwrap().path())
+                .unwrap();
+        assert!(written.contains("To: user@example.com"));
+        assert!(written.contains("Subject: Hello"));
+        assert!(written.contains("token: abc"));
+        assert!(entries.next().is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
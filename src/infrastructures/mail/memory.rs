@@ -0,0 +1,51 @@
+use crate::infrastructures::mail::{Mail, Mailer};
+use anyhow::Error;
+use async_trait::async_trait;
+use log::info;
+use std::sync::Mutex;
+
+/// Keeps sent messages in memory; for tests.
+#[derive(Default)]
+pub struct MemoryMailer {
+    sent: Mutex<Vec<Mail>>,
+}
+
+impl MemoryMailer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Messages sent so far, oldest first.
+    #[cfg(test)]
+    pub fn sent(&self) -> Vec<Mail> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Mailer for MemoryMailer {
+    async fn send(&self, mail: Mail) -> Result<(), Error> {
+        info!("mail to {} kept in memory: {}", mail.to, mail.subject);
+        self.sent.lock().unwrap().push(mail);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_mailer_records_messages() {
+        let mailer = MemoryMailer::new();
+        let mail = Mail {
+            to: "user@example.com".to_string(),
+            subject: "Hello".to_string(),
+            body: "token: abc".to_string(),
+        };
+
+        mailer.send(mail.clone()).await.unwrap();
+
+        assert_eq!(mailer.sent(), vec![mail]);
+    }
+}
@@ -0,0 +1,50 @@
+pub mod file;
+pub mod memory;
+pub mod smtp;
+
+use crate::config::env_settings::MailConfig;
+use anyhow::{Context, Error, bail};
+use async_trait::async_trait;
+use lettre::Message;
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use std::sync::Arc;
+
+/// A plain-text message to a single recipient.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers outgoing mail.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<(), Error>;
+}
+
+/// Build the mailer selected by `mail.transport`.
+pub fn from_config(cfg: &MailConfig) -> Result<Arc<dyn Mailer>, Error> {
+    let from: Mailbox = cfg.from.parse().context("invalid mail.from")?;
+    Ok(match cfg.transport.as_str() {
+        "smtp" => Arc::new(smtp::SmtpMailer::new(from, &cfg.smtp)?),
+        "file" => Arc::new(file::FileMailer::new(from, &cfg.file_dir)),
+        "memory" => Arc::new(memory::MemoryMailer::new()),
+        other => bail!("unknown mail transport {other:?}"),
+    })
+}
+
+/// Render `mail` as an RFC 5322 message from `from`.
+fn message(from: &Mailbox, mail: Mail) -> Result<Message, Error> {
+    let to: Mailbox = mail
+        .to
+        .parse()
+        .with_context(|| format!("invalid recipient {:?}", mail.to))?;
+    Ok(Message::builder()
+        .from(from.clone())
+        .to(to)
+        .subject(mail.subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(mail.body)?)
+}
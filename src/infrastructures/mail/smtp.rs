@@ -0,0 +1,44 @@
+use crate::config::env_settings::SmtpConfig;
+use crate::infrastructures::mail::{Mail, Mailer, message};
+use anyhow::Error;
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+
+/// Sends mail through an SMTP relay.
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(from: Mailbox, cfg: &SmtpConfig) -> Result<Self, Error> {
+        let mut builder = match cfg.tls.as_str() {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&cfg.host)?,
+            "none" => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&cfg.host)
+            },
+            _ => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&cfg.host)?,
+        }
+        .port(cfg.port);
+        if !cfg.username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                cfg.username.clone(),
+                cfg.password.expose().to_string(),
+            ));
+        }
+        Ok(Self {
+            from,
+            transport: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> Result<(), Error> {
+        self.transport.send(message(&self.from, mail)?).await?;
+        Ok(())
+    }
+}
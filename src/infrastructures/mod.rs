@@ -1,4 +1,10 @@
 pub mod cache;
+pub mod crypto;
 pub mod database;
+pub mod jwt;
 pub mod log;
+pub mod mail;
+pub mod oidc;
 pub mod otel;
+pub mod session;
+pub mod totp;
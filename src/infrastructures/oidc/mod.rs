@@ -0,0 +1,54 @@
+use crate::config::env_settings::OIDCConfig;
+use anyhow::{Context, Error};
+use openidconnect::core::{CoreClient, CoreProviderMetadata};
+use openidconnect::{
+    ClientId, ClientSecret, EndpointMaybeSet, EndpointNotSet, EndpointSet,
+    IssuerUrl, RedirectUrl, reqwest,
+};
+
+/// CoreClient as returned by discovery: the auth URL is always set, token and
+/// userinfo URLs are set when the provider advertises them.
+pub type OidcClient = CoreClient<
+    EndpointSet,
+    EndpointNotSet,
+    EndpointNotSet,
+    EndpointNotSet,
+    EndpointMaybeSet,
+    EndpointMaybeSet,
+>;
+
+/// HTTP client for provider calls. Redirects are disabled to avoid SSRF.
+pub fn http_client() -> Result<reqwest::Client, Error> {
+    reqwest::ClientBuilder::new()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .context("failed to build OIDC HTTP client")
+}
+
+/// Run OIDC discovery against the configured issuer and build the client.
+pub async fn discover_client(
+    cfg: &OIDCConfig, http: &reqwest::Client,
+) -> Result<OidcClient, Error> {
+    let issuer = cfg.issuer();
+    let metadata = CoreProviderMetadata::discover_async(
+        IssuerUrl::new(issuer.clone())
+            .with_context(|| format!("invalid OIDC issuer URL {issuer}"))?,
+        http,
+    )
+    .await
+    .with_context(|| format!("OIDC discovery against {issuer} failed"))?;
+
+    let client_secret = (!cfg.client_secret.is_empty())
+        .then(|| ClientSecret::new(cfg.client_secret.expose().to_string()));
+
+    let client = CoreClient::from_provider_metadata(
+        metadata,
+        ClientId::new(cfg.client_id.clone()),
+        client_secret,
+    )
+    .set_redirect_uri(
+        RedirectUrl::new(cfg.redirect_url.clone())
+            .context("invalid oidc.redirect_url")?,
+    );
+    Ok(client)
+}
@@ -0,0 +1,55 @@
+use crate::domains::health::HealthCheck;
+use anyhow::Context;
+use async_trait::async_trait;
+use tokio::net::TcpStream;
+
+/// Connects to the OTLP collector. Spans are exported in the background, so
+/// an unreachable collector only degrades the service.
+pub struct OtlpHealthCheck {
+    authority: String,
+}
+
+impl OtlpHealthCheck {
+    /// `uri` is `otel.uri`, with or without a scheme.
+    pub fn new(uri: &str) -> Self {
+        let without_scheme = uri.split_once("://").map_or(uri, |(_, rest)| rest);
+        let authority = without_scheme.split('/').next().unwrap_or_default();
+        Self {
+            authority: authority.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for OtlpHealthCheck {
+    fn name(&self) -> &'static str {
+        "otlp_exporter"
+    }
+
+    fn critical(&self) -> bool {
+        false
+    }
+
+    async fn check(&self) -> anyhow::Result<()> {
+        TcpStream::connect(&self.authority).await.with_context(|| {
+            format!("collector {} unreachable", self.authority)
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authority_from_uri() {
+        for uri in [
+            "localhost:4317",
+            "http://localhost:4317",
+            "http://localhost:4317/v1",
+        ] {
+            assert_eq!(OtlpHealthCheck::new(uri).authority, "localhost:4317");
+        }
+    }
+}
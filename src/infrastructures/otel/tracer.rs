@@ -4,17 +4,19 @@ use opentelemetry_sdk::{
     trace::{SdkTracerProvider, TraceError},
 };
 
-use crate::config::env_settings::SERVICE_CONFIGURATION;
+use crate::config::env_settings::Settings;
 
-pub fn init_tracer_provider() -> Result<SdkTracerProvider, TraceError> {
+pub fn init_tracer_provider(
+    settings: &Settings,
+) -> Result<SdkTracerProvider, TraceError> {
     let exporter = opentelemetry_otlp::SpanExporter::builder()
         .with_tonic()
-        .with_endpoint(SERVICE_CONFIGURATION.otel.uri.clone())
+        .with_endpoint(settings.otel.uri.clone())
         .build()
         .map_err(|e| TraceError::Other(Box::new(e)))?;
 
     let resource = Resource::builder_empty()
-        .with_service_name(SERVICE_CONFIGURATION.server.name.clone())
+        .with_service_name(settings.server.name.clone())
         .build();
 
     let provider = SdkTracerProvider::builder()
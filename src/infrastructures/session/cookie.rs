@@ -0,0 +1,110 @@
+use crate::config::env_settings::SessionConfig;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use http::HeaderMap;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// SessionCookie signs session ids with HMAC-SHA256 and renders the
+/// `Set-Cookie` values. The cookie value is `<session id>.<signature>`.
+pub struct SessionCookie {
+    name: String,
+    key: Zeroizing<Vec<u8>>,
+    same_site: String,
+    max_age: u64,
+}
+
+impl SessionCookie {
+    pub fn from_config(cfg: &SessionConfig) -> Self {
+        Self {
+            name: cfg.cookie_name.clone(),
+            key: Zeroizing::new(cfg.cookie_secret.expose().as_bytes().to_vec()),
+            same_site: cfg.same_site.clone(),
+            max_age: cfg.absolute_timeout_seconds,
+        }
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.key)
+            .expect("HMAC accepts any key length")
+    }
+
+    pub fn sign(&self, session_id: &str) -> String {
+        let mut mac = self.mac();
+        mac.update(session_id.as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+        format!("{session_id}.{signature}")
+    }
+
+    /// Session id from a signed cookie value, if the signature is valid.
+    pub fn verify(&self, value: &str) -> Option<String> {
+        let (session_id, signature) = value.rsplit_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+        let mut mac = self.mac();
+        mac.update(session_id.as_bytes());
+        mac.verify_slice(&signature).ok()?;
+        Some(session_id.to_string())
+    }
+
+    /// Verified session id from the request's `Cookie` headers.
+    pub fn read(&self, headers: &HeaderMap) -> Option<String> {
+        headers
+            .get_all(http::header::COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(name, _)| *name == self.name)
+            .and_then(|(_, value)| self.verify(value))
+    }
+
+    /// `Set-Cookie` value establishing the session.
+    pub fn set(&self, session_id: &str) -> String {
+        format!(
+            "{}={}; Path=/; Max-Age={}; HttpOnly; Secure; SameSite={}",
+            self.name,
+            self.sign(session_id),
+            self.max_age,
+            self.same_site
+        )
+    }
+
+    /// `Set-Cookie` value removing the session cookie.
+    pub fn clear(&self) -> String {
+        format!(
+            "{}=; Path=/; Max-Age=0; HttpOnly; Secure; SameSite={}",
+            self.name, self.same_site
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::secrets::Secret;
+
+    #[test]
+    fn test_signed_cookie_roundtrip() {
+        let cookie = SessionCookie::from_config(&SessionConfig {
+            cookie_secret: Secret::new("0123456789abcdef0123456789abcdef"),
+            ..Default::default()
+        });
+        let set = cookie.set("abc");
+        assert!(set.contains("HttpOnly; Secure; SameSite=Lax"));
+
+        let value = set.split(';').next().unwrap().to_string();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            http::header::COOKIE,
+            format!("theme=dark; {value}").parse().unwrap(),
+        );
+        assert_eq!(cookie.read(&headers).as_deref(), Some("abc"));
+
+        let forged = value.replace("sid=abc.", "sid=abd.");
+        headers.insert(http::header::COOKIE, forged.parse().unwrap());
+        assert_eq!(cookie.read(&headers), None);
+    }
+}
@@ -0,0 +1,116 @@
+pub mod cookie;
+
+use crate::common;
+use crate::common::errors::CError;
+use crate::domains::session::{Session, SessionStore};
+use crate::infrastructures::cache::local_cache::CacheRegistry;
+use async_trait::async_trait;
+use log::error;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+const SESSION_NAMESPACE: &str = "session";
+
+/// CacheSessionStore keeps sessions in the "session" cache namespace, next to
+/// a per-subject index of session ids. Entries expire with the namespace TTL,
+/// so every save also refreshes the entry's lifetime.
+pub struct CacheSessionStore {
+    caches: Arc<CacheRegistry>,
+    /// Serializes read-modify-write cycles on the subject index
+    index_lock: Mutex<()>,
+}
+
+impl CacheSessionStore {
+    pub fn new(caches: Arc<CacheRegistry>) -> Self {
+        Self {
+            caches,
+            index_lock: Mutex::new(()),
+        }
+    }
+
+    async fn index(&self, subject: &str) -> Vec<String> {
+        self.caches
+            .get_json(SESSION_NAMESPACE, &index_key(subject))
+            .await
+            .unwrap_or_default()
+    }
+
+    async fn put<V: serde::Serialize>(
+        &self, key: String, value: &V,
+    ) -> common::errors::Result<()> {
+        self.caches
+            .put_json(SESSION_NAMESPACE, key, value)
+            .await
+            .map_err(|e| {
+                error!("failed to store session data: {e}");
+                CError::GenericInternalServer
+            })
+    }
+}
+
+#[async_trait]
+impl SessionStore for CacheSessionStore {
+    async fn save(&self, session: &Session) -> common::errors::Result<()> {
+        self.put(session_key(&session.id), session).await?;
+
+        let _guard = self.index_lock.lock().await;
+        let mut ids = self.index(&session.subject).await;
+        if !ids.contains(&session.id) {
+            ids.push(session.id.clone());
+        }
+        self.put(index_key(&session.subject), &ids).await
+    }
+
+    async fn load(&self, id: &str) -> common::errors::Result<Option<Session>> {
+        Ok(self
+            .caches
+            .get_json(SESSION_NAMESPACE, &session_key(id))
+            .await)
+    }
+
+    async fn remove(&self, id: &str) -> common::errors::Result<Option<Session>> {
+        let Some(session) = self
+            .caches
+            .take_json::<Session>(SESSION_NAMESPACE, &session_key(id))
+            .await
+        else {
+            return Ok(None);
+        };
+
+        let _guard = self.index_lock.lock().await;
+        let mut ids = self.index(&session.subject).await;
+        ids.retain(|i| i != id);
+        self.put(index_key(&session.subject), &ids).await?;
+        Ok(Some(session))
+    }
+
+    async fn list(&self, subject: &str) -> common::errors::Result<Vec<Session>> {
+        let _guard = self.index_lock.lock().await;
+        let ids = self.index(subject).await;
+
+        let mut sessions = Vec::with_capacity(ids.len());
+        for id in &ids {
+            if let Some(session) = self
+                .caches
+                .get_json::<Session>(SESSION_NAMESPACE, &session_key(id))
+                .await
+            {
+                sessions.push(session);
+            }
+        }
+        // Drop ids whose sessions were evicted by the cache.
+        if sessions.len() != ids.len() {
+            let live: Vec<&String> = sessions.iter().map(|s| &s.id).collect();
+            self.put(index_key(subject), &live).await?;
+        }
+        Ok(sessions)
+    }
+}
+
+fn session_key(id: &str) -> String {
+    format!("sid:{id}")
+}
+
+fn index_key(subject: &str) -> String {
+    format!("subject:{subject}")
+}
@@ -0,0 +1,125 @@
+use rand::RngCore;
+use ring::hmac;
+
+/// RFC 6238 defaults understood by every authenticator app.
+const STEP_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+/// 160-bit secret, the RFC 4226 recommendation for HMAC-SHA1
+const SECRET_LEN: usize = 20;
+/// Steps accepted either side of the current one, for clock drift
+const ALLOWED_SKEW: i64 = 1;
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// A fresh random TOTP secret.
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+/// RFC 4648 base32 without padding, the form authenticator apps accept.
+pub fn base32(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(
+            BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char,
+        );
+    }
+    out
+}
+
+/// `otpauth://` URI for QR-code enrollment.
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &[u8]) -> String {
+    let label = format!("{issuer}:{account}");
+    let mut uri = url::Url::parse("otpauth://totp/").expect("static URL parses");
+    uri.path_segments_mut()
+        .expect("otpauth URL has a path")
+        .pop()
+        .push(&label);
+    uri.query_pairs_mut()
+        .append_pair("secret", &base32(secret))
+        .append_pair("issuer", issuer)
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", &DIGITS.to_string())
+        .append_pair("period", &STEP_SECONDS.to_string());
+    uri.to_string()
+}
+
+/// Time step containing the Unix timestamp `now`.
+pub fn step_at(now: i64) -> i64 {
+    now.div_euclid(STEP_SECONDS)
+}
+
+/// HOTP value (RFC 4226) of `secret` for time step `step`.
+pub fn code_at(secret: &[u8], step: i64) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret);
+    let tag = hmac::sign(&key, &step.to_be_bytes());
+    let digest = tag.as_ref();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+/// The time step `code` is valid for around `now`, if any. Callers must
+/// reject steps at or before the last one accepted to prevent replay.
+pub fn verify(secret: &[u8], code: &str, now: i64) -> Option<i64> {
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let current = step_at(now);
+    (current - ALLOWED_SKEW..=current + ALLOWED_SKEW).find(|&step| {
+        crate::infrastructures::crypto::constant_time_eq(
+            &code_at(secret, step),
+            code,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        // RFC 6238 Appendix B, SHA-1, truncated to six digits.
+        let secret = b"12345678901234567890";
+        assert_eq!(code_at(secret, step_at(59)), "287082");
+        assert_eq!(code_at(secret, step_at(1111111109)), "081804");
+        assert_eq!(code_at(secret, step_at(2000000000)), "279037");
+
+        assert_eq!(
+            verify(secret, "081804", 1111111109 + 30),
+            Some(step_at(1111111109))
+        );
+        assert_eq!(verify(secret, "081804", 1111111109 + 90), None);
+        assert_eq!(verify(secret, "81804", 1111111109), None);
+    }
+
+    #[test]
+    fn test_base32_and_uri() {
+        assert_eq!(base32(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(
+            otpauth_uri("Example", "alice@example.com", b"foobar"),
+            "otpauth://totp/Example:alice@example.com?secret=MZXW6YTBOI&issuer=Example&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
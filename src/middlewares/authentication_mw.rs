@@ -0,0 +1,431 @@
+use crate::common::api_response::render_error;
+use crate::common::errors::{AppError, CError};
+use crate::constants::http::{HEADER_WWW_AUTHENTICATE, HEADER_X_API_KEY};
+use crate::domains::api_key::ApiKeyTrait;
+use crate::domains::authentication::{
+    AccessTokenVerifier, AuthenticatedPrincipal,
+};
+use crate::domains::session::{Session, SessionTrait};
+use crate::infrastructures::jwt::denylist::TokenDenylist;
+use crate::infrastructures::session::cookie::SessionCookie;
+use crate::middlewares::request_id_mw::{RequestScope, request_id_from_headers};
+use axum::{
+    body::Body,
+    extract::FromRequestParts,
+    http::{HeaderMap, HeaderValue, Request, header, request::Parts},
+    response::Response as AxumResponse,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use futures_util::future::BoxFuture;
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+
+/// AuthenticationLayer validates an `X-API-Key` header or HTTP Basic client
+/// credentials, an `Authorization: Bearer` token or a session cookie, in that
+/// order, and stores the resulting `AuthenticatedPrincipal` in the request
+/// extensions.
+///
+/// Requests without valid credentials pass through unauthenticated; handlers
+/// that need a caller take `AuthenticatedPrincipal` as an extractor.
+#[derive(Clone)]
+pub struct AuthenticationLayer {
+    verifiers: Arc<Vec<Arc<dyn AccessTokenVerifier>>>,
+    api_keys: Option<Arc<dyn ApiKeyTrait>>,
+    sessions: Option<SessionAuth>,
+    denylist: Option<Arc<TokenDenylist>>,
+}
+
+/// Session service plus the cookie that carries the session id.
+#[derive(Clone)]
+struct SessionAuth {
+    sessions: Arc<dyn SessionTrait>,
+    cookie: Arc<SessionCookie>,
+}
+
+impl AuthenticationLayer {
+    /// Verifiers are tried in order; the first to accept the token wins.
+    pub fn new(verifiers: Vec<Arc<dyn AccessTokenVerifier>>) -> Self {
+        Self {
+            verifiers: Arc::new(verifiers),
+            api_keys: None,
+            sessions: None,
+            denylist: None,
+        }
+    }
+
+    /// Also accept API keys, either as `X-API-Key` or as HTTP Basic client
+    /// credentials with the key id as client id and the key as secret. A
+    /// request carrying either is authenticated by its key only.
+    pub fn with_api_keys(mut self, api_keys: Arc<dyn ApiKeyTrait>) -> Self {
+        self.api_keys = Some(api_keys);
+        self
+    }
+
+    /// Also accept session cookies. Each authenticated request extends the
+    /// session's idle expiry.
+    pub fn with_sessions(
+        mut self, sessions: Arc<dyn SessionTrait>, cookie: Arc<SessionCookie>,
+    ) -> Self {
+        self.sessions = Some(SessionAuth { sessions, cookie });
+        self
+    }
+
+    /// Reject bearer tokens whose `jti` has been revoked.
+    pub fn with_token_denylist(mut self, denylist: Arc<TokenDenylist>) -> Self {
+        self.denylist = Some(denylist);
+        self
+    }
+}
+
+impl<S> Layer<S> for AuthenticationLayer {
+    type Service = AuthenticationMiddleware<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthenticationMiddleware {
+            inner,
+            verifiers: self.verifiers.clone(),
+            api_keys: self.api_keys.clone(),
+            sessions: self.sessions.clone(),
+            denylist: self.denylist.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthenticationMiddleware<S> {
+    inner: S,
+    verifiers: Arc<Vec<Arc<dyn AccessTokenVerifier>>>,
+    api_keys: Option<Arc<dyn ApiKeyTrait>>,
+    sessions: Option<SessionAuth>,
+    denylist: Option<Arc<TokenDenylist>>,
+}
+
+impl<S> Service<Request<Body>> for AuthenticationMiddleware<S>
+where
+    S: Service<Request<Body>, Response = AxumResponse, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = AxumResponse;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<AxumResponse, Infallible>>;
+
+    fn poll_ready(
+        &mut self, cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(|_| unreachable!())
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let mut svc = self.inner.clone();
+        let verifiers = self.verifiers.clone();
+        let api_keys = self.api_keys.clone();
+        let sessions = self.sessions.clone();
+        let denylist = self.denylist.clone();
+
+        Box::pin(async move {
+            let client = header_value(req.headers(), HEADER_X_API_KEY)
+                .map(|key| (None, key))
+                .or_else(|| {
+                    client_credentials(req.headers())
+                        .map(|(id, key)| (Some(id), key))
+                });
+            let principal = match (client, &api_keys) {
+                (Some((client_id, key)), Some(api_keys)) => {
+                    api_keys.authenticate(&key).await.ok().filter(|p| {
+                        client_id.is_none() || p.token_id == client_id
+                    })
+                },
+                (Some(_), None) => None,
+                (None, _) => match bearer_token(req.headers()) {
+                    Some(token) => {
+                        verify_bearer(&verifiers, denylist.as_deref(), &token)
+                            .await
+                    },
+                    None => match &sessions {
+                        Some(auth) => resume_session(auth, req.headers()).await,
+                        None => None,
+                    },
+                },
+            };
+            if let Some(principal) = principal {
+                req.extensions_mut().insert(principal);
+            }
+            svc.call(req).await
+        })
+    }
+}
+
+async fn verify_bearer(
+    verifiers: &[Arc<dyn AccessTokenVerifier>], denylist: Option<&TokenDenylist>,
+    token: &str,
+) -> Option<AuthenticatedPrincipal> {
+    for verifier in verifiers {
+        if let Ok(principal) = verifier.verify(token).await {
+            if let (Some(denylist), Some(jti)) = (denylist, &principal.token_id)
+                && denylist.is_revoked(jti).await
+            {
+                return None;
+            }
+            return Some(principal);
+        }
+    }
+    None
+}
+
+async fn resume_session(
+    auth: &SessionAuth, headers: &HeaderMap,
+) -> Option<AuthenticatedPrincipal> {
+    let id = auth.cookie.read(headers)?;
+    let session = auth.sessions.resume(&id).await.ok()?;
+    Some(session_principal(session))
+}
+
+fn session_principal(session: Session) -> AuthenticatedPrincipal {
+    AuthenticatedPrincipal {
+        token_id: Some(session.handle()),
+        subject: session.subject,
+        issuer: session.issuer,
+        username: session.name.or(session.email),
+        roles: Vec::new(),
+        scopes: Vec::new(),
+        expires_at: Some(session.expires_at),
+    }
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// Client id and secret from an `Authorization: Basic` header.
+fn client_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, encoded) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded =
+        String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (id, secret) = decoded.split_once(':')?;
+    Some((id.to_string(), secret.to_string()))
+}
+
+/// Token from an `Authorization: Bearer <token>` header.
+pub fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty())
+        .then(|| token.to_string())
+}
+
+impl<S> FromRequestParts<S> for AuthenticatedPrincipal
+where
+    S: Send + Sync,
+{
+    type Rejection = AxumResponse;
+
+    async fn from_request_parts(
+        parts: &mut Parts, _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        match parts.extensions.get::<AuthenticatedPrincipal>() {
+            Some(principal) => Ok(principal.clone()),
+            None => {
+                Err(unauthorized(request_id_from_headers(&mut parts.headers)))
+            },
+        }
+    }
+}
+
+pub(crate) fn unauthorized(req_id: String) -> AxumResponse {
+    let scope = RequestScope::current().unwrap_or_else(|| RequestScope {
+        request_id: req_id,
+        ..RequestScope::current_or_new()
+    });
+    let mut resp =
+        render_error(&scope, &AppError::new(CError::GenericUnauthorized));
+    resp.headers_mut()
+        .insert(HEADER_WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    resp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common;
+    use crate::config::env_settings::AuthConfig;
+    use crate::domains::api_key::{ApiKeyInfo, CreatedApiKey};
+    use crate::domains::listing::{ListParams, Page};
+    use crate::infrastructures::cache::local_cache::CacheRegistry;
+    use crate::infrastructures::jwt::JwtKeys;
+    use crate::infrastructures::jwt::signing_key::SigningKey;
+    use axum::Router;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use std::time::Duration;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    const KEY_ID: &str = "key-1";
+    const KEY: &str = "ak_abc.secret";
+
+    /// Knows the single key `KEY`, whose id is `KEY_ID`; it is read-only.
+    struct FakeApiKeys;
+
+    #[async_trait::async_trait]
+    impl ApiKeyTrait for FakeApiKeys {
+        async fn create(
+            &self, _: String, _: Vec<String>, _: Option<u64>, _: String,
+        ) -> common::errors::Result<CreatedApiKey> {
+            Err(CError::GenericPermission)
+        }
+
+        async fn list(
+            &self, _: &ListParams,
+        ) -> common::errors::Result<Page<ApiKeyInfo>> {
+            Ok(Page {
+                items: Vec::new(),
+                total: 0,
+            })
+        }
+
+        async fn revoke(&self, _: Uuid) -> common::errors::Result<()> {
+            Err(CError::ApiKeyNotFound)
+        }
+
+        async fn authenticate(
+            &self, key: &str,
+        ) -> common::errors::Result<AuthenticatedPrincipal> {
+            if key != KEY {
+                return Err(CError::GenericUnauthorized);
+            }
+            Ok(AuthenticatedPrincipal {
+                subject: format!("api-key:{KEY_ID}"),
+                issuer: "api-key".to_string(),
+                username: None,
+                roles: Vec::new(),
+                scopes: Vec::new(),
+                token_id: Some(KEY_ID.to_string()),
+                expires_at: None,
+            })
+        }
+    }
+
+    async fn whoami(principal: AuthenticatedPrincipal) -> String {
+        principal.subject
+    }
+
+    #[tokio::test]
+    async fn test_bearer_token_populates_principal() {
+        let keys = Arc::new(JwtKeys::from_config(&AuthConfig::default()));
+        keys.install(&[SigningKey::generate().unwrap()]).unwrap();
+        let token = keys.issue("user-1", None, &[]).unwrap();
+        let app = Router::new()
+            .route("/whoami", get(whoami))
+            .layer(AuthenticationLayer::new(vec![keys]));
+
+        let call = |auth: Option<String>| {
+            let mut req = Request::builder().uri("/whoami");
+            if let Some(auth) = auth {
+                req = req.header(header::AUTHORIZATION, auth);
+            }
+            app.clone().oneshot(req.body(Body::empty()).unwrap())
+        };
+
+        let ok = call(Some(format!("Bearer {token}"))).await.unwrap();
+        assert_eq!(ok.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(ok.into_body(), 1024).await.unwrap();
+        assert_eq!(&body[..], b"user-1");
+
+        let missing = call(None).await.unwrap();
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(missing.headers()[HEADER_WWW_AUTHENTICATE], "Bearer");
+
+        let forged = call(Some(format!("Bearer {token}x"))).await.unwrap();
+        assert_eq!(forged.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_revoked_bearer_token_is_rejected() {
+        let keys = Arc::new(JwtKeys::from_config(&AuthConfig::default()));
+        keys.install(&[SigningKey::generate().unwrap()]).unwrap();
+        let token = keys.issue("user-1", None, &[]).unwrap();
+        let claims = keys.verify(&token).unwrap();
+        let denylist = Arc::new(TokenDenylist::new(
+            CacheRegistry::init().clone(),
+            Duration::from_secs(60),
+            1_000,
+        ));
+        let app = Router::new().route("/whoami", get(whoami)).layer(
+            AuthenticationLayer::new(vec![keys])
+                .with_token_denylist(denylist.clone()),
+        );
+        let call = || {
+            let req = Request::builder()
+                .uri("/whoami")
+                .header(header::AUTHORIZATION, format!("Bearer {token}"));
+            app.clone().oneshot(req.body(Body::empty()).unwrap())
+        };
+
+        assert_eq!(call().await.unwrap().status(), StatusCode::OK);
+        denylist.revoke(&claims.jti, claims.exp).await;
+        assert_eq!(call().await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_and_basic_credentials() {
+        let keys = Arc::new(JwtKeys::from_config(&AuthConfig::default()));
+        keys.install(&[SigningKey::generate().unwrap()]).unwrap();
+        let token = keys.issue("user-1", None, &[]).unwrap();
+        let app = Router::new().route("/whoami", get(whoami)).layer(
+            AuthenticationLayer::new(vec![keys])
+                .with_api_keys(Arc::new(FakeApiKeys)),
+        );
+        let call = |headers: Vec<(header::HeaderName, String)>| {
+            let mut req = Request::builder().uri("/whoami");
+            for (name, value) in headers {
+                req = req.header(name, value);
+            }
+            app.clone().oneshot(req.body(Body::empty()).unwrap())
+        };
+        let basic = |id: &str, secret: &str| {
+            format!("Basic {}", STANDARD.encode(format!("{id}:{secret}")))
+        };
+        let x_api_key = header::HeaderName::from_static("x-api-key");
+
+        let ok = call(vec![(x_api_key.clone(), KEY.to_string())])
+            .await
+            .unwrap();
+        assert_eq!(ok.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(ok.into_body(), 1024).await.unwrap();
+        assert_eq!(&body[..], format!("api-key:{KEY_ID}").as_bytes());
+
+        let wrong = call(vec![(x_api_key.clone(), "ak_abc.nope".to_string())]);
+        assert_eq!(wrong.await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        let ok = call(vec![(header::AUTHORIZATION, basic(KEY_ID, KEY))]);
+        assert_eq!(ok.await.unwrap().status(), StatusCode::OK);
+
+        // The client id must name the key presented as secret.
+        let other = call(vec![(header::AUTHORIZATION, basic("key-2", KEY))]);
+        assert_eq!(other.await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        // A rejected key is not retried as a bearer token.
+        let both = call(vec![
+            (x_api_key, "ak_abc.nope".to_string()),
+            (header::AUTHORIZATION, format!("Bearer {token}")),
+        ]);
+        assert_eq!(both.await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::common::errors::CError;
+use crate::domains::authentication::AuthenticatedPrincipal;
+use crate::domains::authorization::AuthorizationTrait;
+use crate::middlewares::authentication_mw::unauthorized;
+use crate::middlewares::request_id_mw::request_id_from_headers;
+use axum::{
+    body::Body,
+    http::Request,
+    response::{IntoResponse, Response as AxumResponse},
+};
+use futures_util::future::BoxFuture;
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+use tracing::{error, warn};
+
+/// Require `permission` for every route the layer wraps, e.g.
+/// `.route_layer(require_permission("users:write"))`.
+///
+/// The caller must be authenticated (`AuthenticationLayer`). Permissions come
+/// from the principal's scopes, then from its roles through the
+/// `Arc<dyn AuthorizationTrait>` request extension.
+pub fn require_permission(permission: &'static str) -> RequirePermissionLayer {
+    RequirePermissionLayer { permission }
+}
+
+#[derive(Clone)]
+pub struct RequirePermissionLayer {
+    permission: &'static str,
+}
+
+impl<S> Layer<S> for RequirePermissionLayer {
+    type Service = RequirePermissionMiddleware<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        RequirePermissionMiddleware {
+            inner,
+            permission: self.permission,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequirePermissionMiddleware<S> {
+    inner: S,
+    permission: &'static str,
+}
+
+impl<S> Service<Request<Body>> for RequirePermissionMiddleware<S>
+where
+    S: Service<Request<Body>, Response = AxumResponse, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = AxumResponse;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<AxumResponse, Infallible>>;
+
+    fn poll_ready(
+        &mut self, cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(|_| unreachable!())
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let mut svc = self.inner.clone();
+        let permission = self.permission;
+        let req_id = request_id_from_headers(req.headers_mut());
+        let principal = req.extensions().get::<AuthenticatedPrincipal>().cloned();
+        let authorizer = req
+            .extensions()
+            .get::<Arc<dyn AuthorizationTrait>>()
+            .cloned();
+
+        Box::pin(async move {
+            let Some(principal) = principal else {
+                return Ok(unauthorized(req_id));
+            };
+            if principal.scopes.iter().any(|s| s == permission) {
+                return svc.call(req).await;
+            }
+            let Some(authorizer) = authorizer else {
+                error!(
+                    request_id = %req_id,
+                    permission = permission,
+                    "no authorizer registered; denying request",
+                );
+                return Ok(CError::GenericInternalServer.into_response());
+            };
+
+            match authorizer.permissions_for(&principal.roles).await {
+                Ok(granted) if granted.contains(permission) => {
+                    svc.call(req).await
+                },
+                Ok(_) => {
+                    warn!(
+                        request_id = %req_id,
+                        subject = %principal.subject,
+                        permission = permission,
+                        "permission denied",
+                    );
+                    Ok(CError::GenericPermission.into_response())
+                },
+                Err(err) => Ok(err.into_response()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common;
+    use crate::config::env_settings::AuthConfig;
+    use crate::infrastructures::jwt::JwtKeys;
+    use crate::infrastructures::jwt::signing_key::SigningKey;
+    use crate::middlewares::authentication_mw::AuthenticationLayer;
+    use async_trait::async_trait;
+    use axum::routing::get;
+    use axum::{Extension, Router};
+    use http::{StatusCode, header};
+    use std::collections::HashSet;
+    use tower::ServiceExt;
+
+    struct StaticAuthorizer;
+
+    #[async_trait]
+    impl AuthorizationTrait for StaticAuthorizer {
+        async fn permissions_for(
+            &self, roles: &[String],
+        ) -> common::errors::Result<HashSet<String>> {
+            Ok(roles
+                .iter()
+                .filter(|r| *r == "editor")
+                .map(|_| "users:write".to_string())
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_require_permission() {
+        let keys = Arc::new(JwtKeys::from_config(&AuthConfig::default()));
+        keys.install(&[SigningKey::generate().unwrap()]).unwrap();
+        let authorizer: Arc<dyn AuthorizationTrait> = Arc::new(StaticAuthorizer);
+        let app = Router::new()
+            .route("/users", get(|| async { "ok" }))
+            .route_layer(require_permission("users:write"))
+            .layer(Extension(authorizer))
+            .layer(AuthenticationLayer::new(vec![keys.clone()]));
+
+        let call = |roles: Option<&[String]>| {
+            let mut req = Request::builder().uri("/users");
+            if let Some(roles) = roles {
+                let token = keys.issue("user-1", None, roles).unwrap();
+                req =
+                    req.header(header::AUTHORIZATION, format!("Bearer {token}"));
+            }
+            app.clone().oneshot(req.body(Body::empty()).unwrap())
+        };
+
+        let anonymous = call(None).await.unwrap();
+        assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+
+        let viewer = call(Some(&["viewer".to_string()])).await.unwrap();
+        assert_eq!(viewer.status(), StatusCode::FORBIDDEN);
+
+        let editor = call(Some(&["editor".to_string()])).await.unwrap();
+        assert_eq!(editor.status(), StatusCode::OK);
+    }
+}
@@ -1,6 +1,8 @@
+pub mod authentication_mw;
+pub mod authorization_mw;
 pub mod not_found_mw;
 pub mod recovery_mw;
-mod request_context;
+pub mod request_context;
 pub mod request_id_mw;
 pub mod request_logging_mw;
 pub mod timeout_mw;
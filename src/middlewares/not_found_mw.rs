@@ -1,26 +1,52 @@
-use crate::common::api_response::Response;
-use crate::middlewares::request_id_mw::request_id_from_headers;
+use crate::common::api_response::render_error;
+use crate::common::errors::{AppError, CError};
+use crate::middlewares::request_id_mw::{RequestScope, request_id_from_headers};
 use axum::{
-    http::{HeaderMap, Method, StatusCode, Uri},
+    http::{HeaderMap, Uri},
     response::IntoResponse,
 };
 
 pub async fn not_found_middleware(
-    mut headers: HeaderMap, uri: Uri, method: Method,
+    mut headers: HeaderMap, uri: Uri,
 ) -> impl IntoResponse {
     let req_id = request_id_from_headers(&mut headers);
+    let scope = RequestScope::new(req_id, &uri, &headers);
 
-    Response::<serde_json::Value>::new_with_request_id(req_id)
-        .with_code("NOT_FOUND")
-        .with_message("No route matches the requested path")
-        .with_meta_kv("path", uri.path())
-        .with_meta_kv("method", method.to_string())
-        .with_meta_kv(
-            "host",
-            headers
-                .get("Host")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or(""),
-        )
-        .with_status(StatusCode::NOT_FOUND)
+    render_error(&scope, &AppError::new(CError::GenericUnknownAPIPath))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::body::Body;
+    use http::{Request, StatusCode, header};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_unknown_path_is_localized() {
+        let cases = [
+            ("es", "ruta de la api desconocida"),
+            ("fr", "chemin d'api inconnu"),
+        ];
+        for (locale, message) in cases {
+            let res = Router::new()
+                .fallback(not_found_middleware)
+                .oneshot(
+                    Request::get("/nowhere")
+                        .header(header::ACCEPT_LANGUAGE, locale)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::NOT_FOUND);
+            assert_eq!(res.headers()[header::CONTENT_LANGUAGE], locale);
+
+            let body = axum::body::to_bytes(res.into_body(), 4096).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(body["code"], "400004");
+            assert_eq!(body["message"], message, "{locale}");
+        }
+    }
 }
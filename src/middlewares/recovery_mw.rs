@@ -1,10 +1,7 @@
-use crate::common::api_response::Response;
-use crate::middlewares::request_id_mw::request_id_from_headers;
-use axum::{
-    body::Body,
-    http::{Request, StatusCode},
-    response::Response as AxumResponse,
-};
+use crate::common::api_response::render_error;
+use crate::common::errors::{AppError, CError};
+use crate::middlewares::request_id_mw::{RequestScope, request_id_from_headers};
+use axum::{body::Body, http::Request, response::Response as AxumResponse};
 use futures_util::FutureExt;
 use std::{
     convert::Infallible,
@@ -60,20 +57,15 @@ where
         let method = req.method().clone();
         let uri = req.uri().clone();
         let req_id = request_id_from_headers(&mut headers);
+        let scope = RequestScope::new(req_id.clone(), &uri, &headers);
 
         Box::pin(async move {
             match AssertUnwindSafe(svc.call(req)).catch_unwind().await {
                 Ok(Ok(res)) => Ok(res),
-                Ok(Err(_)) => {
-                    let resp =
-                        Response::<serde_json::Value>::new_with_request_id(
-                            req_id,
-                        )
-                        .with_code("INTERNAL_ERROR")
-                        .with_message("internal web error")
-                        .with_status(StatusCode::INTERNAL_SERVER_ERROR);
-                    Ok(resp)
-                },
+                Ok(Err(_)) => Ok(render_error(
+                    &scope,
+                    &AppError::new(CError::GenericInternalServer),
+                )),
                 Err(panic_payload) => {
                     let panic_msg = if let Some(s) =
                         panic_payload.downcast_ref::<&str>()
@@ -97,20 +89,11 @@ where
                         "request panicked"
                     );
 
-                    let mut resp =
-                        Response::<serde_json::Value>::new_with_request_id(
-                            req_id,
-                        )
-                        .with_code("INTERNAL_ERROR")
-                        .with_message("internal web error")
-                        .with_meta_kv("path", uri.path())
-                        .with_meta_kv("method", method.to_string());
-
-                    if cfg!(debug_assertions) {
-                        resp = resp.with_meta_kv("panic", panic_msg);
-                    }
-
-                    Ok(resp.with_status(StatusCode::INTERNAL_SERVER_ERROR))
+                    // The panic message is logged above, never returned.
+                    Ok(render_error(
+                        &scope,
+                        &AppError::new(CError::GenericInternalServer),
+                    ))
                 },
             }
         })
@@ -1,8 +1,12 @@
+use crate::common::client_ip::TrustedProxies;
+use crate::domains::authentication::AuthenticatedPrincipal;
 use crate::middlewares::request_id_mw::request_id_from_headers;
-use axum::{body::Body, http::Request};
+use axum::{body::Body, extract::ConnectInfo, http::Request};
 use futures_util::future::BoxFuture;
 use std::{
     convert::Infallible,
+    net::SocketAddr,
+    sync::Arc,
     task::{Context, Poll},
 };
 use tokio::task_local;
@@ -11,7 +15,9 @@ use tower::{Layer, Service};
 #[derive(Debug, Clone)]
 pub struct RequestContext {
     pub request_id: String,
+    /// Subject of the authenticated principal, if any
     pub subject: Option<String>,
+    /// Client address, resolved through `server.trusted_proxies`
     pub ip: Option<String>,
     pub user_agent: Option<String>,
 }
@@ -24,17 +30,28 @@ pub fn with_ctx<R>(f: impl FnOnce(&RequestContext) -> R) -> Option<R> {
     REQ_CTX.try_with(f).ok()
 }
 
-#[derive(Clone, Default)]
-pub struct RequestContextLayer;
+#[derive(Clone)]
+pub struct RequestContextLayer {
+    trusted_proxies: Arc<TrustedProxies>,
+}
+impl RequestContextLayer {
+    pub fn new(trusted_proxies: Arc<TrustedProxies>) -> Self {
+        Self { trusted_proxies }
+    }
+}
 impl<S> Layer<S> for RequestContextLayer {
     type Service = RequestContextMw<S>;
     fn layer(&self, inner: S) -> Self::Service {
-        RequestContextMw { inner }
+        RequestContextMw {
+            inner,
+            trusted_proxies: self.trusted_proxies.clone(),
+        }
     }
 }
 #[derive(Clone)]
 pub struct RequestContextMw<S> {
     inner: S,
+    trusted_proxies: Arc<TrustedProxies>,
 }
 
 impl<S> Service<Request<Body>> for RequestContextMw<S>
@@ -54,8 +71,18 @@ where
 
     fn call(&mut self, mut req: Request<Body>) -> Self::Future {
         let rid = request_id_from_headers(req.headers_mut());
-        let subject = hdr(&req, "x-subject");
-        let ip = hdr(&req, "x-forwarded-for").or_else(|| hdr(&req, "x-real-ip"));
+        let subject = req
+            .extensions()
+            .get::<AuthenticatedPrincipal>()
+            .map(|p| p.subject.clone());
+        let peer = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let ip = self
+            .trusted_proxies
+            .client_ip(peer, req.headers())
+            .map(|ip| ip.to_string());
         let ua = hdr(&req, "user-agent");
 
         let ctx = RequestContext {
@@ -75,3 +102,46 @@ fn hdr(req: &Request<Body>, name: &str) -> Option<String> {
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use tower::ServiceExt;
+
+    async fn recorded_ip(trusted: &[String], peer: &str) -> Option<String> {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async { Json(with_ctx(|ctx| ctx.ip.clone()).flatten()) }),
+            )
+            .layer(RequestContextLayer::new(Arc::new(TrustedProxies::new(
+                trusted,
+            ))));
+        let mut req = Request::get("/")
+            .header("x-forwarded-for", "198.51.100.1")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 443)));
+
+        let res = app.oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(res.into_body(), 64).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ip_honours_forwarding_only_from_trusted_proxies() {
+        assert_eq!(
+            recorded_ip(&[], "203.0.113.9").await.as_deref(),
+            Some("203.0.113.9")
+        );
+        assert_eq!(
+            recorded_ip(&["203.0.113.0/24".to_string()], "203.0.113.9")
+                .await
+                .as_deref(),
+            Some("198.51.100.1")
+        );
+    }
+}
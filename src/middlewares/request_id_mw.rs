@@ -1,8 +1,12 @@
+use crate::common::api_response::ErrorFormat;
+use crate::common::i18n;
 use crate::constants::http::HEADER_X_REQUEST_ID;
 use axum::{
     body::Body,
     extract::FromRequestParts,
-    http::{HeaderMap, HeaderValue, Request, StatusCode, request::Parts},
+    http::{
+        HeaderMap, HeaderValue, Request, StatusCode, Uri, header, request::Parts,
+    },
 };
 use futures_util::future::ready;
 use std::{
@@ -15,6 +19,55 @@ use uuid::Uuid;
 #[derive(Clone, Debug)]
 pub struct RequestId(pub String);
 
+/// What an error response needs to know about the request it answers.
+#[derive(Clone, Debug)]
+pub struct RequestScope {
+    pub request_id: String,
+    /// Request path, used as the problem `instance`
+    pub path: String,
+    pub format: ErrorFormat,
+    /// Negotiated from `Accept-Language`
+    pub locale: &'static str,
+}
+
+tokio::task_local! {
+    static CURRENT_SCOPE: RequestScope;
+}
+
+impl RequestScope {
+    pub fn new(request_id: String, uri: &Uri, headers: &HeaderMap) -> Self {
+        Self {
+            request_id,
+            path: uri.path().to_string(),
+            format: ErrorFormat::from_accept(
+                headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()),
+            ),
+            locale: i18n::negotiate(
+                headers
+                    .get(header::ACCEPT_LANGUAGE)
+                    .and_then(|v| v.to_str().ok()),
+            ),
+        }
+    }
+
+    /// Scope of the request being handled, when called from within
+    /// `RequestIdLayer` (tasks spawned by handlers do not inherit it).
+    pub fn current() -> Option<Self> {
+        CURRENT_SCOPE.try_with(Clone::clone).ok()
+    }
+
+    /// `current()`, or an envelope-format scope in the default locale with a
+    /// fresh request id.
+    pub fn current_or_new() -> Self {
+        Self::current().unwrap_or_else(|| Self {
+            request_id: Uuid::new_v4().to_string(),
+            path: String::new(),
+            format: ErrorFormat::Envelope,
+            locale: i18n::default_locale(),
+        })
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct RequestIdLayer;
 
@@ -50,9 +103,10 @@ where
 
     fn call(&mut self, mut req: Request<Body>) -> Self::Future {
         let rid = request_id_from_headers(req.headers_mut());
+        let scope = RequestScope::new(rid.clone(), req.uri(), req.headers());
         req.extensions_mut().insert(RequestId(rid));
         let mut svc = self.inner.clone();
-        Box::pin(async move { svc.call(req).await })
+        Box::pin(CURRENT_SCOPE.scope(scope, async move { svc.call(req).await }))
     }
 }
 
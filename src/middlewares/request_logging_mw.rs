@@ -1,7 +1,9 @@
+use crate::common::client_ip::TrustedProxies;
+use crate::domains::authentication::AuthenticatedPrincipal;
 use crate::middlewares::request_id_mw::request_id_from_headers;
 use axum::{
     body::Body,
-    extract::MatchedPath,
+    extract::{ConnectInfo, MatchedPath},
     http::{HeaderMap, Request},
     response::Response as AxumResponse,
 };
@@ -9,25 +11,38 @@ use futures_util::future::BoxFuture;
 use std::{
     convert::Infallible,
     net::SocketAddr,
+    sync::Arc,
     task::{Context, Poll},
     time::Instant,
 };
 use tower::{Layer, Service};
 use tracing::{error, info};
 
-#[derive(Clone, Default)]
-pub struct RequestLoggingLayer;
+#[derive(Clone)]
+pub struct RequestLoggingLayer {
+    trusted_proxies: Arc<TrustedProxies>,
+}
+
+impl RequestLoggingLayer {
+    pub fn new(trusted_proxies: Arc<TrustedProxies>) -> Self {
+        Self { trusted_proxies }
+    }
+}
 
 impl<S> Layer<S> for RequestLoggingLayer {
     type Service = RequestLoggingMiddleware<S>;
     fn layer(&self, inner: S) -> Self::Service {
-        RequestLoggingMiddleware { inner }
+        RequestLoggingMiddleware {
+            inner,
+            trusted_proxies: self.trusted_proxies.clone(),
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct RequestLoggingMiddleware<S> {
     inner: S,
+    trusted_proxies: Arc<TrustedProxies>,
 }
 
 impl<S> Service<Request<Body>> for RequestLoggingMiddleware<S>
@@ -55,6 +70,20 @@ where
         let method = req.method().clone();
         let uri = req.uri().clone();
         let mut headers = req.headers().clone();
+        let subject = req
+            .extensions()
+            .get::<AuthenticatedPrincipal>()
+            .map(|p| p.subject.clone())
+            .unwrap_or_default();
+        let peer = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let client_ip = self
+            .trusted_proxies
+            .client_ip(peer, &headers)
+            .map(|ip| ip.to_string())
+            .unwrap_or_default();
 
         Box::pin(async move {
             let res = svc.call(req).await?;
@@ -74,15 +103,6 @@ where
             let host = header_str(&headers, "host");
 
             let request_id = request_id_from_headers(&mut headers);
-            let subject = header_str(&headers, "x-subject");
-
-            let client_ip = client_ip_from_headers(&headers)
-                .or_else(|| {
-                    res.extensions()
-                        .get::<axum::extract::ConnectInfo<SocketAddr>>()
-                        .map(|ci| ci.0.ip().to_string())
-                })
-                .unwrap_or_default();
 
             if (500..=599).contains(&status) {
                 error!(
@@ -128,23 +148,3 @@ fn header_str(headers: &HeaderMap, key: &str) -> String {
         .unwrap_or_default()
         .to_string()
 }
-
-fn client_ip_from_headers(headers: &HeaderMap) -> Option<String> {
-    // X-Forwarded-For may contain a list like "client, proxy1, proxy2"
-    if let Some(v) = headers.get("x-forwarded-for").and_then(|v| v.to_str().ok())
-    {
-        if let Some(first) = v.split(',').next() {
-            let ip = first.trim();
-            if !ip.is_empty() {
-                return Some(ip.to_string());
-            }
-        }
-    }
-    if let Some(v) = headers.get("x-real-ip").and_then(|v| v.to_str().ok()) {
-        let ip = v.trim();
-        if !ip.is_empty() {
-            return Some(ip.to_string());
-        }
-    }
-    None
-}
@@ -1,26 +1,31 @@
-use crate::common::api_response::Response;
-use crate::middlewares::request_id_mw::request_id_from_headers;
-use axum::{
-    body::Body,
-    http::{Request, StatusCode},
-    response::Response as AxumResponse,
-};
+use crate::common::api_response::render_error;
+use crate::common::errors::{AppError, CError};
+use crate::middlewares::request_id_mw::{RequestScope, request_id_from_headers};
+use axum::{body::Body, http::Request, response::Response as AxumResponse};
 use futures_util::future::BoxFuture;
 use std::{
     convert::Infallible,
     task::{Context, Poll},
     time::Duration,
 };
+use tokio::sync::watch;
 use tokio::time::timeout;
 use tower::{Layer, Service};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct TimeoutLayer {
-    duration: Duration,
+    duration: watch::Receiver<Duration>,
 }
 
 impl TimeoutLayer {
     pub fn new(duration: Duration) -> Self {
+        let (_, rx) = watch::channel(duration);
+        Self { duration: rx }
+    }
+
+    /// Read the timeout from `duration` on every request so published changes
+    /// apply without rebuilding the router.
+    pub fn watching(duration: watch::Receiver<Duration>) -> Self {
         Self { duration }
     }
 }
@@ -30,7 +35,7 @@ impl<S> Layer<S> for TimeoutLayer {
     fn layer(&self, inner: S) -> Self::Service {
         TimeoutMiddleware {
             inner,
-            duration: self.duration,
+            duration: self.duration.clone(),
         }
     }
 }
@@ -38,7 +43,7 @@ impl<S> Layer<S> for TimeoutLayer {
 #[derive(Clone)]
 pub struct TimeoutMiddleware<S> {
     inner: S,
-    duration: Duration,
+    duration: watch::Receiver<Duration>,
 }
 
 impl<S> Service<Request<Body>> for TimeoutMiddleware<S>
@@ -61,39 +66,26 @@ where
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
         let mut svc = self.inner.clone();
-        let dur = self.duration;
+        let dur = *self.duration.borrow();
 
         let mut headers = req.headers().clone();
-        let method = req.method().clone();
-        let uri = req.uri().clone();
-        let req_id = request_id_from_headers(&mut headers);
+        let scope = RequestScope::new(
+            request_id_from_headers(&mut headers),
+            req.uri(),
+            &headers,
+        );
 
         Box::pin(async move {
             match timeout(dur, svc.call(req)).await {
                 Ok(Ok(res)) => Ok(res),
-                Ok(Err(_)) => {
-                    let resp =
-                        Response::<serde_json::Value>::new_with_request_id(
-                            req_id,
-                        )
-                        .with_code("INTERNAL_ERROR")
-                        .with_message("Unhandled internal error")
-                        .with_status(StatusCode::INTERNAL_SERVER_ERROR);
-                    Ok(resp)
-                },
-                Err(_) => {
-                    let resp =
-                        Response::<serde_json::Value>::new_with_request_id(
-                            req_id,
-                        )
-                        .with_code("TIMEOUT")
-                        .with_message("Request timed out")
-                        .with_meta_kv("timeout_ms", dur.as_millis() as i64)
-                        .with_meta_kv("path", uri.path())
-                        .with_meta_kv("method", method.to_string())
-                        .with_status(StatusCode::GATEWAY_TIMEOUT);
-                    Ok(resp)
-                },
+                Ok(Err(_)) => Ok(render_error(
+                    &scope,
+                    &AppError::new(CError::GenericInternalServer),
+                )),
+                Err(_) => Ok(render_error(
+                    &scope,
+                    &AppError::new(CError::GenericRequestTimedOut),
+                )),
             }
         })
     }
@@ -0,0 +1,498 @@
+use crate::common;
+use crate::common::errors::CError;
+use crate::config::env_settings::AccountTokensConfig;
+use crate::domains::account::AccountTrait;
+use crate::infrastructures::crypto::{hash_password, random_token, sha256_hex};
+use crate::infrastructures::database::models::account_token::{
+    AccountToken, NewAccountToken,
+};
+use crate::infrastructures::database::models::security_event::NewSecurityEvent;
+use crate::infrastructures::database::models::user::User;
+use crate::infrastructures::database::schema::{
+    account_tokens, refresh_token_families, refresh_tokens, security_events,
+    users,
+};
+use crate::infrastructures::database::{DbConn, DbPool};
+use crate::infrastructures::mail::{Mail, Mailer};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use log::{error, info};
+use std::sync::Arc;
+use url::Url;
+use uuid::Uuid;
+
+const PASSWORD_RESET: &str = "password_reset";
+const EMAIL_VERIFICATION: &str = "email_verification";
+const REVOKED_ON_PASSWORD_RESET: &str = "password_reset";
+const PASSWORD_RESET_EVENT: &str = "password_reset";
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+#[derive(Clone)]
+pub struct AccountService {
+    db: &'static DbPool,
+    mailer: Arc<dyn Mailer>,
+    password_reset_ttl: Duration,
+    email_verification_ttl: Duration,
+    password_reset_url: String,
+    email_verification_url: String,
+}
+
+impl AccountService {
+    pub fn new(
+        db: &'static DbPool, mailer: Arc<dyn Mailer>, cfg: &AccountTokensConfig,
+    ) -> Self {
+        Self {
+            db,
+            mailer,
+            password_reset_ttl: Duration::seconds(
+                cfg.password_reset_ttl_seconds as i64,
+            ),
+            email_verification_ttl: Duration::seconds(
+                cfg.email_verification_ttl_seconds as i64,
+            ),
+            password_reset_url: cfg.password_reset_url.clone(),
+            email_verification_url: cfg.email_verification_url.clone(),
+        }
+    }
+
+    async fn conn(&self) -> common::errors::Result<DbConn> {
+        self.db.get().await.map_err(|e| {
+            error!("failed to get database connection: {e}");
+            CError::InvalidDatabaseClient
+        })
+    }
+
+    /// Store a new `purpose` token for `user_id`, replacing any unused one,
+    /// and return the link that redeems it.
+    async fn issue(
+        &self, conn: &mut DbConn, user_id: Uuid, email: &str,
+        purpose: &'static str,
+    ) -> common::errors::Result<String> {
+        let (ttl, base_url) = match purpose {
+            PASSWORD_RESET => (self.password_reset_ttl, &self.password_reset_url),
+            _ => (self.email_verification_ttl, &self.email_verification_url),
+        };
+        let token = random_token(32);
+        let link = Url::parse_with_params(base_url, [("token", &token)])
+            .map_err(|e| {
+                error!("invalid {purpose} url {base_url:?}: {e}");
+                CError::GenericInternalServer
+            })?;
+
+        let token_hash = sha256_hex(&token);
+        let email = email.to_string();
+        let expires_at = Utc::now() + ttl;
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            replace_token(conn, user_id, purpose, token_hash, email, expires_at)
+                .scope_boxed()
+        })
+        .await
+        .map_err(db_error)?;
+        Ok(link.into())
+    }
+
+    /// Issue and mail a reset link to every active account registered with
+    /// `email`. Runs after the response is sent, so whether the address is
+    /// known does not show in the response time.
+    async fn send_password_reset(
+        &self, email: &str,
+    ) -> common::errors::Result<()> {
+        let mut conn = self.conn().await?;
+        let user_ids: Vec<Uuid> = users::table
+            .filter(users::email.eq(email))
+            .filter(users::is_active.eq(true))
+            .select(users::id)
+            .load(&mut conn)
+            .await
+            .map_err(db_error)?;
+
+        for user_id in user_ids {
+            let link = self
+                .issue(&mut conn, user_id, email, PASSWORD_RESET)
+                .await?;
+            let mail = Mail {
+                to: email.to_string(),
+                subject: "Reset your password".to_string(),
+                body: format!(
+                    "A password reset was requested for your account.\n\n\
+                     Open this link within {} minutes to choose a new password:\n\
+                     {link}\n\n\
+                     If you did not request this, you can ignore this message.\n",
+                    self.password_reset_ttl.num_minutes()
+                ),
+            };
+            if let Err(e) = self.mailer.send(mail).await {
+                error!("failed to send mail: {e:#}");
+            }
+            info!("password reset requested for {user_id}");
+        }
+        Ok(())
+    }
+
+    /// Send `mail` in the background so the response time does not depend on
+    /// the mail relay or on whether a message was sent at all.
+    fn deliver(&self, mail: Mail) {
+        let mailer = self.mailer.clone();
+        tokio::spawn(async move {
+            if let Err(e) = mailer.send(mail).await {
+                error!("failed to send mail: {e:#}");
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl AccountTrait for AccountService {
+    async fn request_password_reset(
+        &self, email: String,
+    ) -> common::errors::Result<()> {
+        let svc = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = svc.send_password_reset(email.trim()).await {
+                error!("failed to process password reset request: {e}");
+            }
+        });
+        Ok(())
+    }
+
+    async fn reset_password(
+        &self, token: String, new_password: String,
+    ) -> common::errors::Result<()> {
+        if new_password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(CError::WeakPassword);
+        }
+        let password_hash =
+            tokio::task::spawn_blocking(move || hash_password(&new_password))
+                .await
+                .map_err(|e| {
+                    error!("password hashing panicked: {e}");
+                    CError::GenericInternalServer
+                })?
+                .map_err(|e| {
+                    error!("failed to hash password: {e}");
+                    CError::GenericInternalServer
+                })?;
+
+        let mut conn = self.conn().await?;
+        let token_hash = sha256_hex(&token);
+        let user_id = conn
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                reset(conn, token_hash, password_hash, Utc::now()).scope_boxed()
+            })
+            .await
+            .map_err(db_error)?
+            .ok_or(CError::InvalidAccountToken)?;
+
+        info!("password reset completed for {user_id}");
+        Ok(())
+    }
+
+    async fn request_email_verification(
+        &self, user_id: Uuid,
+    ) -> common::errors::Result<()> {
+        let mut conn = self.conn().await?;
+        let user: User = users::table
+            .find(user_id)
+            .select(User::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(db_error)?
+            .ok_or(CError::UserNotFound)?;
+        let Some(email) = user.email else {
+            return Err(CError::GenericBadRequest);
+        };
+        if user.email_verified_at.is_some() {
+            return Err(CError::EmailAlreadyVerified);
+        }
+
+        let link = self
+            .issue(&mut conn, user_id, &email, EMAIL_VERIFICATION)
+            .await?;
+        self.deliver(Mail {
+            to: email,
+            subject: "Verify your email address".to_string(),
+            body: format!(
+                "Open this link within {} hours to verify your email address:\n\
+                 {link}\n",
+                self.email_verification_ttl.num_hours()
+            ),
+        });
+        Ok(())
+    }
+
+    async fn verify_email(&self, token: String) -> common::errors::Result<()> {
+        let mut conn = self.conn().await?;
+        let token_hash = sha256_hex(&token);
+        let user_id = conn
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                async move {
+                    let now = Utc::now();
+                    let Some(token) =
+                        redeem(conn, EMAIL_VERIFICATION, token_hash, now).await?
+                    else {
+                        return Ok(None);
+                    };
+                    let updated = diesel::update(
+                        users::table
+                            .find(token.user_id)
+                            .filter(users::email.eq(&token.email)),
+                    )
+                    .set(users::email_verified_at.eq(now))
+                    .execute(conn)
+                    .await?;
+                    Ok((updated > 0).then_some(token.user_id))
+                }
+                .scope_boxed()
+            })
+            .await
+            .map_err(db_error)?
+            .ok_or(CError::InvalidAccountToken)?;
+
+        info!("email verified for {user_id}");
+        Ok(())
+    }
+}
+
+/// Store a new token for `user_id`, dropping its unused `purpose` tokens.
+async fn replace_token(
+    conn: &mut DbConn, user_id: Uuid, purpose: &'static str, token_hash: String,
+    email: String, expires_at: DateTime<Utc>,
+) -> diesel::QueryResult<()> {
+    diesel::delete(
+        account_tokens::table
+            .filter(account_tokens::user_id.eq(user_id))
+            .filter(account_tokens::purpose.eq(purpose))
+            .filter(account_tokens::used_at.is_null()),
+    )
+    .execute(conn)
+    .await?;
+    diesel::insert_into(account_tokens::table)
+        .values(NewAccountToken {
+            id: Uuid::new_v4(),
+            user_id,
+            purpose,
+            token_hash: &token_hash,
+            email: &email,
+            expires_at,
+        })
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Consume the unused, unexpired `purpose` token hashed as `token_hash`.
+async fn redeem(
+    conn: &mut DbConn, purpose: &str, token_hash: String, now: DateTime<Utc>,
+) -> diesel::QueryResult<Option<AccountToken>> {
+    let token: Option<AccountToken> = account_tokens::table
+        .filter(account_tokens::token_hash.eq(token_hash))
+        .filter(account_tokens::purpose.eq(purpose))
+        .filter(account_tokens::used_at.is_null())
+        .filter(account_tokens::expires_at.gt(now))
+        .select(AccountToken::as_select())
+        .for_update()
+        .first(conn)
+        .await
+        .optional()?;
+    if let Some(token) = &token {
+        diesel::update(account_tokens::table.find(token.id))
+            .set(account_tokens::used_at.eq(now))
+            .execute(conn)
+            .await?;
+    }
+    Ok(token)
+}
+
+/// Redeem a password reset token and replace the password of its user.
+/// Returns the user id, or `None` when the token or account is not valid.
+async fn reset(
+    conn: &mut DbConn, token_hash: String, password_hash: String,
+    now: DateTime<Utc>,
+) -> diesel::QueryResult<Option<Uuid>> {
+    let Some(token) = redeem(conn, PASSWORD_RESET, token_hash, now).await? else {
+        return Ok(None);
+    };
+    let user_id = token.user_id;
+
+    let updated = diesel::update(
+        users::table.find(user_id).filter(users::is_active.eq(true)),
+    )
+    .set((
+        users::password_hash.eq(password_hash),
+        users::updated_at.eq(now),
+        users::failed_login_count.eq(0),
+        users::last_failed_login_at.eq(None::<DateTime<Utc>>),
+        users::locked_until.eq(None::<DateTime<Utc>>),
+    ))
+    .execute(conn)
+    .await?;
+    if updated == 0 {
+        return Ok(None);
+    }
+
+    diesel::update(
+        refresh_token_families::table
+            .filter(refresh_token_families::user_id.eq(user_id))
+            .filter(refresh_token_families::revoked_at.is_null()),
+    )
+    .set((
+        refresh_token_families::revoked_at.eq(now),
+        refresh_token_families::revoked_reason.eq(REVOKED_ON_PASSWORD_RESET),
+    ))
+    .execute(conn)
+    .await?;
+    diesel::update(
+        refresh_tokens::table
+            .filter(refresh_tokens::user_id.eq(user_id))
+            .filter(refresh_tokens::revoked_at.is_null()),
+    )
+    .set(refresh_tokens::revoked_at.eq(now))
+    .execute(conn)
+    .await?;
+    diesel::insert_into(security_events::table)
+        .values(NewSecurityEvent {
+            id: Uuid::new_v4(),
+            user_id: Some(user_id),
+            kind: PASSWORD_RESET_EVENT,
+            detail: "password reset; refresh tokens revoked",
+        })
+        .execute(conn)
+        .await?;
+    Ok(Some(user_id))
+}
+
+fn db_error(e: diesel::result::Error) -> CError {
+    error!("database query failed: {e}");
+    CError::GenericInternalServer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructures::crypto::verify_password;
+    use crate::infrastructures::database::models::refresh_token::{
+        NewRefreshToken, NewRefreshTokenFamily,
+    };
+    use crate::infrastructures::database::testing::{insert_user, test_pool};
+    use crate::infrastructures::mail::memory::MemoryMailer;
+
+    async fn service() -> (AccountService, Arc<MemoryMailer>) {
+        let mailer = Arc::new(MemoryMailer::new());
+        let svc = AccountService::new(
+            test_pool().await,
+            mailer.clone(),
+            &AccountTokensConfig::default(),
+        );
+        (svc, mailer)
+    }
+
+    /// The `token` query parameter of the link in `mail`.
+    fn token_in(mail: &Mail) -> String {
+        let link = mail
+            .body
+            .split_whitespace()
+            .find(|word| word.starts_with("http"))
+            .unwrap();
+        Url::parse(link)
+            .unwrap()
+            .query_pairs()
+            .find(|(k, _)| k == "token")
+            .unwrap()
+            .1
+            .into_owned()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_password_reset_is_single_use_and_revokes_sessions() {
+        let (svc, mailer) = service().await;
+        let mut conn = svc.conn().await.unwrap();
+        let email = format!("{}@example.com", Uuid::new_v4());
+        let user_id = insert_user(&mut conn, Some(&email), "old-password").await;
+        let family_id = Uuid::new_v4();
+        diesel::insert_into(refresh_token_families::table)
+            .values(NewRefreshTokenFamily {
+                id: family_id,
+                user_id,
+            })
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        diesel::insert_into(refresh_tokens::table)
+            .values(NewRefreshToken {
+                id: Uuid::new_v4(),
+                user_id,
+                token_hash: &sha256_hex(&random_token(32)),
+                expires_at: Utc::now() + Duration::days(1),
+                family_id,
+            })
+            .execute(&mut conn)
+            .await
+            .unwrap();
+
+        svc.send_password_reset(&email).await.unwrap();
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, email);
+        let token = token_in(&sent[0]);
+
+        svc.reset_password(token.clone(), "new-password".to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            svc.reset_password(token, "other-password".to_string())
+                .await,
+            Err(CError::InvalidAccountToken)
+        );
+
+        let hash: String = users::table
+            .find(user_id)
+            .select(users::password_hash)
+            .first(&mut conn)
+            .await
+            .unwrap();
+        assert!(verify_password("new-password", &hash));
+        let reason: Option<String> = refresh_token_families::table
+            .find(family_id)
+            .select(refresh_token_families::revoked_reason)
+            .first(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(reason.as_deref(), Some(REVOKED_ON_PASSWORD_RESET));
+        let live: i64 = refresh_tokens::table
+            .filter(refresh_tokens::user_id.eq(user_id))
+            .filter(refresh_tokens::revoked_at.is_null())
+            .count()
+            .get_result(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(live, 0);
+        let events: Vec<String> = security_events::table
+            .filter(security_events::user_id.eq(user_id))
+            .select(security_events::kind)
+            .load(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(events, vec![PASSWORD_RESET_EVENT]);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_password_reset_for_unknown_email_sends_nothing() {
+        let (svc, mailer) = service().await;
+        let email = format!("{}@example.com", Uuid::new_v4());
+
+        svc.send_password_reset(&email).await.unwrap();
+
+        assert!(mailer.sent().is_empty());
+        assert_eq!(
+            svc.reset_password(random_token(32), "new-password".to_string())
+                .await,
+            Err(CError::InvalidAccountToken)
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use crate::common;
+use crate::common::errors::CError;
+use crate::domains::api_key::{ApiKeyInfo, ApiKeyTrait, CreatedApiKey};
+use crate::domains::authentication::AuthenticatedPrincipal;
+use crate::domains::listing::{FilterValue, ListParams, Page};
+use crate::infrastructures::crypto::{
+    constant_time_eq, random_token, sha256_hex,
+};
+use crate::infrastructures::database::listing::{filter_by_op, order_by_sort};
+use crate::infrastructures::database::models::api_key::{ApiKey, NewApiKey};
+use crate::infrastructures::database::schema::api_keys;
+use crate::infrastructures::database::{DbConn, DbPool};
+use async_trait::async_trait;
+use chrono::{Duration, TimeDelta, Utc};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use log::{error, warn};
+use uuid::Uuid;
+
+/// Keys look like `ak_<prefix>.<secret>`.
+const KEY_MARKER: &str = "ak_";
+const API_KEY_ISSUER: &str = "api-key";
+/// `last_used_at` is written at most this often per key.
+const LAST_USED_RESOLUTION_SECONDS: i64 = 60;
+
+pub struct ApiKeyService {
+    db: &'static DbPool,
+}
+
+impl ApiKeyService {
+    pub fn new(db: &'static DbPool) -> Self {
+        Self { db }
+    }
+
+    async fn conn(&self) -> common::errors::Result<DbConn> {
+        self.db.get().await.map_err(|e| {
+            error!("failed to get database connection: {e}");
+            CError::InvalidDatabaseClient
+        })
+    }
+
+    /// Record use without delaying the request.
+    fn touch(&self, id: Uuid) {
+        let db = self.db;
+        tokio::spawn(async move {
+            let Ok(mut conn) = db.get().await else {
+                return;
+            };
+            let now = Utc::now();
+            let stale = now - Duration::seconds(LAST_USED_RESOLUTION_SECONDS);
+            let result = diesel::update(
+                api_keys::table.filter(api_keys::id.eq(id)).filter(
+                    api_keys::last_used_at
+                        .is_null()
+                        .or(api_keys::last_used_at.lt(stale)),
+                ),
+            )
+            .set(api_keys::last_used_at.eq(now))
+            .execute(&mut conn)
+            .await;
+            if let Err(e) = result {
+                warn!("failed to record api key use: {e}");
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl ApiKeyTrait for ApiKeyService {
+    async fn create(
+        &self, name: String, scopes: Vec<String>, expires_in: Option<u64>,
+        created_by: String,
+    ) -> common::errors::Result<CreatedApiKey> {
+        let expires_at = match expires_in {
+            Some(seconds) => Some(
+                i64::try_from(seconds)
+                    .ok()
+                    .and_then(TimeDelta::try_seconds)
+                    .and_then(|ttl| Utc::now().checked_add_signed(ttl))
+                    .ok_or(CError::GenericBadRequest)?,
+            ),
+            None => None,
+        };
+        let mut conn = self.conn().await?;
+
+        let prefix = random_token(6);
+        let key = format!("{KEY_MARKER}{prefix}.{}", random_token(32));
+        let key_hash = sha256_hex(&key);
+
+        let row: ApiKey = diesel::insert_into(api_keys::table)
+            .values(NewApiKey {
+                id: Uuid::new_v4(),
+                name: &name,
+                prefix: &prefix,
+                key_hash: &key_hash,
+                scopes: &scopes,
+                created_by: &created_by,
+                expires_at,
+            })
+            .returning(ApiKey::as_returning())
+            .get_result(&mut conn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(CreatedApiKey {
+            info: info(row),
+            key,
+        })
+    }
+
+    async fn list(
+        &self, params: &ListParams,
+    ) -> common::errors::Result<Page<ApiKeyInfo>> {
+        let mut conn = self.conn().await?;
+        let total: i64 = filtered(params)
+            .count()
+            .get_result(&mut conn)
+            .await
+            .map_err(db_error)?;
+        let rows: Vec<ApiKey> = order_by_sort!(filtered(params), &params.sort, {
+            "created_at" => api_keys::created_at,
+            "name" => api_keys::name,
+            "expires_at" => api_keys::expires_at,
+            "last_used_at" => api_keys::last_used_at,
+        })
+        .then_order_by(api_keys::id)
+        .limit(params.limit())
+        .offset(params.offset())
+        .select(ApiKey::as_select())
+        .load(&mut conn)
+        .await
+        .map_err(db_error)?;
+        Ok(Page {
+            items: rows.into_iter().map(info).collect(),
+            total,
+        })
+    }
+
+    async fn revoke(&self, id: Uuid) -> common::errors::Result<()> {
+        let mut conn = self.conn().await?;
+        let updated = diesel::update(
+            api_keys::table
+                .filter(api_keys::id.eq(id))
+                .filter(api_keys::revoked_at.is_null()),
+        )
+        .set(api_keys::revoked_at.eq(Utc::now()))
+        .execute(&mut conn)
+        .await
+        .map_err(db_error)?;
+        match updated {
+            0 => Err(CError::ApiKeyNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    async fn authenticate(
+        &self, key: &str,
+    ) -> common::errors::Result<AuthenticatedPrincipal> {
+        let prefix = key
+            .strip_prefix(KEY_MARKER)
+            .and_then(|rest| rest.split_once('.'))
+            .map(|(prefix, _)| prefix)
+            .ok_or(CError::GenericUnauthorized)?;
+
+        let mut conn = self.conn().await?;
+        let row: ApiKey = api_keys::table
+            .filter(api_keys::prefix.eq(prefix))
+            .select(ApiKey::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(db_error)?
+            .ok_or(CError::GenericUnauthorized)?;
+
+        let now = Utc::now();
+        if !constant_time_eq(&row.key_hash, &sha256_hex(key))
+            || row.revoked_at.is_some()
+            || row.expires_at.is_some_and(|exp| exp <= now)
+        {
+            return Err(CError::GenericUnauthorized);
+        }
+        self.touch(row.id);
+
+        Ok(AuthenticatedPrincipal {
+            subject: format!("{API_KEY_ISSUER}:{}", row.id),
+            issuer: API_KEY_ISSUER.to_string(),
+            username: Some(row.name),
+            roles: Vec::new(),
+            scopes: row.scopes,
+            token_id: Some(row.id.to_string()),
+            expires_at: row.expires_at.map(|exp| exp.timestamp()),
+        })
+    }
+}
+
+fn info(row: ApiKey) -> ApiKeyInfo {
+    ApiKeyInfo {
+        id: row.id,
+        name: row.name,
+        prefix: row.prefix,
+        scopes: row.scopes,
+        created_by: row.created_by,
+        expires_at: row.expires_at,
+        last_used_at: row.last_used_at,
+        revoked_at: row.revoked_at,
+        created_at: row.created_at,
+    }
+}
+
+/// Keys matching the filters of `params`, see `ApiKeyListing`.
+fn filtered<'a>(params: &ListParams) -> api_keys::BoxedQuery<'a, Pg> {
+    let now = Utc::now();
+    let mut query = api_keys::table.into_boxed();
+    for filter in &params.filters {
+        query = match (filter.field, &filter.value) {
+            ("status", FilterValue::Text(status)) => match status.as_str() {
+                "revoked" => query.filter(api_keys::revoked_at.is_not_null()),
+                "expired" => query
+                    .filter(api_keys::revoked_at.is_null())
+                    .filter(api_keys::expires_at.le(now)),
+                _ => query.filter(api_keys::revoked_at.is_null()).filter(
+                    api_keys::expires_at
+                        .is_null()
+                        .or(api_keys::expires_at.gt(now)),
+                ),
+            },
+            ("created_by", FilterValue::Text(subject)) => {
+                filter_by_op!(
+                    query,
+                    api_keys::created_by,
+                    filter.op,
+                    subject.clone()
+                )
+            },
+            ("created_at", FilterValue::Timestamp(at)) => {
+                filter_by_op!(query, api_keys::created_at, filter.op, *at)
+            },
+            _ => query,
+        };
+    }
+    query
+}
+
+fn db_error(e: diesel::result::Error) -> CError {
+    error!("database query failed: {e}");
+    CError::GenericInternalServer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructures::database::testing::unconnected_pool;
+
+    #[tokio::test]
+    async fn test_create_rejects_unrepresentable_expiry() {
+        let svc = ApiKeyService::new(unconnected_pool());
+        for expires_in in [u64::MAX, i64::MAX as u64] {
+            let err = svc
+                .create("ci".into(), Vec::new(), Some(expires_in), "admin".into())
+                .await
+                .unwrap_err();
+            assert_eq!(err, CError::GenericBadRequest);
+        }
+    }
+}
@@ -1,14 +1,639 @@
-use crate::common::api_response::BaseOutput;
-use crate::domains::authentication::AuthenticationTrait;
+use crate::common;
+use crate::common::errors::CError;
+use crate::domains::authentication::{
+    AuthTokens, AuthenticationTrait, LoginOutcome, MfaLogin,
+};
+use crate::domains::lockout::LockoutTrait;
+use crate::domains::mfa::MfaTrait;
+use crate::infrastructures::crypto::{
+    hash_password, random_token, sha256_hex, verify_password,
+};
+use crate::infrastructures::database::models::refresh_token::{
+    NewRefreshToken, NewRefreshTokenFamily, RefreshToken, RefreshTokenFamily,
+};
+use crate::infrastructures::database::models::security_event::NewSecurityEvent;
+use crate::infrastructures::database::models::user::User;
+use crate::infrastructures::database::schema::{
+    refresh_token_families, refresh_tokens, security_events, user_roles, users,
+};
+use crate::infrastructures::database::{DbConn, DbPool};
+use crate::infrastructures::jwt::JwtKeys;
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use log::error;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
 
-pub struct AuthenticationService {}
+const TOKEN_TYPE: &str = "Bearer";
+const REVOKED_ON_LOGOUT: &str = "logout";
+const REVOKED_ON_REUSE: &str = "reuse_detected";
+const REFRESH_TOKEN_REUSE_EVENT: &str = "refresh_token_reuse";
+
+/// Result of exchanging a refresh token, decided inside one transaction so a
+/// reuse revocation is committed even though the request is rejected.
+enum Rotation {
+    Rotated { user: User, refresh_token: String },
+    Rejected,
+    ReuseDetected { user_id: Uuid, family_id: Uuid },
+}
+
+/// Verified when the username is unknown so both paths cost the same.
+static DUMMY_HASH: Lazy<String> = Lazy::new(|| {
+    hash_password("dummy-password").expect("argon2 hashing must succeed")
+});
+
+pub struct AuthenticationService {
+    db: &'static DbPool,
+    jwt: Arc<JwtKeys>,
+    mfa: Arc<dyn MfaTrait>,
+    lockout: Arc<dyn LockoutTrait>,
+    /// Refresh token lifetime in seconds
+    refresh_ttl: u64,
+}
 
 impl AuthenticationService {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(
+        db: &'static DbPool, jwt: Arc<JwtKeys>, mfa: Arc<dyn MfaTrait>,
+        lockout: Arc<dyn LockoutTrait>, refresh_ttl: u64,
+    ) -> Self {
+        Self {
+            db,
+            jwt,
+            mfa,
+            lockout,
+            refresh_ttl,
+        }
+    }
+
+    async fn conn(&self) -> common::errors::Result<DbConn> {
+        self.db.get().await.map_err(|e| {
+            error!("failed to get database connection: {e}");
+            CError::InvalidDatabaseClient
+        })
+    }
+
+    /// Sign an access token carrying the user's current roles.
+    async fn access_token(
+        &self, conn: &mut DbConn, user: &User,
+    ) -> common::errors::Result<String> {
+        let roles: Vec<String> = user_roles::table
+            .filter(user_roles::user_id.eq(user.id))
+            .select(user_roles::role)
+            .load(conn)
+            .await
+            .map_err(db_error)?;
+        self.jwt
+            .issue(&user.id.to_string(), Some(&user.username), &roles)
+            .map_err(|e| {
+                error!("failed to sign access token: {e}");
+                CError::GenericInternalServer
+            })
+    }
+
+    /// Start a new refresh token family for `user` and issue its first
+    /// token pair. The family and its first token are written together so a
+    /// failure leaves no empty family behind.
+    async fn start_family(
+        &self, conn: &mut DbConn, user: &User,
+    ) -> common::errors::Result<AuthTokens> {
+        let user_id = user.id;
+        let refresh_ttl = self.refresh_ttl;
+        let refresh_token = conn
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                async move {
+                    let family_id = Uuid::new_v4();
+                    diesel::insert_into(refresh_token_families::table)
+                        .values(NewRefreshTokenFamily {
+                            id: family_id,
+                            user_id,
+                        })
+                        .execute(conn)
+                        .await?;
+                    insert_refresh_token(conn, user_id, family_id, refresh_ttl)
+                        .await
+                }
+                .scope_boxed()
+            })
+            .await
+            .map_err(db_error)?;
+        self.tokens(conn, user, refresh_token).await
+    }
+
+    /// Sign an access token for `user` and pair it with `refresh_token`.
+    async fn tokens(
+        &self, conn: &mut DbConn, user: &User, refresh_token: String,
+    ) -> common::errors::Result<AuthTokens> {
+        Ok(AuthTokens {
+            access_token: self.access_token(conn, user).await?,
+            token_type: TOKEN_TYPE.to_string(),
+            expires_in: self.jwt.access_ttl(),
+            refresh_token,
+            refresh_expires_in: self.refresh_ttl,
+        })
     }
 }
 
 #[async_trait]
-impl AuthenticationTrait for AuthenticationService {}
+impl AuthenticationTrait for AuthenticationService {
+    async fn login(
+        &self, username: String, password: String, client_ip: String,
+    ) -> common::errors::Result<LoginOutcome> {
+        let mut conn = self.conn().await?;
+
+        let user: Option<User> = users::table
+            .filter(users::username.eq(&username))
+            .select(User::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(db_error)?;
+        let user_id = user.as_ref().map(|u| u.id);
+
+        // Checked before the password so a throttled caller learns nothing.
+        if let Some(retry_after) =
+            self.lockout.retry_after(user_id, &client_ip).await?
+        {
+            return Ok(LoginOutcome::Throttled(retry_after));
+        }
+
+        let hash = user
+            .as_ref()
+            .map(|u| u.password_hash.clone())
+            .unwrap_or_else(|| DUMMY_HASH.clone());
+        let verified = tokio::task::spawn_blocking(move || {
+            verify_password(&password, &hash)
+        })
+        .await
+        .unwrap_or(false);
+        if !verified {
+            self.lockout.record_failure(user_id, &client_ip).await?;
+            return Err(CError::GenericUnauthorized);
+        }
+
+        match user {
+            Some(user) if user.is_active => {
+                match self.mfa.challenge(user.id).await? {
+                    // The count is cleared once the second factor succeeds.
+                    Some(challenge) => Ok(LoginOutcome::MfaRequired(challenge)),
+                    None => {
+                        self.lockout.record_success(user.id).await?;
+                        self.start_family(&mut conn, &user)
+                            .await
+                            .map(LoginOutcome::Tokens)
+                    },
+                }
+            },
+            _ => Err(CError::GenericUnauthorized),
+        }
+    }
+
+    async fn verify_mfa(
+        &self, mfa_token: String, code: String, client_ip: String,
+    ) -> common::errors::Result<MfaLogin> {
+        // Read first: a failed redeem may discard the challenge.
+        let user_id = self.mfa.challenged_user(&mfa_token).await;
+        let redemption = match self.mfa.redeem(&mfa_token, &code).await {
+            Ok(redemption) => redemption,
+            Err(err) => {
+                if err.status().is_client_error() {
+                    self.lockout.record_failure(user_id, &client_ip).await?;
+                }
+                return Err(err);
+            },
+        };
+        self.lockout.record_success(redemption.user_id).await?;
+
+        let mut conn = self.conn().await?;
+        let user: User = users::table
+            .find(redemption.user_id)
+            .select(User::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(db_error)?
+            .filter(|user| user.is_active)
+            .ok_or(CError::GenericUnauthorized)?;
+
+        Ok(MfaLogin {
+            tokens: self.start_family(&mut conn, &user).await?,
+            recovery_codes: redemption.recovery_codes,
+        })
+    }
+
+    async fn logout(&self, refresh_token: String) -> common::errors::Result<()> {
+        let mut conn = self.conn().await?;
+
+        let family_id: Option<Uuid> = refresh_tokens::table
+            .filter(refresh_tokens::token_hash.eq(sha256_hex(&refresh_token)))
+            .select(refresh_tokens::family_id)
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(db_error)?;
+        if let Some(family_id) = family_id {
+            revoke_family(&mut conn, family_id, REVOKED_ON_LOGOUT, Utc::now())
+                .await
+                .map_err(db_error)?;
+        }
+        Ok(())
+    }
+
+    async fn refresh(
+        &self, refresh_token: String,
+    ) -> common::errors::Result<AuthTokens> {
+        let mut conn = self.conn().await?;
+        let now = Utc::now();
+        let token_hash = sha256_hex(&refresh_token);
+        let refresh_ttl = self.refresh_ttl;
+
+        let rotation = conn
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                rotate(conn, token_hash, now, refresh_ttl).scope_boxed()
+            })
+            .await
+            .map_err(db_error)?;
+
+        match rotation {
+            Rotation::Rotated {
+                user,
+                refresh_token,
+            } => self.tokens(&mut conn, &user, refresh_token).await,
+            Rotation::Rejected => Err(CError::GenericUnauthorized),
+            Rotation::ReuseDetected { user_id, family_id } => {
+                warn!(
+                    user_id = %user_id,
+                    family_id = %family_id,
+                    "refresh token reuse detected; token family revoked",
+                );
+                Err(CError::GenericUnauthorized)
+            },
+        }
+    }
+}
+
+/// Exchange the token hashed as `token_hash` for a new one in the same
+/// family. The row is locked so concurrent presentations serialize and
+/// the second one is seen as reuse.
+async fn rotate(
+    conn: &mut DbConn, token_hash: String, now: DateTime<Utc>, refresh_ttl: u64,
+) -> diesel::QueryResult<Rotation> {
+    let row: Option<(RefreshToken, RefreshTokenFamily, User)> =
+        refresh_tokens::table
+            .inner_join(refresh_token_families::table)
+            .inner_join(users::table)
+            .filter(refresh_tokens::token_hash.eq(token_hash))
+            .select((
+                RefreshToken::as_select(),
+                RefreshTokenFamily::as_select(),
+                User::as_select(),
+            ))
+            .for_update()
+            .first(conn)
+            .await
+            .optional()?;
+    let Some((stored, family, user)) = row else {
+        return Ok(Rotation::Rejected);
+    };
+
+    if stored.used_at.is_some() {
+        if family.revoked_at.is_none() {
+            revoke_family(conn, family.id, REVOKED_ON_REUSE, now).await?;
+            diesel::insert_into(security_events::table)
+                .values(NewSecurityEvent {
+                    id: Uuid::new_v4(),
+                    user_id: Some(user.id),
+                    kind: REFRESH_TOKEN_REUSE_EVENT,
+                    detail: &format!(
+                        "refresh token {} reused; family {} revoked",
+                        stored.id, family.id
+                    ),
+                })
+                .execute(conn)
+                .await?;
+        }
+        return Ok(Rotation::ReuseDetected {
+            user_id: user.id,
+            family_id: family.id,
+        });
+    }
+
+    if stored.revoked_at.is_some()
+        || stored.expires_at <= now
+        || family.revoked_at.is_some()
+        || !user.is_active
+    {
+        return Ok(Rotation::Rejected);
+    }
+
+    diesel::update(refresh_tokens::table.find(stored.id))
+        .set(refresh_tokens::used_at.eq(now))
+        .execute(conn)
+        .await?;
+    let refresh_token =
+        insert_refresh_token(conn, user.id, family.id, refresh_ttl).await?;
+    Ok(Rotation::Rotated {
+        user,
+        refresh_token,
+    })
+}
+
+/// Persist a new refresh token in `family_id` and return its plaintext.
+async fn insert_refresh_token(
+    conn: &mut DbConn, user_id: Uuid, family_id: Uuid, refresh_ttl: u64,
+) -> diesel::QueryResult<String> {
+    let refresh_token = random_token(32);
+    let token_hash = sha256_hex(&refresh_token);
+
+    diesel::insert_into(refresh_tokens::table)
+        .values(NewRefreshToken {
+            id: Uuid::new_v4(),
+            user_id,
+            token_hash: &token_hash,
+            expires_at: Utc::now() + Duration::seconds(refresh_ttl as i64),
+            family_id,
+        })
+        .execute(conn)
+        .await?;
+    Ok(refresh_token)
+}
+
+/// Revoke a token family and every token in it.
+async fn revoke_family(
+    conn: &mut DbConn, family_id: Uuid, reason: &str, now: DateTime<Utc>,
+) -> diesel::QueryResult<()> {
+    diesel::update(
+        refresh_token_families::table
+            .find(family_id)
+            .filter(refresh_token_families::revoked_at.is_null()),
+    )
+    .set((
+        refresh_token_families::revoked_at.eq(now),
+        refresh_token_families::revoked_reason.eq(reason),
+    ))
+    .execute(conn)
+    .await?;
+    diesel::update(
+        refresh_tokens::table
+            .filter(refresh_tokens::family_id.eq(family_id))
+            .filter(refresh_tokens::revoked_at.is_null()),
+    )
+    .set(refresh_tokens::revoked_at.eq(now))
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+fn db_error(e: diesel::result::Error) -> CError {
+    error!("database query failed: {e}");
+    CError::GenericInternalServer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::env_settings::AuthConfig;
+    use crate::domains::authentication::AuthenticatedPrincipal;
+    use crate::domains::mfa::{
+        MfaChallenge, MfaRedemption, MfaSubject, TotpEnrollment,
+    };
+    use crate::infrastructures::database::testing::{insert_user, test_pool};
+    use crate::infrastructures::jwt::signing_key::SigningKey;
+    use std::sync::Mutex;
+
+    const CLIENT_IP: &str = "203.0.113.9";
+    const GOOD_CODE: &str = "123456";
+
+    /// Challenges every login; `GOOD_CODE` redeems it. Every user counts as
+    /// already enrolled.
+    struct FakeMfa {
+        user_id: Mutex<Option<Uuid>>,
+    }
+
+    #[async_trait]
+    impl MfaTrait for FakeMfa {
+        async fn challenge(
+            &self, user_id: Uuid,
+        ) -> common::errors::Result<Option<MfaChallenge>> {
+            *self.user_id.lock().unwrap() = Some(user_id);
+            Ok(Some(MfaChallenge::default()))
+        }
+
+        async fn redeem(
+            &self, _: &str, code: &str,
+        ) -> common::errors::Result<MfaRedemption> {
+            match (code, *self.user_id.lock().unwrap()) {
+                (GOOD_CODE, Some(user_id)) => Ok(MfaRedemption {
+                    user_id,
+                    recovery_codes: Vec::new(),
+                }),
+                _ => Err(CError::InvalidMfaCode),
+            }
+        }
+
+        async fn challenged_user(&self, _: &str) -> Option<Uuid> {
+            *self.user_id.lock().unwrap()
+        }
+
+        async fn enroll(
+            &self, _: MfaSubject,
+        ) -> common::errors::Result<TotpEnrollment> {
+            Err(CError::MfaAlreadyEnabled)
+        }
+
+        async fn activate(
+            &self, _: Uuid, _: &str,
+        ) -> common::errors::Result<Vec<String>> {
+            Err(CError::MfaAlreadyEnabled)
+        }
+
+        async fn regenerate_recovery_codes(
+            &self, _: Uuid, _: &str,
+        ) -> common::errors::Result<Vec<String>> {
+            Ok(Vec::new())
+        }
+
+        async fn disable(&self, _: Uuid, _: &str) -> common::errors::Result<()> {
+            Ok(())
+        }
+
+        fn local_user(&self, _: &AuthenticatedPrincipal) -> Option<Uuid> {
+            None
+        }
+    }
+
+    /// Records every call as `"failure <user> <ip>"` or `"success <user>"`.
+    #[derive(Default)]
+    struct FakeLockout {
+        calls: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LockoutTrait for FakeLockout {
+        async fn retry_after(
+            &self, _: Option<Uuid>, _: &str,
+        ) -> common::errors::Result<Option<u64>> {
+            Ok(None)
+        }
+
+        async fn record_failure(
+            &self, user_id: Option<Uuid>, ip: &str,
+        ) -> common::errors::Result<()> {
+            let user = user_id.map(|id| id.to_string()).unwrap_or_default();
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("failure {user} {ip}"));
+            Ok(())
+        }
+
+        async fn record_success(
+            &self, user_id: Uuid,
+        ) -> common::errors::Result<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("success {user_id}"));
+            Ok(())
+        }
+
+        async fn unlock(&self, _: Uuid) -> common::errors::Result<()> {
+            Ok(())
+        }
+    }
+
+    async fn service() -> (AuthenticationService, Arc<FakeLockout>) {
+        let jwt = JwtKeys::from_config(&AuthConfig::default());
+        jwt.install(&[SigningKey::generate().unwrap()]).unwrap();
+        let lockout = Arc::new(FakeLockout::default());
+        let svc = AuthenticationService::new(
+            test_pool().await,
+            Arc::new(jwt),
+            Arc::new(FakeMfa {
+                user_id: Mutex::new(None),
+            }),
+            lockout.clone(),
+            3600,
+        );
+        (svc, lockout)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_mfa_failures_count_and_success_clears_after_second_factor() {
+        let (svc, lockout) = service().await;
+        let mut conn = svc.conn().await.unwrap();
+        let user_id = insert_user(&mut conn, None, "password").await;
+        let username = format!("user-{user_id}");
+
+        let outcome = svc
+            .login(username, "password".into(), CLIENT_IP.into())
+            .await
+            .unwrap();
+        assert!(matches!(outcome, LoginOutcome::MfaRequired(_)));
+        assert!(lockout.calls.lock().unwrap().is_empty());
+
+        let err = svc
+            .verify_mfa("mfa".into(), "000000".into(), CLIENT_IP.into())
+            .await
+            .unwrap_err();
+        assert_eq!(err, CError::InvalidMfaCode);
+        assert_eq!(
+            *lockout.calls.lock().unwrap(),
+            [format!("failure {user_id} {CLIENT_IP}")]
+        );
+
+        svc.verify_mfa("mfa".into(), GOOD_CODE.into(), CLIENT_IP.into())
+            .await
+            .unwrap();
+        assert_eq!(
+            lockout.calls.lock().unwrap().last(),
+            Some(&format!("success {user_id}"))
+        );
+    }
+
+    /// Tokens of a fresh family for a new user.
+    async fn signed_in(svc: &AuthenticationService) -> (Uuid, AuthTokens) {
+        let mut conn = svc.conn().await.unwrap();
+        let user_id = insert_user(&mut conn, None, "password").await;
+        let user: User = users::table
+            .find(user_id)
+            .select(User::as_select())
+            .first(&mut conn)
+            .await
+            .unwrap();
+        (user_id, svc.start_family(&mut conn, &user).await.unwrap())
+    }
+
+    async fn reuse_events(svc: &AuthenticationService, user_id: Uuid) -> i64 {
+        let mut conn = svc.conn().await.unwrap();
+        security_events::table
+            .filter(security_events::user_id.eq(user_id))
+            .filter(security_events::kind.eq(REFRESH_TOKEN_REUSE_EVENT))
+            .count()
+            .get_result(&mut conn)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_refresh_rotates_within_the_family() {
+        let (svc, _) = service().await;
+        let (user_id, first) = signed_in(&svc).await;
+
+        let second = svc.refresh(first.refresh_token.clone()).await.unwrap();
+        assert_ne!(second.refresh_token, first.refresh_token);
+        let third = svc.refresh(second.refresh_token.clone()).await.unwrap();
+        assert_ne!(third.refresh_token, second.refresh_token);
+
+        let mut conn = svc.conn().await.unwrap();
+        let rows: Vec<(Uuid, bool)> = refresh_tokens::table
+            .filter(refresh_tokens::user_id.eq(user_id))
+            .order(refresh_tokens::created_at)
+            .select((
+                refresh_tokens::family_id,
+                refresh_tokens::used_at.is_not_null(),
+            ))
+            .load(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|(family, _)| *family == rows[0].0));
+        let used: Vec<bool> = rows.iter().map(|(_, used)| *used).collect();
+        assert_eq!(used, [true, true, false]);
+        assert_eq!(reuse_events(&svc, user_id).await, 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_refresh_reuse_revokes_the_family() {
+        let (svc, _) = service().await;
+        let (user_id, first) = signed_in(&svc).await;
+        let second = svc.refresh(first.refresh_token.clone()).await.unwrap();
+
+        let err = svc.refresh(first.refresh_token.clone()).await.unwrap_err();
+        assert_eq!(err, CError::GenericUnauthorized);
+
+        let mut conn = svc.conn().await.unwrap();
+        let reason: Option<String> = refresh_token_families::table
+            .filter(refresh_token_families::user_id.eq(user_id))
+            .select(refresh_token_families::revoked_reason)
+            .first(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(reason.as_deref(), Some(REVOKED_ON_REUSE));
+        assert_eq!(reuse_events(&svc, user_id).await, 1);
+
+        // The token issued before the reuse no longer works either, and
+        // replaying into a revoked family records no further events.
+        let err = svc.refresh(second.refresh_token).await.unwrap_err();
+        assert_eq!(err, CError::GenericUnauthorized);
+        svc.refresh(first.refresh_token).await.unwrap_err();
+        assert_eq!(reuse_events(&svc, user_id).await, 1);
+    }
+}
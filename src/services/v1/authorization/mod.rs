@@ -0,0 +1,99 @@
+use crate::common;
+use crate::common::errors::CError;
+use crate::domains::authorization::AuthorizationTrait;
+use crate::infrastructures::cache::local_cache::CacheRegistry;
+use crate::infrastructures::database::DbPool;
+use crate::infrastructures::database::schema::role_permissions;
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use log::error;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+const PERMISSIONS_NAMESPACE: &str = "permissions";
+
+/// AuthorizationService resolves roles through the `role_permissions` table.
+/// Each role's permission list is cached in the "permissions" namespace, so
+/// mapping changes take effect once that namespace's TTL expires.
+pub struct AuthorizationService {
+    db: &'static DbPool,
+    caches: Arc<CacheRegistry>,
+}
+
+impl AuthorizationService {
+    pub fn new(db: &'static DbPool, caches: Arc<CacheRegistry>) -> Self {
+        // Fallback for configs that do not declare the namespace.
+        caches.ensure_namespace(
+            PERMISSIONS_NAMESPACE,
+            Duration::from_secs(60),
+            10_000,
+        );
+        Self { db, caches }
+    }
+
+    async fn load(
+        &self, roles: &[String],
+    ) -> common::errors::Result<Vec<(String, String)>> {
+        let mut conn = self.db.get().await.map_err(|e| {
+            error!("failed to get database connection: {e}");
+            CError::InvalidDatabaseClient
+        })?;
+        role_permissions::table
+            .filter(role_permissions::role.eq_any(roles))
+            .select((role_permissions::role, role_permissions::permission))
+            .load(&mut conn)
+            .await
+            .map_err(|e| {
+                error!("failed to load role permissions: {e}");
+                CError::GenericInternalServer
+            })
+    }
+}
+
+#[async_trait]
+impl AuthorizationTrait for AuthorizationService {
+    async fn permissions_for(
+        &self, roles: &[String],
+    ) -> common::errors::Result<HashSet<String>> {
+        let mut granted = HashSet::new();
+        let mut missing = Vec::new();
+        for role in roles {
+            match self
+                .caches
+                .get_json::<Vec<String>>(PERMISSIONS_NAMESPACE, &role_key(role))
+                .await
+            {
+                Some(permissions) => granted.extend(permissions),
+                None => missing.push(role.clone()),
+            }
+        }
+        if missing.is_empty() {
+            return Ok(granted);
+        }
+
+        let rows = self.load(&missing).await?;
+        for role in &missing {
+            let permissions: Vec<String> = rows
+                .iter()
+                .filter(|(r, _)| r == role)
+                .map(|(_, p)| p.clone())
+                .collect();
+            // Roles without permissions are cached too, as an empty list.
+            if let Err(e) = self
+                .caches
+                .put_json(PERMISSIONS_NAMESPACE, role_key(role), &permissions)
+                .await
+            {
+                error!("failed to cache permissions for role {role}: {e}");
+            }
+            granted.extend(permissions);
+        }
+        Ok(granted)
+    }
+}
+
+fn role_key(role: &str) -> String {
+    format!("role:{role}")
+}
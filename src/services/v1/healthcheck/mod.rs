@@ -1,13 +1,88 @@
+use crate::common;
 use crate::common::api_response::BaseOutput;
-use crate::domains::health::HealthcheckTrait;
+use crate::domains::health::{
+    ComponentHealth, HealthCheck, HealthStatus, HealthcheckTrait, LifecycleState,
+    Readiness,
+};
 use async_trait::async_trait;
-use crate::common;
+use futures_util::future::join_all;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Health checks run by the readiness probe, each bounded by its own timeout.
+pub struct HealthCheckRegistry {
+    checks: Vec<Arc<dyn HealthCheck>>,
+    default_timeout: Duration,
+}
+
+impl HealthCheckRegistry {
+    pub fn new(default_timeout: Duration) -> Self {
+        Self {
+            checks: Vec::new(),
+            default_timeout,
+        }
+    }
+
+    pub fn register(mut self, check: Arc<dyn HealthCheck>) -> Self {
+        self.checks.push(check);
+        self
+    }
+
+    /// Run every check concurrently.
+    pub async fn run(&self) -> Readiness {
+        let components =
+            join_all(self.checks.iter().map(|check| self.run_one(check))).await;
+        let status = components
+            .iter()
+            .map(|c| match (c.status, c.critical) {
+                (HealthStatus::Up, _) => HealthStatus::Up,
+                (_, true) => HealthStatus::Down,
+                (_, false) => HealthStatus::Degraded,
+            })
+            .max()
+            .unwrap_or_default();
+        Readiness {
+            status,
+            state: LifecycleState::Ready,
+            components,
+        }
+    }
+
+    async fn run_one(&self, check: &Arc<dyn HealthCheck>) -> ComponentHealth {
+        let timeout = check.timeout().unwrap_or(self.default_timeout);
+        let started = Instant::now();
+        let error = match tokio::time::timeout(timeout, check.check()).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(format!("{e:#}")),
+            Err(_) => Some(format!("timed out after {}ms", timeout.as_millis())),
+        };
+        ComponentHealth {
+            name: check.name(),
+            status: match error {
+                None => HealthStatus::Up,
+                Some(_) => HealthStatus::Down,
+            },
+            critical: check.critical(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            error,
+        }
+    }
+}
 
-pub struct HealthcheckService {}
+pub struct HealthcheckService {
+    registry: HealthCheckRegistry,
+    lifecycle: watch::Receiver<LifecycleState>,
+}
 
 impl HealthcheckService {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(
+        registry: HealthCheckRegistry, lifecycle: watch::Receiver<LifecycleState>,
+    ) -> Self {
+        Self {
+            registry,
+            lifecycle,
+        }
     }
 }
 
@@ -33,23 +108,123 @@ impl HealthcheckTrait for HealthcheckService {
         })
     }
 
-    async fn ready(&self) -> common::errors::Result<BaseOutput> {
-        Ok(BaseOutput {
-            code: "OK".to_string(),
-            message: "OK".to_string(),
-            data: None,
-            count: None,
-            meta: None,
-        })
+    async fn ready(&self) -> common::errors::Result<Readiness> {
+        // Dependencies are irrelevant while starting or draining.
+        let state = *self.lifecycle.borrow();
+        if state != LifecycleState::Ready {
+            return Ok(Readiness {
+                status: HealthStatus::Down,
+                state,
+                components: Vec::new(),
+            });
+        }
+        Ok(self.registry.run().await)
     }
 
-    async fn started(&self) -> common::errors::Result<BaseOutput> {
-        Ok(BaseOutput {
-            code: "OK".to_string(),
-            message: "OK".to_string(),
-            data: None,
-            count: None,
-            meta: None,
+    async fn started(&self) -> common::errors::Result<LifecycleState> {
+        Ok(*self.lifecycle.borrow())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StubCheck {
+        name: &'static str,
+        critical: bool,
+        delay: Duration,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl HealthCheck for StubCheck {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn critical(&self) -> bool {
+            self.critical
+        }
+
+        async fn check(&self) -> anyhow::Result<()> {
+            tokio::time::sleep(self.delay).await;
+            match self.fail {
+                true => anyhow::bail!("unreachable"),
+                false => Ok(()),
+            }
+        }
+    }
+
+    fn stub(
+        name: &'static str, critical: bool, fail: bool,
+    ) -> Arc<dyn HealthCheck> {
+        Arc::new(StubCheck {
+            name,
+            critical,
+            delay: Duration::ZERO,
+            fail,
         })
     }
+
+    #[tokio::test]
+    async fn test_readiness_status_follows_criticality() {
+        let timeout = Duration::from_secs(1);
+
+        let all_up = HealthCheckRegistry::new(timeout)
+            .register(stub("database", true, false))
+            .register(stub("otlp", false, false));
+        assert_eq!(all_up.run().await.status, HealthStatus::Up);
+
+        let degraded = HealthCheckRegistry::new(timeout)
+            .register(stub("database", true, false))
+            .register(stub("otlp", false, true));
+        let readiness = degraded.run().await;
+        assert_eq!(readiness.status, HealthStatus::Degraded);
+        assert_eq!(readiness.components[1].status, HealthStatus::Down);
+        assert_eq!(
+            readiness.components[1].error.as_deref(),
+            Some("unreachable")
+        );
+
+        let down = HealthCheckRegistry::new(timeout)
+            .register(stub("database", true, true))
+            .register(stub("otlp", false, true));
+        assert_eq!(down.run().await.status, HealthStatus::Down);
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_while_draining() {
+        let (tx, rx) = watch::channel(LifecycleState::Ready);
+        let svc = HealthcheckService::new(
+            HealthCheckRegistry::new(Duration::from_secs(1))
+                .register(stub("database", true, false)),
+            rx,
+        );
+        assert_eq!(svc.ready().await.unwrap().status, HealthStatus::Up);
+
+        tx.send_replace(LifecycleState::Draining);
+        let readiness = svc.ready().await.unwrap();
+        assert_eq!(readiness.status, HealthStatus::Down);
+        assert_eq!(readiness.state, LifecycleState::Draining);
+        assert!(readiness.components.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_slow_check_times_out() {
+        let registry = HealthCheckRegistry::new(Duration::from_millis(20))
+            .register(Arc::new(StubCheck {
+                name: "database",
+                critical: true,
+                delay: Duration::from_secs(5),
+                fail: false,
+            }));
+
+        let readiness = registry.run().await;
+        assert_eq!(readiness.status, HealthStatus::Down);
+        assert_eq!(
+            readiness.components[0].error.as_deref(),
+            Some("timed out after 20ms")
+        );
+    }
 }
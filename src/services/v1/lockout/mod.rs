@@ -0,0 +1,351 @@
+use crate::common;
+use crate::common::errors::CError;
+use crate::config::env_settings::LockoutConfig;
+use crate::domains::lockout::LockoutTrait;
+use crate::infrastructures::cache::local_cache::CacheRegistry;
+use crate::infrastructures::database::models::security_event::NewSecurityEvent;
+use crate::infrastructures::database::schema::{security_events, users};
+use crate::infrastructures::database::{DbConn, DbPool};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+const ATTEMPTS_NAMESPACE: &str = "login_attempts";
+const ACCOUNT_LOCKED_EVENT: &str = "account_locked";
+
+/// `failed_login_count`, `last_failed_login_at` and `locked_until` of a user.
+type AccountCounters = (i32, Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// When an account may try again, from its consecutive failure count.
+#[derive(Debug, Clone, Copy)]
+struct LockoutPolicy {
+    max_failures: u32,
+    lockout: Duration,
+    backoff_base: Duration,
+    backoff_max: Duration,
+}
+
+impl LockoutPolicy {
+    /// Delay enforced after `failures` consecutive failures: the base delay
+    /// doubled for each failure past the first, capped at the maximum.
+    fn backoff(&self, failures: u32) -> Duration {
+        if failures == 0 {
+            return Duration::zero();
+        }
+        let factor = 1i32.checked_shl(failures - 1).unwrap_or(i32::MAX);
+        self.backoff_base
+            .checked_mul(factor)
+            .map_or(self.backoff_max, |delay| delay.min(self.backoff_max))
+    }
+
+    /// Time left before the next attempt is allowed, if any.
+    fn retry_after(
+        &self, failures: u32, last_failed_at: Option<DateTime<Utc>>,
+        locked_until: Option<DateTime<Utc>>, now: DateTime<Utc>,
+    ) -> Option<Duration> {
+        let backoff_until = last_failed_at.map(|at| at + self.backoff(failures));
+        [locked_until, backoff_until]
+            .into_iter()
+            .flatten()
+            .max()
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+}
+
+/// Failed logins from one client IP, kept in the "login_attempts" namespace
+/// under `ip:{ip}`.
+#[derive(Serialize, Deserialize)]
+struct IpFailures {
+    failures: u32,
+    /// Unix timestamp; the namespace TTL may be longer than the window
+    window_ends_at: i64,
+}
+
+/// LockoutService keeps per-account counters on the user row, so they are
+/// shared by every instance, and per-IP counters in the local cache.
+pub struct LockoutService {
+    db: &'static DbPool,
+    caches: Arc<CacheRegistry>,
+    policy: LockoutPolicy,
+    ip_max_failures: u32,
+    ip_window: Duration,
+}
+
+impl LockoutService {
+    pub fn new(
+        db: &'static DbPool, caches: Arc<CacheRegistry>, cfg: &LockoutConfig,
+    ) -> Self {
+        // Fallback for configs that do not declare the namespace.
+        caches.ensure_namespace(
+            ATTEMPTS_NAMESPACE,
+            std::time::Duration::from_secs(cfg.ip_window_seconds),
+            100_000,
+        );
+        Self {
+            db,
+            caches,
+            policy: LockoutPolicy {
+                max_failures: cfg.max_failures,
+                lockout: Duration::seconds(cfg.lockout_seconds as i64),
+                backoff_base: Duration::seconds(cfg.backoff_base_seconds as i64),
+                backoff_max: Duration::seconds(cfg.backoff_max_seconds as i64),
+            },
+            ip_max_failures: cfg.ip_max_failures,
+            ip_window: Duration::seconds(cfg.ip_window_seconds as i64),
+        }
+    }
+
+    async fn conn(&self) -> common::errors::Result<DbConn> {
+        self.db.get().await.map_err(|e| {
+            error!("failed to get database connection: {e}");
+            CError::InvalidDatabaseClient
+        })
+    }
+
+    async fn ip_failures(&self, ip: &str) -> Option<IpFailures> {
+        self.caches
+            .get_json::<IpFailures>(ATTEMPTS_NAMESPACE, &ip_key(ip))
+            .await
+            .filter(|f| f.window_ends_at > Utc::now().timestamp())
+    }
+
+    async fn account_retry_after(
+        &self, user_id: Uuid, now: DateTime<Utc>,
+    ) -> common::errors::Result<Option<Duration>> {
+        let mut conn = self.conn().await?;
+        let row: Option<AccountCounters> = users::table
+            .find(user_id)
+            .select((
+                users::failed_login_count,
+                users::last_failed_login_at,
+                users::locked_until,
+            ))
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(db_error)?;
+        Ok(row.and_then(|(failures, last_failed_at, locked_until)| {
+            self.policy.retry_after(
+                failures.max(0) as u32,
+                last_failed_at,
+                locked_until,
+                now,
+            )
+        }))
+    }
+}
+
+#[async_trait]
+impl LockoutTrait for LockoutService {
+    async fn retry_after(
+        &self, user_id: Option<Uuid>, ip: &str,
+    ) -> common::errors::Result<Option<u64>> {
+        let now = Utc::now();
+        let ip_wait = self
+            .ip_failures(ip)
+            .await
+            .filter(|f| f.failures >= self.ip_max_failures)
+            .map(|f| Duration::seconds(f.window_ends_at - now.timestamp()));
+        let account_wait = match user_id {
+            Some(user_id) => self.account_retry_after(user_id, now).await?,
+            None => None,
+        };
+        // Round up so clients never retry a moment too early.
+        Ok(ip_wait.max(account_wait).map(|wait| {
+            (wait + Duration::milliseconds(999)).num_seconds().max(1) as u64
+        }))
+    }
+
+    async fn record_failure(
+        &self, user_id: Option<Uuid>, ip: &str,
+    ) -> common::errors::Result<()> {
+        let now = Utc::now();
+
+        // Not atomic across concurrent requests; the account counter below
+        // is, so a racing IP may at worst get a few extra guesses.
+        let mut ip_failures = self.ip_failures(ip).await.unwrap_or(IpFailures {
+            failures: 0,
+            window_ends_at: (now + self.ip_window).timestamp(),
+        });
+        ip_failures.failures += 1;
+        self.caches
+            .put_json(ATTEMPTS_NAMESPACE, ip_key(ip), &ip_failures)
+            .await
+            .map_err(|_| CError::GenericInternalServer)?;
+        if ip_failures.failures == self.ip_max_failures {
+            warn!(ip = %ip, "too many failed logins; client ip throttled");
+        }
+
+        let Some(user_id) = user_id else {
+            return Ok(());
+        };
+        let policy = self.policy;
+        let mut conn = self.conn().await?;
+        let locked = conn
+            .transaction::<_, diesel::result::Error, _>(|conn| {
+                count_failure(conn, user_id, policy, now).scope_boxed()
+            })
+            .await
+            .map_err(db_error)?;
+        if locked {
+            warn!(
+                user_id = %user_id,
+                ip = %ip,
+                "too many failed logins; account locked",
+            );
+        }
+        Ok(())
+    }
+
+    async fn record_success(&self, user_id: Uuid) -> common::errors::Result<()> {
+        let mut conn = self.conn().await?;
+        diesel::update(
+            users::table
+                .find(user_id)
+                .filter(users::failed_login_count.gt(0)),
+        )
+        .set((
+            users::failed_login_count.eq(0),
+            users::last_failed_login_at.eq(None::<DateTime<Utc>>),
+        ))
+        .execute(&mut conn)
+        .await
+        .map_err(db_error)?;
+        Ok(())
+    }
+
+    async fn unlock(&self, user_id: Uuid) -> common::errors::Result<()> {
+        let mut conn = self.conn().await?;
+        let updated = diesel::update(users::table.find(user_id))
+            .set((
+                users::failed_login_count.eq(0),
+                users::last_failed_login_at.eq(None::<DateTime<Utc>>),
+                users::locked_until.eq(None::<DateTime<Utc>>),
+            ))
+            .execute(&mut conn)
+            .await
+            .map_err(db_error)?;
+        match updated {
+            0 => Err(CError::UserNotFound),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Increment the failure count of `user_id`, turning it into a lockout once
+/// it reaches the limit. Returns whether the account was locked.
+async fn count_failure(
+    conn: &mut DbConn, user_id: Uuid, policy: LockoutPolicy, now: DateTime<Utc>,
+) -> diesel::QueryResult<bool> {
+    let failures: Option<i32> = users::table
+        .find(user_id)
+        .select(users::failed_login_count)
+        .for_update()
+        .first(conn)
+        .await
+        .optional()?;
+    let Some(failures) = failures else {
+        return Ok(false);
+    };
+
+    let failures = failures.max(0) as u32 + 1;
+    if failures < policy.max_failures {
+        diesel::update(users::table.find(user_id))
+            .set((
+                users::failed_login_count.eq(failures as i32),
+                users::last_failed_login_at.eq(now),
+            ))
+            .execute(conn)
+            .await?;
+        return Ok(false);
+    }
+
+    let locked_until = now + policy.lockout;
+    diesel::update(users::table.find(user_id))
+        .set((
+            users::failed_login_count.eq(0),
+            users::last_failed_login_at.eq(None::<DateTime<Utc>>),
+            users::locked_until.eq(locked_until),
+        ))
+        .execute(conn)
+        .await?;
+    diesel::insert_into(security_events::table)
+        .values(NewSecurityEvent {
+            id: Uuid::new_v4(),
+            user_id: Some(user_id),
+            kind: ACCOUNT_LOCKED_EVENT,
+            detail: &format!(
+                "{failures} consecutive failed logins; locked until {locked_until}"
+            ),
+        })
+        .execute(conn)
+        .await?;
+    Ok(true)
+}
+
+fn ip_key(ip: &str) -> String {
+    format!("ip:{ip}")
+}
+
+fn db_error(e: diesel::result::Error) -> CError {
+    error!("database query failed: {e}");
+    CError::GenericInternalServer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> LockoutPolicy {
+        LockoutPolicy {
+            max_failures: 5,
+            lockout: Duration::seconds(900),
+            backoff_base: Duration::seconds(1),
+            backoff_max: Duration::seconds(60),
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let policy = policy();
+        let delays: Vec<i64> = [0, 1, 2, 3, 4, 7, 8, 40]
+            .into_iter()
+            .map(|n| policy.backoff(n).num_seconds())
+            .collect();
+        assert_eq!(delays, vec![0, 1, 2, 4, 8, 60, 60, 60]);
+    }
+
+    #[test]
+    fn test_retry_after_takes_longest_wait() {
+        let policy = policy();
+        let now = Utc::now();
+        let last_failed_at = Some(now - Duration::seconds(1));
+
+        assert_eq!(policy.retry_after(0, None, None, now), None);
+        assert_eq!(
+            policy.retry_after(3, last_failed_at, None, now),
+            Some(Duration::seconds(3))
+        );
+        assert_eq!(
+            policy.retry_after(
+                3,
+                last_failed_at,
+                Some(now + Duration::seconds(600)),
+                now
+            ),
+            Some(Duration::seconds(600))
+        );
+        assert_eq!(
+            policy.retry_after(0, None, Some(now - Duration::seconds(1)), now),
+            None
+        );
+    }
+}
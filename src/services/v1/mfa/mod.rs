@@ -0,0 +1,475 @@
+use crate::common;
+use crate::common::errors::CError;
+use crate::config::env_settings::AuthConfig;
+use crate::domains::authentication::AuthenticatedPrincipal;
+use crate::domains::mfa::{
+    MfaChallenge, MfaRedemption, MfaSubject, MfaTrait, TotpEnrollment,
+};
+use crate::infrastructures::cache::local_cache::CacheRegistry;
+use crate::infrastructures::crypto::{Sealer, random_token, sha256_hex};
+use crate::infrastructures::database::models::mfa::{
+    NewRecoveryCode, NewUserTotp, UserTotp,
+};
+use crate::infrastructures::database::schema::{
+    mfa_recovery_codes, user_roles, user_totp, users,
+};
+use crate::infrastructures::database::{DbConn, DbPool};
+use crate::infrastructures::totp;
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+const STATE_NAMESPACE: &str = "state";
+const METHOD_TOTP: &str = "totp";
+const METHOD_RECOVERY_CODE: &str = "recovery_code";
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Login halted between password and second factor, kept in the "state"
+/// namespace under `mfa:{mfa_token}`.
+#[derive(Serialize, Deserialize)]
+struct PendingMfa {
+    user_id: Uuid,
+    enrollment_required: bool,
+    attempts: u32,
+    /// Unix timestamp; re-inserting after a wrong code must not extend it
+    expires_at: i64,
+}
+
+pub struct MfaService {
+    db: &'static DbPool,
+    caches: Arc<CacheRegistry>,
+    sealer: Sealer,
+    /// `iss` of access tokens issued by this service
+    local_issuer: String,
+    /// Issuer label shown in authenticator apps
+    app_issuer: String,
+    required_roles: Vec<String>,
+    max_attempts: u32,
+    /// Lifetime of a challenge in seconds
+    pending_ttl: u64,
+}
+
+impl MfaService {
+    pub fn new(
+        db: &'static DbPool, caches: Arc<CacheRegistry>, cfg: &AuthConfig,
+        pending_ttl: u64,
+    ) -> Self {
+        // Fallback for configs that do not declare the namespace.
+        caches.ensure_namespace(
+            STATE_NAMESPACE,
+            Duration::from_secs(pending_ttl),
+            10_000,
+        );
+        Self {
+            db,
+            caches,
+            sealer: Sealer::new(cfg.jwt_secret.expose()),
+            local_issuer: cfg.issuer.clone(),
+            app_issuer: cfg.mfa.issuer.clone(),
+            required_roles: cfg.mfa.required_roles.clone(),
+            max_attempts: cfg.mfa.max_attempts,
+            pending_ttl,
+        }
+    }
+
+    async fn conn(&self) -> common::errors::Result<DbConn> {
+        self.db.get().await.map_err(|e| {
+            error!("failed to get database connection: {e}");
+            CError::InvalidDatabaseClient
+        })
+    }
+
+    async fn totp_row(
+        &self, conn: &mut DbConn, user_id: Uuid,
+    ) -> common::errors::Result<Option<UserTotp>> {
+        user_totp::table
+            .find(user_id)
+            .select(UserTotp::as_select())
+            .first(conn)
+            .await
+            .optional()
+            .map_err(db_error)
+    }
+
+    /// Whether any of the user's roles mandates MFA.
+    async fn mfa_required(
+        &self, conn: &mut DbConn, user_id: Uuid,
+    ) -> common::errors::Result<bool> {
+        let count: i64 = user_roles::table
+            .filter(user_roles::user_id.eq(user_id))
+            .filter(user_roles::role.eq_any(&self.required_roles))
+            .count()
+            .get_result(conn)
+            .await
+            .map_err(db_error)?;
+        Ok(count > 0)
+    }
+
+    /// Check a TOTP code and record its time step so it cannot be replayed.
+    async fn check_totp(
+        &self, conn: &mut DbConn, row: &UserTotp, code: &str,
+    ) -> common::errors::Result<()> {
+        let secret = self
+            .sealer
+            .open(secret_aad(row.user_id).as_bytes(), &row.secret)
+            .ok_or_else(|| {
+                error!(
+                    "failed to unseal totp secret of {}; was auth.jwt_secret changed?",
+                    row.user_id
+                );
+                CError::GenericInternalServer
+            })?;
+        let step = totp::verify(&secret, code.trim(), Utc::now().timestamp())
+            .ok_or(CError::InvalidMfaCode)?;
+
+        let updated = diesel::update(
+            user_totp::table.find(row.user_id).filter(
+                user_totp::last_used_step
+                    .is_null()
+                    .or(user_totp::last_used_step.lt(step)),
+            ),
+        )
+        .set(user_totp::last_used_step.eq(step))
+        .execute(conn)
+        .await
+        .map_err(db_error)?;
+        match updated {
+            0 => Err(CError::InvalidMfaCode),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check an enabled user's TOTP code.
+    async fn check_enabled_totp(
+        &self, conn: &mut DbConn, user_id: Uuid, code: &str,
+    ) -> common::errors::Result<()> {
+        let row = self
+            .totp_row(conn, user_id)
+            .await?
+            .filter(|row| row.enabled_at.is_some())
+            .ok_or(CError::MfaNotEnrolled)?;
+        self.check_totp(conn, &row, code).await
+    }
+
+    /// Consume an unused recovery code.
+    async fn use_recovery_code(
+        &self, conn: &mut DbConn, user_id: Uuid, code: &str,
+    ) -> common::errors::Result<()> {
+        let updated = diesel::update(
+            mfa_recovery_codes::table
+                .filter(mfa_recovery_codes::user_id.eq(user_id))
+                .filter(
+                    mfa_recovery_codes::code_hash
+                        .eq(sha256_hex(&normalize_recovery_code(code))),
+                )
+                .filter(mfa_recovery_codes::used_at.is_null()),
+        )
+        .set(mfa_recovery_codes::used_at.eq(Utc::now()))
+        .execute(conn)
+        .await
+        .map_err(db_error)?;
+        match updated {
+            0 => Err(CError::InvalidMfaCode),
+            _ => Ok(()),
+        }
+    }
+
+    /// Replace the user's recovery codes and return the plaintext ones.
+    async fn replace_recovery_codes(
+        &self, conn: &mut DbConn, user_id: Uuid,
+    ) -> common::errors::Result<Vec<String>> {
+        let codes: Vec<String> =
+            (0..RECOVERY_CODE_COUNT).map(|_| recovery_code()).collect();
+        let rows: Vec<NewRecoveryCode> = codes
+            .iter()
+            .map(|code| NewRecoveryCode {
+                id: Uuid::new_v4(),
+                user_id,
+                code_hash: sha256_hex(&normalize_recovery_code(code)),
+            })
+            .collect();
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            async move {
+                diesel::delete(
+                    mfa_recovery_codes::table
+                        .filter(mfa_recovery_codes::user_id.eq(user_id)),
+                )
+                .execute(conn)
+                .await?;
+                diesel::insert_into(mfa_recovery_codes::table)
+                    .values(&rows)
+                    .execute(conn)
+                    .await?;
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+        .map_err(db_error)?;
+        Ok(codes)
+    }
+
+    /// Verify the first code of a pending enrollment and enable it.
+    async fn activate_with(
+        &self, conn: &mut DbConn, user_id: Uuid, code: &str,
+    ) -> common::errors::Result<Vec<String>> {
+        let row = self
+            .totp_row(conn, user_id)
+            .await?
+            .ok_or(CError::MfaNotEnrolled)?;
+        if row.enabled_at.is_some() {
+            return Err(CError::MfaAlreadyEnabled);
+        }
+        self.check_totp(conn, &row, code).await?;
+
+        diesel::update(user_totp::table.find(user_id))
+            .set(user_totp::enabled_at.eq(Utc::now()))
+            .execute(conn)
+            .await
+            .map_err(db_error)?;
+        self.replace_recovery_codes(conn, user_id).await
+    }
+
+    async fn pending(&self, mfa_token: &str) -> Option<PendingMfa> {
+        self.caches
+            .get_json::<PendingMfa>(STATE_NAMESPACE, &pending_key(mfa_token))
+            .await
+            .filter(|p| p.expires_at > Utc::now().timestamp())
+    }
+}
+
+#[async_trait]
+impl MfaTrait for MfaService {
+    async fn challenge(
+        &self, user_id: Uuid,
+    ) -> common::errors::Result<Option<MfaChallenge>> {
+        let mut conn = self.conn().await?;
+        let enabled = self
+            .totp_row(&mut conn, user_id)
+            .await?
+            .is_some_and(|row| row.enabled_at.is_some());
+        if !enabled && !self.mfa_required(&mut conn, user_id).await? {
+            return Ok(None);
+        }
+
+        let mfa_token = random_token(32);
+        let pending = PendingMfa {
+            user_id,
+            enrollment_required: !enabled,
+            attempts: 0,
+            expires_at: Utc::now().timestamp() + self.pending_ttl as i64,
+        };
+        self.caches
+            .put_json(STATE_NAMESPACE, pending_key(&mfa_token), &pending)
+            .await
+            .map_err(|_| CError::GenericInternalServer)?;
+
+        let methods = match enabled {
+            true => vec![METHOD_TOTP, METHOD_RECOVERY_CODE],
+            false => vec![METHOD_TOTP],
+        };
+        Ok(Some(MfaChallenge {
+            mfa_token,
+            methods: methods.into_iter().map(str::to_string).collect(),
+            enrollment_required: !enabled,
+            expires_in: self.pending_ttl,
+        }))
+    }
+
+    async fn redeem(
+        &self, mfa_token: &str, code: &str,
+    ) -> common::errors::Result<MfaRedemption> {
+        let key = pending_key(mfa_token);
+        let mut pending: PendingMfa = self
+            .caches
+            .take_json(STATE_NAMESPACE, &key)
+            .await
+            .filter(|p: &PendingMfa| p.expires_at > Utc::now().timestamp())
+            .ok_or(CError::GenericUnauthorized)?;
+
+        let mut conn = self.conn().await?;
+        let result = match pending.enrollment_required {
+            true => self.activate_with(&mut conn, pending.user_id, code).await,
+            false => match self
+                .check_enabled_totp(&mut conn, pending.user_id, code)
+                .await
+            {
+                Err(CError::InvalidMfaCode) => self
+                    .use_recovery_code(&mut conn, pending.user_id, code)
+                    .await
+                    .map(|_| Vec::new()),
+                other => other.map(|_| Vec::new()),
+            },
+        };
+
+        match result {
+            Ok(recovery_codes) => Ok(MfaRedemption {
+                user_id: pending.user_id,
+                recovery_codes,
+            }),
+            Err(err) => {
+                // Put the challenge back unless wrong codes used it up.
+                if err == CError::InvalidMfaCode {
+                    pending.attempts += 1;
+                }
+                if pending.attempts < self.max_attempts {
+                    let _ = self
+                        .caches
+                        .put_json(STATE_NAMESPACE, key, &pending)
+                        .await;
+                } else {
+                    warn!(
+                        user_id = %pending.user_id,
+                        "mfa challenge discarded after too many wrong codes",
+                    );
+                }
+                Err(err)
+            },
+        }
+    }
+
+    async fn challenged_user(&self, mfa_token: &str) -> Option<Uuid> {
+        self.pending(mfa_token).await.map(|p| p.user_id)
+    }
+
+    async fn enroll(
+        &self, subject: MfaSubject,
+    ) -> common::errors::Result<TotpEnrollment> {
+        let user_id = match subject {
+            MfaSubject::Principal(principal) => self
+                .local_user(&principal)
+                .ok_or(CError::GenericPermission)?,
+            MfaSubject::Pending(mfa_token) => self
+                .pending(&mfa_token)
+                .await
+                .filter(|p| p.enrollment_required)
+                .map(|p| p.user_id)
+                .ok_or(CError::GenericUnauthorized)?,
+        };
+
+        let mut conn = self.conn().await?;
+        let username: String = users::table
+            .find(user_id)
+            .select(users::username)
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(db_error)?
+            .ok_or(CError::GenericPermission)?;
+        if self
+            .totp_row(&mut conn, user_id)
+            .await?
+            .is_some_and(|row| row.enabled_at.is_some())
+        {
+            return Err(CError::MfaAlreadyEnabled);
+        }
+
+        let secret = totp::generate_secret();
+        let sealed = self.sealer.seal(secret_aad(user_id).as_bytes(), &secret);
+        diesel::insert_into(user_totp::table)
+            .values(NewUserTotp {
+                user_id,
+                secret: &sealed,
+            })
+            .on_conflict(user_totp::user_id)
+            .do_update()
+            .set((
+                user_totp::secret.eq(&sealed),
+                user_totp::last_used_step.eq(None::<i64>),
+                user_totp::created_at.eq(Utc::now()),
+            ))
+            .execute(&mut conn)
+            .await
+            .map_err(db_error)?;
+
+        Ok(TotpEnrollment {
+            secret: totp::base32(&secret),
+            otpauth_uri: totp::otpauth_uri(&self.app_issuer, &username, &secret),
+        })
+    }
+
+    async fn activate(
+        &self, user_id: Uuid, code: &str,
+    ) -> common::errors::Result<Vec<String>> {
+        let mut conn = self.conn().await?;
+        self.activate_with(&mut conn, user_id, code).await
+    }
+
+    async fn regenerate_recovery_codes(
+        &self, user_id: Uuid, code: &str,
+    ) -> common::errors::Result<Vec<String>> {
+        let mut conn = self.conn().await?;
+        self.check_enabled_totp(&mut conn, user_id, code).await?;
+        self.replace_recovery_codes(&mut conn, user_id).await
+    }
+
+    async fn disable(
+        &self, user_id: Uuid, code: &str,
+    ) -> common::errors::Result<()> {
+        let mut conn = self.conn().await?;
+        if self.mfa_required(&mut conn, user_id).await? {
+            return Err(CError::MfaRequired);
+        }
+        self.check_enabled_totp(&mut conn, user_id, code).await?;
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            async move {
+                diesel::delete(user_totp::table.find(user_id))
+                    .execute(conn)
+                    .await?;
+                diesel::delete(
+                    mfa_recovery_codes::table
+                        .filter(mfa_recovery_codes::user_id.eq(user_id)),
+                )
+                .execute(conn)
+                .await?;
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+        .map_err(db_error)
+    }
+
+    fn local_user(&self, principal: &AuthenticatedPrincipal) -> Option<Uuid> {
+        (principal.issuer == self.local_issuer)
+            .then(|| Uuid::parse_str(&principal.subject).ok())
+            .flatten()
+    }
+}
+
+fn pending_key(mfa_token: &str) -> String {
+    format!("mfa:{mfa_token}")
+}
+
+/// Binds a sealed secret to its owner so rows cannot be swapped.
+fn secret_aad(user_id: Uuid) -> String {
+    format!("totp:{user_id}")
+}
+
+/// `xxxxx-xxxxx` from a lowercase base32 alphabet (50 bits).
+fn recovery_code() -> String {
+    let raw = totp::base32(&totp::generate_secret()).to_ascii_lowercase();
+    format!("{}-{}", &raw[..5], &raw[5..10])
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn db_error(e: diesel::result::Error) -> CError {
+    error!("database query failed: {e}");
+    CError::GenericInternalServer
+}
@@ -1,2 +1,11 @@
+pub mod account;
+pub mod api_key;
 pub mod authentication;
+pub mod authorization;
 pub mod healthcheck;
+pub mod lockout;
+pub mod mfa;
+pub mod oidc;
+pub mod session;
+pub mod signing_key;
+pub mod token;
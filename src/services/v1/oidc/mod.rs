@@ -0,0 +1,364 @@
+use crate::common;
+use crate::common::errors::CError;
+use crate::domains::authentication::{
+    OidcAuthenticationTrait, OidcAuthorization, OidcIdentity, OidcLogin,
+};
+use crate::domains::session::SessionTrait;
+use crate::infrastructures::cache::local_cache::CacheRegistry;
+use crate::infrastructures::oidc::OidcClient;
+use async_trait::async_trait;
+use chrono::Utc;
+use log::warn;
+use openidconnect::core::CoreAuthenticationFlow;
+use openidconnect::{
+    AccessTokenHash, AuthorizationCode, CsrfToken, Nonce, OAuth2TokenResponse,
+    PkceCodeChallenge, PkceCodeVerifier, Scope, TokenResponse, reqwest,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const STATE_NAMESPACE: &str = "state";
+
+/// Data kept in the "state" namespace between redirect and callback.
+#[derive(Serialize, Deserialize)]
+struct PendingAuthorization {
+    pkce_verifier: String,
+    nonce: String,
+    return_to: Option<String>,
+}
+
+pub struct OidcService {
+    client: OidcClient,
+    http: reqwest::Client,
+    caches: Arc<CacheRegistry>,
+    scopes: Vec<String>,
+    sessions: Arc<dyn SessionTrait>,
+}
+
+impl OidcService {
+    pub fn new(
+        client: OidcClient, http: reqwest::Client, caches: Arc<CacheRegistry>,
+        scopes: Vec<String>, sessions: Arc<dyn SessionTrait>,
+    ) -> Self {
+        Self {
+            client,
+            http,
+            caches,
+            scopes,
+            sessions,
+        }
+    }
+
+    async fn exchange(
+        &self, code: String, pending: PendingAuthorization,
+    ) -> common::errors::Result<OidcIdentity> {
+        let token = self
+            .client
+            .exchange_code(AuthorizationCode::new(code))
+            .map_err(|e| {
+                warn!("oidc provider has no token endpoint: {e}");
+                CError::OidcProviderFailure
+            })?
+            .set_pkce_verifier(PkceCodeVerifier::new(pending.pkce_verifier))
+            .request_async(&self.http)
+            .await
+            .map_err(|e| {
+                warn!("oidc code exchange failed: {e}");
+                CError::OidcProviderFailure
+            })?;
+
+        let id_token = token.id_token().ok_or_else(|| {
+            warn!("oidc token response has no id_token");
+            CError::OidcProviderFailure
+        })?;
+        let verifier = self.client.id_token_verifier();
+        let claims = id_token
+            .claims(&verifier, &Nonce::new(pending.nonce))
+            .map_err(|e| {
+                warn!("oidc id_token rejected: {e}");
+                CError::GenericUnauthorized
+            })?;
+
+        // Make sure the access token was not swapped for another user's.
+        if let Some(expected) = claims.access_token_hash() {
+            let actual = id_token
+                .signing_alg()
+                .ok()
+                .zip(id_token.signing_key(&verifier).ok())
+                .and_then(|(alg, key)| {
+                    AccessTokenHash::from_token(token.access_token(), alg, key)
+                        .ok()
+                });
+            if actual.as_ref() != Some(expected) {
+                warn!("oidc access token hash mismatch");
+                return Err(CError::GenericUnauthorized);
+            }
+        }
+
+        Ok(OidcIdentity {
+            issuer: claims.issuer().to_string(),
+            subject: claims.subject().to_string(),
+            email: claims.email().map(|e| e.to_string()),
+            name: claims
+                .name()
+                .and_then(|n| n.get(None))
+                .map(|n| n.to_string()),
+        })
+    }
+}
+
+#[async_trait]
+impl OidcAuthenticationTrait for OidcService {
+    async fn authorize(
+        &self, return_to: Option<String>,
+    ) -> common::errors::Result<OidcAuthorization> {
+        let (challenge, verifier) = PkceCodeChallenge::new_random_sha256();
+
+        let mut request = self.client.authorize_url(
+            CoreAuthenticationFlow::AuthorizationCode,
+            CsrfToken::new_random,
+            Nonce::new_random,
+        );
+        for scope in &self.scopes {
+            request = request.add_scope(Scope::new(scope.clone()));
+        }
+        let (url, state, nonce) = request.set_pkce_challenge(challenge).url();
+
+        let pending = PendingAuthorization {
+            pkce_verifier: verifier.secret().clone(),
+            nonce: nonce.secret().clone(),
+            return_to: return_to.filter(|p| is_local_path(p)),
+        };
+        self.caches
+            .put_json(STATE_NAMESPACE, state_key(state.secret()), &pending)
+            .await
+            .map_err(|_| CError::GenericInternalServer)?;
+
+        Ok(OidcAuthorization {
+            url: url.to_string(),
+        })
+    }
+
+    async fn complete(
+        &self, code: String, state: String, current_session: Option<String>,
+    ) -> common::errors::Result<OidcLogin> {
+        let pending: PendingAuthorization = self
+            .caches
+            .take_json(STATE_NAMESPACE, &state_key(&state))
+            .await
+            .ok_or(CError::InvalidOidcState)?;
+        let return_to = pending.return_to.clone();
+
+        let identity = self.exchange(code, pending).await?;
+
+        let session = self
+            .sessions
+            .create(&identity, current_session.as_deref())
+            .await?;
+
+        Ok(OidcLogin {
+            expires_in: (session.expires_at - Utc::now().timestamp()).max(0)
+                as u64,
+            session_id: session.id,
+            identity,
+            return_to,
+        })
+    }
+}
+
+fn state_key(state: &str) -> String {
+    format!("oidc:{state}")
+}
+
+/// Only same-origin absolute paths are accepted as post-login targets.
+fn is_local_path(path: &str) -> bool {
+    path.starts_with('/') && !path.starts_with("//") && !path.contains('\\')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::env_settings::{OIDCConfig, SessionConfig};
+    use crate::config::secrets::Secret;
+    use crate::infrastructures::cache::local_cache::init_default_caches;
+    use crate::infrastructures::oidc::{discover_client, http_client};
+    use crate::infrastructures::session::CacheSessionStore;
+    use crate::services::v1::session::SessionService;
+    use axum::extract::{Form, State};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use base64::Engine;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use openidconnect::core::{
+        CoreIdToken, CoreIdTokenClaims, CoreJsonWebKeySet,
+        CoreJwsSigningAlgorithm, CoreProviderMetadata, CoreResponseType,
+        CoreRsaPrivateSigningKey, CoreSubjectIdentifierType,
+    };
+    use openidconnect::{
+        Audience, AuthUrl, EmptyAdditionalClaims,
+        EmptyAdditionalProviderMetadata, EndUserEmail, IssuerUrl, JsonWebKeyId,
+        JsonWebKeySetUrl, PrivateSigningKey, ResponseTypes, StandardClaims,
+        SubjectIdentifier, TokenUrl,
+    };
+    use rsa::pkcs1::EncodeRsaPrivateKey;
+    use sha2::{Digest, Sha256};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use url::Url;
+
+    const CLIENT_ID: &str = "test-client";
+
+    #[derive(Clone)]
+    struct MockIssuer {
+        issuer: String,
+        key: Arc<CoreRsaPrivateSigningKey>,
+        // code -> (pkce challenge, nonce)
+        codes: Arc<Mutex<HashMap<String, (String, String)>>>,
+    }
+
+    async fn discovery(State(m): State<MockIssuer>) -> Json<serde_json::Value> {
+        let metadata = CoreProviderMetadata::new(
+            IssuerUrl::new(m.issuer.clone()).unwrap(),
+            AuthUrl::new(format!("{}/authorize", m.issuer)).unwrap(),
+            JsonWebKeySetUrl::new(format!("{}/jwks", m.issuer)).unwrap(),
+            vec![ResponseTypes::new(vec![CoreResponseType::Code])],
+            vec![CoreSubjectIdentifierType::Public],
+            vec![CoreJwsSigningAlgorithm::RsaSsaPkcs1V15Sha256],
+            EmptyAdditionalProviderMetadata {},
+        )
+        .set_token_endpoint(Some(
+            TokenUrl::new(format!("{}/token", m.issuer)).unwrap(),
+        ));
+        Json(serde_json::to_value(metadata).unwrap())
+    }
+
+    async fn jwks(State(m): State<MockIssuer>) -> Json<CoreJsonWebKeySet> {
+        Json(CoreJsonWebKeySet::new(vec![m.key.as_verification_key()]))
+    }
+
+    async fn token(
+        State(m): State<MockIssuer>, Form(form): Form<HashMap<String, String>>,
+    ) -> Result<Json<serde_json::Value>, http::StatusCode> {
+        let code = form.get("code").ok_or(http::StatusCode::BAD_REQUEST)?;
+        let (challenge, nonce) = m
+            .codes
+            .lock()
+            .unwrap()
+            .remove(code)
+            .ok_or(http::StatusCode::BAD_REQUEST)?;
+        let verifier = form
+            .get("code_verifier")
+            .ok_or(http::StatusCode::BAD_REQUEST)?;
+        if URL_SAFE_NO_PAD.encode(Sha256::digest(verifier)) != challenge {
+            return Err(http::StatusCode::BAD_REQUEST);
+        }
+
+        let claims = CoreIdTokenClaims::new(
+            IssuerUrl::new(m.issuer.clone()).unwrap(),
+            vec![Audience::new(CLIENT_ID.to_string())],
+            Utc::now() + chrono::Duration::minutes(5),
+            Utc::now(),
+            StandardClaims::new(SubjectIdentifier::new("user-1".to_string()))
+                .set_email(Some(EndUserEmail::new("user@example.com".into()))),
+            EmptyAdditionalClaims {},
+        )
+        .set_nonce(Some(Nonce::new(nonce)));
+        let id_token = CoreIdToken::new(
+            claims,
+            m.key.as_ref(),
+            CoreJwsSigningAlgorithm::RsaSsaPkcs1V15Sha256,
+            None,
+            None,
+        )
+        .unwrap();
+
+        Ok(Json(serde_json::json!({
+            "access_token": "access",
+            "token_type": "bearer",
+            "expires_in": 300,
+            "id_token": id_token.to_string(),
+        })))
+    }
+
+    async fn start_mock_issuer() -> MockIssuer {
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+
+        let rsa_key =
+            rsa::RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap();
+        let pem = rsa_key.to_pkcs1_pem(Default::default()).unwrap();
+        let key = CoreRsaPrivateSigningKey::from_pem(
+            &pem,
+            Some(JsonWebKeyId::new("test".into())),
+        )
+        .unwrap();
+
+        let mock = MockIssuer {
+            issuer,
+            key: Arc::new(key),
+            codes: Arc::new(Mutex::new(HashMap::new())),
+        };
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .with_state(mock.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        mock
+    }
+
+    #[tokio::test]
+    async fn test_authorization_code_flow_with_pkce() {
+        init_default_caches();
+        let mock = start_mock_issuer().await;
+
+        let cfg = OIDCConfig {
+            enabled: true,
+            issuer_url: mock.issuer.clone(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: Secret::new("secret"),
+            redirect_url: "http://localhost/api/v1/auth/oidc/callback".into(),
+            ..Default::default()
+        };
+        let http = http_client().unwrap();
+        let client = discover_client(&cfg, &http).await.unwrap();
+        let caches = CacheRegistry::global().clone();
+        let sessions: Arc<dyn SessionTrait> = Arc::new(SessionService::new(
+            Arc::new(CacheSessionStore::new(caches.clone())),
+            &SessionConfig::default(),
+        ));
+        let svc = OidcService::new(
+            client,
+            http,
+            caches,
+            cfg.scope_list(),
+            sessions.clone(),
+        );
+
+        let auth = svc.authorize(Some("/home".into())).await.unwrap();
+        let url = Url::parse(&auth.url).unwrap();
+        let query: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        let state = query["state"].clone();
+        assert_eq!(query["code_challenge_method"], "S256");
+
+        mock.codes.lock().unwrap().insert(
+            "code-1".into(),
+            (query["code_challenge"].clone(), query["nonce"].clone()),
+        );
+
+        let login = svc
+            .complete("code-1".into(), state.clone(), None)
+            .await
+            .unwrap();
+        assert_eq!(login.identity.subject, "user-1");
+        assert_eq!(login.identity.email.as_deref(), Some("user@example.com"));
+        assert_eq!(login.return_to.as_deref(), Some("/home"));
+        let session = sessions.resume(&login.session_id).await.unwrap();
+        assert_eq!(session.subject, "user-1");
+
+        // state is single-use
+        let replay = svc.complete("code-1".into(), state, None).await;
+        assert_eq!(replay.unwrap_err(), CError::InvalidOidcState);
+    }
+}
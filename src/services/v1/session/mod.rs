@@ -0,0 +1,244 @@
+use crate::common;
+use crate::common::errors::CError;
+use crate::config::env_settings::SessionConfig;
+use crate::domains::authentication::OidcIdentity;
+use crate::domains::session::{Session, SessionStore, SessionTrait};
+use crate::infrastructures::crypto::random_token;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::sync::Arc;
+
+pub struct SessionService {
+    store: Arc<dyn SessionStore>,
+    /// Idle timeout in seconds
+    idle_timeout: i64,
+    /// Absolute timeout in seconds
+    absolute_timeout: i64,
+}
+
+impl SessionService {
+    pub fn new(store: Arc<dyn SessionStore>, cfg: &SessionConfig) -> Self {
+        Self {
+            store,
+            idle_timeout: cfg.idle_timeout_seconds as i64,
+            absolute_timeout: cfg.absolute_timeout_seconds as i64,
+        }
+    }
+
+    fn is_live(session: &Session, now: i64) -> bool {
+        session.expires_at > now && session.absolute_expires_at > now
+    }
+}
+
+#[async_trait]
+impl SessionTrait for SessionService {
+    async fn create(
+        &self, identity: &OidcIdentity, replaces: Option<&str>,
+    ) -> common::errors::Result<Session> {
+        if let Some(old) = replaces {
+            self.store.remove(old).await?;
+        }
+
+        let now = Utc::now().timestamp();
+        let absolute_expires_at = now + self.absolute_timeout;
+        let session = Session {
+            // 256-bit random, URL-safe session identifier
+            id: random_token(32),
+            subject: identity.subject.clone(),
+            issuer: identity.issuer.clone(),
+            email: identity.email.clone(),
+            name: identity.name.clone(),
+            created_at: now,
+            last_seen_at: now,
+            expires_at: (now + self.idle_timeout).min(absolute_expires_at),
+            absolute_expires_at,
+        };
+        self.store.save(&session).await?;
+        Ok(session)
+    }
+
+    async fn resume(&self, id: &str) -> common::errors::Result<Session> {
+        let now = Utc::now().timestamp();
+        let mut session = self
+            .store
+            .load(id)
+            .await?
+            .ok_or(CError::GenericUnauthorized)?;
+        if !Self::is_live(&session, now) {
+            self.store.remove(id).await?;
+            return Err(CError::GenericUnauthorized);
+        }
+
+        session.last_seen_at = now;
+        session.expires_at =
+            (now + self.idle_timeout).min(session.absolute_expires_at);
+        self.store.save(&session).await?;
+        Ok(session)
+    }
+
+    async fn end(&self, id: &str) -> common::errors::Result<()> {
+        self.store.remove(id).await.map(|_| ())
+    }
+
+    async fn list(&self, subject: &str) -> common::errors::Result<Vec<Session>> {
+        let now = Utc::now().timestamp();
+        let mut sessions: Vec<Session> = self
+            .store
+            .list(subject)
+            .await?
+            .into_iter()
+            .filter(|s| Self::is_live(s, now))
+            .collect();
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.last_seen_at));
+        Ok(sessions)
+    }
+
+    async fn revoke(
+        &self, subject: &str, handle: &str,
+    ) -> common::errors::Result<()> {
+        let session = self
+            .store
+            .list(subject)
+            .await?
+            .into_iter()
+            .find(|s| s.handle() == handle)
+            .ok_or(CError::SessionNotFound)?;
+        self.store.remove(&session.id).await.map(|_| ())
+    }
+
+    async fn revoke_all(&self, subject: &str) -> common::errors::Result<usize> {
+        let sessions = self.store.list(subject).await?;
+        for session in &sessions {
+            self.store.remove(&session.id).await?;
+        }
+        Ok(sessions.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructures::cache::local_cache::CacheRegistry;
+    use crate::infrastructures::session::CacheSessionStore;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    const IDLE: i64 = 600;
+    const ABSOLUTE: i64 = 3600;
+
+    fn service() -> (SessionService, Arc<CacheSessionStore>) {
+        let caches = CacheRegistry::init().clone();
+        caches.ensure_namespace(
+            "session",
+            Duration::from_secs(ABSOLUTE as u64),
+            1_000,
+        );
+        let store = Arc::new(CacheSessionStore::new(caches));
+        let cfg = SessionConfig {
+            idle_timeout_seconds: IDLE as u64,
+            absolute_timeout_seconds: ABSOLUTE as u64,
+            ..SessionConfig::default()
+        };
+        (SessionService::new(store.clone(), &cfg), store)
+    }
+
+    /// An identity no other test uses, since the cache is process-wide.
+    fn identity() -> OidcIdentity {
+        OidcIdentity {
+            issuer: "https://issuer.example".into(),
+            subject: Uuid::new_v4().to_string(),
+            email: None,
+            name: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_slides_idle_expiry() {
+        let (svc, store) = service();
+        let mut session = svc.create(&identity(), None).await.unwrap();
+        let now = Utc::now().timestamp();
+        assert_eq!(session.expires_at - session.created_at, IDLE);
+
+        // Nearly idle: resuming pushes expiry a full idle period out.
+        session.last_seen_at = now - IDLE + 1;
+        session.expires_at = now + 1;
+        store.save(&session).await.unwrap();
+        let resumed = svc.resume(&session.id).await.unwrap();
+        assert!(resumed.expires_at >= now + IDLE);
+        assert!(resumed.last_seen_at >= now);
+
+        // Idle too long: rejected and removed.
+        session.expires_at = now - 1;
+        store.save(&session).await.unwrap();
+        let err = svc.resume(&session.id).await.unwrap_err();
+        assert_eq!(err, CError::GenericUnauthorized);
+        assert!(store.load(&session.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_absolute_expiry_caps_sliding() {
+        let (svc, store) = service();
+        let mut session = svc.create(&identity(), None).await.unwrap();
+        assert_eq!(session.absolute_expires_at - session.created_at, ABSOLUTE);
+        let now = Utc::now().timestamp();
+
+        // Close to the absolute limit: expiry slides no further than it.
+        session.absolute_expires_at = now + 5;
+        store.save(&session).await.unwrap();
+        let resumed = svc.resume(&session.id).await.unwrap();
+        assert_eq!(resumed.expires_at, now + 5);
+
+        // Past the absolute limit: rejected even though not idle.
+        session.expires_at = now + IDLE;
+        session.absolute_expires_at = now - 1;
+        store.save(&session).await.unwrap();
+        let err = svc.resume(&session.id).await.unwrap_err();
+        assert_eq!(err, CError::GenericUnauthorized);
+        assert!(svc.list(&session.subject).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_login_rotates_session_id() {
+        let (svc, _) = service();
+        let identity = identity();
+        let first = svc.create(&identity, None).await.unwrap();
+        let second = svc.create(&identity, Some(&first.id)).await.unwrap();
+
+        assert_ne!(first.id, second.id);
+        let err = svc.resume(&first.id).await.unwrap_err();
+        assert_eq!(err, CError::GenericUnauthorized);
+        svc.resume(&second.id).await.unwrap();
+        let live = svc.list(&identity.subject).await.unwrap();
+        assert_eq!(live.len(), 1);
+        assert_eq!(live[0].id, second.id);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_by_handle() {
+        let (svc, _) = service();
+        let (alice, bob) = (identity(), identity());
+        let kept = svc.create(&alice, None).await.unwrap();
+        let revoked = svc.create(&alice, None).await.unwrap();
+        let other = svc.create(&bob, None).await.unwrap();
+
+        // Handles only resolve among the subject's own sessions.
+        let err = svc
+            .revoke(&alice.subject, &other.handle())
+            .await
+            .unwrap_err();
+        assert_eq!(err, CError::SessionNotFound);
+
+        svc.revoke(&alice.subject, &revoked.handle()).await.unwrap();
+        let live = svc.list(&alice.subject).await.unwrap();
+        assert_eq!(live.len(), 1);
+        assert_eq!(live[0].id, kept.id);
+        assert!(svc.resume(&revoked.id).await.is_err());
+        svc.resume(&other.id).await.unwrap();
+
+        let err = svc
+            .revoke(&alice.subject, &revoked.handle())
+            .await
+            .unwrap_err();
+        assert_eq!(err, CError::SessionNotFound);
+    }
+}
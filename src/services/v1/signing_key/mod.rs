@@ -0,0 +1,216 @@
+use crate::common;
+use crate::common::errors::CError;
+use crate::config::env_settings::SigningKeysConfig;
+use crate::config::secrets::Secret;
+use crate::domains::signing_key::{SigningKeyInfo, SigningKeyTrait};
+use crate::infrastructures::crypto::Sealer;
+use crate::infrastructures::database::models::signing_key::SigningKeyRow;
+use crate::infrastructures::database::schema::signing_keys;
+use crate::infrastructures::database::{DbConn, DbPool};
+use crate::infrastructures::jwt::JwtKeys;
+use crate::infrastructures::jwt::signing_key::{SIGNING_ALGORITHM, SigningKey};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use log::{error, info};
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+/// SigningKeyService persists the key ring in Postgres, private keys sealed
+/// with `auth.jwt_secret`, and keeps `JwtKeys` in step with it.
+pub struct SigningKeyService {
+    db: &'static DbPool,
+    jwt: Arc<JwtKeys>,
+    sealer: Sealer,
+    rotation_interval: Duration,
+    retention: Duration,
+}
+
+impl SigningKeyService {
+    pub fn new(
+        db: &'static DbPool, jwt: Arc<JwtKeys>, secret: &Secret,
+        cfg: &SigningKeysConfig,
+    ) -> Self {
+        Self {
+            db,
+            jwt,
+            sealer: Sealer::new(secret.expose()),
+            rotation_interval: Duration::seconds(
+                cfg.rotation_interval_seconds as i64,
+            ),
+            retention: Duration::seconds(cfg.retention_seconds as i64),
+        }
+    }
+
+    async fn conn(&self) -> common::errors::Result<DbConn> {
+        self.db.get().await.map_err(|e| {
+            error!("failed to get database connection: {e}");
+            CError::InvalidDatabaseClient
+        })
+    }
+
+    /// All stored keys, newest first.
+    async fn load(
+        &self, conn: &mut DbConn,
+    ) -> common::errors::Result<Vec<SigningKeyRow>> {
+        signing_keys::table
+            .order(signing_keys::created_at.desc())
+            .select(SigningKeyRow::as_select())
+            .load(conn)
+            .await
+            .map_err(db_error)
+    }
+
+    /// Unseal `rows` and make them the ring used for signing and verifying.
+    fn install(&self, rows: &[SigningKeyRow]) -> common::errors::Result<()> {
+        let keys = rows
+            .iter()
+            .map(|row| self.unseal(row))
+            .collect::<common::errors::Result<Vec<_>>>()?;
+        self.jwt.install(&keys).map_err(|e| {
+            error!("failed to load signing keys: {e}");
+            CError::GenericInternalServer
+        })
+    }
+
+    fn unseal(&self, row: &SigningKeyRow) -> common::errors::Result<SigningKey> {
+        let pem = self
+            .sealer
+            .open(row.kid.as_bytes(), &row.private_key)
+            .and_then(|pem| String::from_utf8(pem.to_vec()).ok())
+            .ok_or_else(|| {
+                error!(
+                    "failed to unseal signing key {}; was auth.jwt_secret changed?",
+                    row.kid
+                );
+                CError::GenericInternalServer
+            })?;
+        Ok(SigningKey::from_parts(
+            row.kid.clone(),
+            Zeroizing::new(pem),
+            row.public_n.clone(),
+            row.public_e.clone(),
+            row.created_at,
+            row.retired_at,
+        ))
+    }
+
+    /// Generate and store a new active key, retiring every other one.
+    async fn generate(
+        &self, conn: &mut DbConn,
+    ) -> common::errors::Result<SigningKeyRow> {
+        let key = tokio::task::spawn_blocking(SigningKey::generate)
+            .await
+            .map_err(|e| {
+                error!("signing key generation panicked: {e}");
+                CError::GenericInternalServer
+            })?
+            .map_err(|e| {
+                error!("failed to generate signing key: {e}");
+                CError::GenericInternalServer
+            })?;
+        let row = SigningKeyRow {
+            private_key: self
+                .sealer
+                .seal(key.kid.as_bytes(), key.private_pem().as_bytes()),
+            kid: key.kid,
+            algorithm: SIGNING_ALGORITHM.to_string(),
+            public_n: key.n,
+            public_e: key.e,
+            created_at: key.created_at,
+            retired_at: None,
+        };
+
+        let inserted = row.clone();
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            async move {
+                diesel::insert_into(signing_keys::table)
+                    .values(&inserted)
+                    .execute(conn)
+                    .await?;
+                diesel::update(
+                    signing_keys::table
+                        .filter(signing_keys::kid.ne(&inserted.kid))
+                        .filter(signing_keys::retired_at.is_null()),
+                )
+                .set(signing_keys::retired_at.eq(inserted.created_at))
+                .execute(conn)
+                .await?;
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+        .map_err(db_error)?;
+
+        info!("signing key {} activated", row.kid);
+        Ok(row)
+    }
+}
+
+#[async_trait]
+impl SigningKeyTrait for SigningKeyService {
+    async fn sync(&self) -> common::errors::Result<()> {
+        let mut conn = self.conn().await?;
+        let now = Utc::now();
+
+        let purged = diesel::delete(
+            signing_keys::table
+                .filter(signing_keys::retired_at.lt(now - self.retention)),
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(db_error)?;
+        if purged > 0 {
+            info!("purged {purged} retired signing key(s)");
+        }
+
+        let mut rows = self.load(&mut conn).await?;
+        let due = rows.iter().find(|row| row.retired_at.is_none()).is_none_or(
+            |active| active.created_at + self.rotation_interval <= now,
+        );
+        if due {
+            self.generate(&mut conn).await?;
+            rows = self.load(&mut conn).await?;
+        }
+        self.install(&rows)
+    }
+
+    async fn rotate(&self) -> common::errors::Result<SigningKeyInfo> {
+        let mut conn = self.conn().await?;
+        let row = self.generate(&mut conn).await?;
+        let rows = self.load(&mut conn).await?;
+        self.install(&rows)?;
+        Ok(info(&row, true))
+    }
+
+    async fn list(&self) -> common::errors::Result<Vec<SigningKeyInfo>> {
+        let mut conn = self.conn().await?;
+        let rows = self.load(&mut conn).await?;
+        let active_kid = rows
+            .iter()
+            .find(|row| row.retired_at.is_none())
+            .map(|row| row.kid.clone());
+        Ok(rows
+            .iter()
+            .map(|row| info(row, active_kid.as_ref() == Some(&row.kid)))
+            .collect())
+    }
+}
+
+fn info(row: &SigningKeyRow, active: bool) -> SigningKeyInfo {
+    SigningKeyInfo {
+        kid: row.kid.clone(),
+        algorithm: row.algorithm.clone(),
+        active,
+        created_at: row.created_at,
+        retired_at: row.retired_at,
+    }
+}
+
+fn db_error(e: diesel::result::Error) -> CError {
+    error!("database query failed: {e}");
+    CError::GenericInternalServer
+}
@@ -0,0 +1,192 @@
+use crate::common;
+use crate::common::errors::CError;
+use crate::domains::api_key::ApiKeyTrait;
+use crate::domains::authentication::AuthenticationTrait;
+use crate::domains::token::{TokenIntrospection, TokenIntrospectionTrait};
+use crate::infrastructures::crypto::sha256_hex;
+use crate::infrastructures::database::models::refresh_token::{
+    RefreshToken, RefreshTokenFamily,
+};
+use crate::infrastructures::database::models::user::User;
+use crate::infrastructures::database::schema::{
+    refresh_token_families, refresh_tokens, users,
+};
+use crate::infrastructures::database::{DbConn, DbPool};
+use crate::infrastructures::jwt::denylist::TokenDenylist;
+use crate::infrastructures::jwt::{AccessClaims, JwtKeys};
+use async_trait::async_trait;
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use log::{error, info};
+use std::sync::Arc;
+
+const ACCESS_TOKEN: &str = "access_token";
+const REFRESH_TOKEN: &str = "refresh_token";
+const API_KEY: &str = "api_key";
+
+pub struct TokenService {
+    db: &'static DbPool,
+    jwt: Arc<JwtKeys>,
+    denylist: Arc<TokenDenylist>,
+    authentication: Arc<dyn AuthenticationTrait>,
+    api_keys: Arc<dyn ApiKeyTrait>,
+}
+
+impl TokenService {
+    pub fn new(
+        db: &'static DbPool, jwt: Arc<JwtKeys>, denylist: Arc<TokenDenylist>,
+        authentication: Arc<dyn AuthenticationTrait>,
+        api_keys: Arc<dyn ApiKeyTrait>,
+    ) -> Self {
+        Self {
+            db,
+            jwt,
+            denylist,
+            authentication,
+            api_keys,
+        }
+    }
+
+    async fn conn(&self) -> common::errors::Result<DbConn> {
+        self.db.get().await.map_err(|e| {
+            error!("failed to get database connection: {e}");
+            CError::InvalidDatabaseClient
+        })
+    }
+
+    /// Claims of a valid, unrevoked access token issued by this service.
+    async fn access_claims(&self, token: &str) -> Option<AccessClaims> {
+        let claims = self.jwt.verify(token).ok()?;
+        match self.denylist.is_revoked(&claims.jti).await {
+            true => None,
+            false => Some(claims),
+        }
+    }
+
+    async fn introspect_access_token(
+        &self, token: &str,
+    ) -> Option<TokenIntrospection> {
+        let claims = self.access_claims(token).await?;
+        Some(TokenIntrospection {
+            active: true,
+            username: claims.username,
+            token_type: Some(ACCESS_TOKEN.to_string()),
+            exp: Some(claims.exp),
+            iat: Some(claims.iat),
+            sub: Some(claims.sub),
+            aud: Some(claims.aud),
+            iss: Some(claims.iss),
+            jti: Some(claims.jti),
+            ..TokenIntrospection::default()
+        })
+    }
+
+    async fn introspect_refresh_token(
+        &self, token: &str,
+    ) -> common::errors::Result<Option<TokenIntrospection>> {
+        let mut conn = self.conn().await?;
+        let row: Option<(RefreshToken, RefreshTokenFamily, User)> =
+            refresh_tokens::table
+                .inner_join(refresh_token_families::table)
+                .inner_join(users::table)
+                .filter(refresh_tokens::token_hash.eq(sha256_hex(token)))
+                .select((
+                    RefreshToken::as_select(),
+                    RefreshTokenFamily::as_select(),
+                    User::as_select(),
+                ))
+                .first(&mut conn)
+                .await
+                .optional()
+                .map_err(db_error)?;
+        let Some((stored, family, user)) = row else {
+            return Ok(None);
+        };
+
+        // Unknown tokens fall through to the next type; known but unusable
+        // ones are reported inactive.
+        let active = stored.used_at.is_none()
+            && stored.revoked_at.is_none()
+            && stored.expires_at > Utc::now()
+            && family.revoked_at.is_none()
+            && user.is_active;
+        if !active {
+            return Ok(Some(TokenIntrospection::inactive()));
+        }
+        Ok(Some(TokenIntrospection {
+            active: true,
+            username: Some(user.username),
+            token_type: Some(REFRESH_TOKEN.to_string()),
+            exp: Some(stored.expires_at.timestamp()),
+            iat: Some(stored.created_at.timestamp()),
+            sub: Some(user.id.to_string()),
+            iss: Some(self.jwt.issuer().to_string()),
+            jti: Some(stored.id.to_string()),
+            ..TokenIntrospection::default()
+        }))
+    }
+
+    async fn introspect_api_key(
+        &self, token: &str,
+    ) -> Option<TokenIntrospection> {
+        let principal = self.api_keys.authenticate(token).await.ok()?;
+        Some(TokenIntrospection {
+            active: true,
+            scope: Some(principal.scopes.join(" ")),
+            client_id: principal.token_id.clone(),
+            username: principal.username,
+            token_type: Some(API_KEY.to_string()),
+            exp: principal.expires_at,
+            sub: Some(principal.subject),
+            iss: Some(principal.issuer),
+            jti: principal.token_id,
+            ..TokenIntrospection::default()
+        })
+    }
+}
+
+#[async_trait]
+impl TokenIntrospectionTrait for TokenService {
+    async fn introspect(
+        &self, token: &str, token_type_hint: Option<&str>,
+    ) -> common::errors::Result<TokenIntrospection> {
+        let refresh_first = token_type_hint == Some(REFRESH_TOKEN);
+        if refresh_first
+            && let Some(found) = self.introspect_refresh_token(token).await?
+        {
+            return Ok(found);
+        }
+        if let Some(found) = self.introspect_access_token(token).await {
+            return Ok(found);
+        }
+        if let Some(found) = self.introspect_api_key(token).await {
+            return Ok(found);
+        }
+        if !refresh_first
+            && let Some(found) = self.introspect_refresh_token(token).await?
+        {
+            return Ok(found);
+        }
+        Ok(TokenIntrospection::inactive())
+    }
+
+    async fn revoke(
+        &self, token: &str, _token_type_hint: Option<&str>,
+    ) -> common::errors::Result<()> {
+        // Checking the signature needs no lookup, so the hint is not worth
+        // following here.
+        if let Some(claims) = self.access_claims(token).await {
+            self.denylist.revoke(&claims.jti, claims.exp).await;
+            info!("access token {} of {} revoked", claims.jti, claims.sub);
+            return Ok(());
+        }
+        // Revokes the whole family; unknown tokens are ignored.
+        self.authentication.logout(token.to_string()).await
+    }
+}
+
+fn db_error(e: diesel::result::Error) -> CError {
+    error!("database query failed: {e}");
+    CError::GenericInternalServer
+}
@@ -1,17 +1,40 @@
-use crate::domains::authentication::AuthenticationTrait;
+use crate::domains::account::AccountTrait;
+use crate::domains::api_key::ApiKeyTrait;
+use crate::domains::authentication::{
+    AccessTokenVerifier, AuthenticationTrait, OidcAuthenticationTrait,
+};
+use crate::domains::authorization::AuthorizationTrait;
 use crate::domains::health::HealthcheckTrait;
+use crate::domains::lockout::LockoutTrait;
+use crate::domains::mfa::MfaTrait;
+use crate::domains::session::SessionTrait;
+use crate::domains::signing_key::SigningKeyTrait;
+use crate::domains::token::TokenIntrospectionTrait;
+use crate::infrastructures::jwt::JwtKeys;
+use crate::infrastructures::jwt::denylist::TokenDenylist;
 use once_cell::sync::OnceCell;
-use openidconnect::core::CoreClient;
 use opentelemetry::global::BoxedTracer;
 use std::sync::Arc;
 
-static OIDC: OnceCell<CoreClient> = OnceCell::new();
+static OIDC_AUTH: OnceCell<Arc<dyn OidcAuthenticationTrait>> = OnceCell::new();
 static HEALTH: OnceCell<Arc<dyn HealthcheckTrait>> = OnceCell::new();
 static AUTH: OnceCell<Arc<dyn AuthenticationTrait>> = OnceCell::new();
+static MFA: OnceCell<Arc<dyn MfaTrait>> = OnceCell::new();
+static LOCKOUT: OnceCell<Arc<dyn LockoutTrait>> = OnceCell::new();
+static ACCOUNTS: OnceCell<Arc<dyn AccountTrait>> = OnceCell::new();
+static API_KEYS: OnceCell<Arc<dyn ApiKeyTrait>> = OnceCell::new();
+static SESSIONS: OnceCell<Arc<dyn SessionTrait>> = OnceCell::new();
+static AUTHZ: OnceCell<Arc<dyn AuthorizationTrait>> = OnceCell::new();
+static JWT_KEYS: OnceCell<Arc<JwtKeys>> = OnceCell::new();
+static SIGNING_KEYS: OnceCell<Arc<dyn SigningKeyTrait>> = OnceCell::new();
+static TOKENS: OnceCell<Arc<dyn TokenIntrospectionTrait>> = OnceCell::new();
+static TOKEN_DENYLIST: OnceCell<Arc<TokenDenylist>> = OnceCell::new();
+static TOKEN_VERIFIERS: OnceCell<Vec<Arc<dyn AccessTokenVerifier>>> =
+    OnceCell::new();
 static TRACER: OnceCell<Arc<BoxedTracer>> = OnceCell::new();
 
-pub fn set_oidc(c: CoreClient) {
-    let _ = OIDC.set(c);
+pub fn set_oidc_auth(o: Arc<dyn OidcAuthenticationTrait>) {
+    let _ = OIDC_AUTH.set(o);
 }
 pub fn set_health(h: Arc<dyn HealthcheckTrait>) {
     let _ = HEALTH.set(h);
@@ -19,13 +42,46 @@ pub fn set_health(h: Arc<dyn HealthcheckTrait>) {
 pub fn set_auth(a: Arc<dyn AuthenticationTrait>) {
     let _ = AUTH.set(a);
 }
+pub fn set_mfa(m: Arc<dyn MfaTrait>) {
+    let _ = MFA.set(m);
+}
+pub fn set_lockout(l: Arc<dyn LockoutTrait>) {
+    let _ = LOCKOUT.set(l);
+}
+pub fn set_accounts(a: Arc<dyn AccountTrait>) {
+    let _ = ACCOUNTS.set(a);
+}
+pub fn set_api_keys(k: Arc<dyn ApiKeyTrait>) {
+    let _ = API_KEYS.set(k);
+}
+pub fn set_sessions(s: Arc<dyn SessionTrait>) {
+    let _ = SESSIONS.set(s);
+}
+pub fn set_authorization(a: Arc<dyn AuthorizationTrait>) {
+    let _ = AUTHZ.set(a);
+}
+pub fn set_jwt_keys(k: Arc<JwtKeys>) {
+    let _ = JWT_KEYS.set(k);
+}
+pub fn set_signing_keys(s: Arc<dyn SigningKeyTrait>) {
+    let _ = SIGNING_KEYS.set(s);
+}
+pub fn set_tokens(t: Arc<dyn TokenIntrospectionTrait>) {
+    let _ = TOKENS.set(t);
+}
+pub fn set_token_denylist(d: Arc<TokenDenylist>) {
+    let _ = TOKEN_DENYLIST.set(d);
+}
+pub fn set_token_verifiers(v: Vec<Arc<dyn AccessTokenVerifier>>) {
+    let _ = TOKEN_VERIFIERS.set(v);
+}
 pub fn set_tracer(t: Arc<BoxedTracer>) {
     let _ = TRACER.set(t);
 }
 
-pub fn oidc() -> &'static CoreClient {
-    OIDC.get()
-        .expect("OIDC client not set; call app_registry::set_oidc(...) first")
+/// OIDC is optional, so this returns `None` instead of panicking.
+pub fn oidc_auth() -> Option<&'static Arc<dyn OidcAuthenticationTrait>> {
+    OIDC_AUTH.get()
 }
 pub fn health() -> &'static Arc<dyn HealthcheckTrait> {
     HEALTH.get().expect(
@@ -36,6 +92,60 @@ pub fn auth() -> &'static Arc<dyn AuthenticationTrait> {
     AUTH.get()
         .expect("Auth service not set; call app_registry::set_auth(...) first")
 }
+pub fn mfa() -> &'static Arc<dyn MfaTrait> {
+    MFA.get()
+        .expect("MFA service not set; call app_registry::set_mfa(...) first")
+}
+pub fn lockout() -> &'static Arc<dyn LockoutTrait> {
+    LOCKOUT.get().expect(
+        "Lockout service not set; call app_registry::set_lockout(...) first",
+    )
+}
+pub fn accounts() -> &'static Arc<dyn AccountTrait> {
+    ACCOUNTS.get().expect(
+        "Account service not set; call app_registry::set_accounts(...) first",
+    )
+}
+pub fn api_keys() -> &'static Arc<dyn ApiKeyTrait> {
+    API_KEYS.get().expect(
+        "API key service not set; call app_registry::set_api_keys(...) first",
+    )
+}
+pub fn sessions() -> &'static Arc<dyn SessionTrait> {
+    SESSIONS.get().expect(
+        "Session service not set; call app_registry::set_sessions(...) first",
+    )
+}
+pub fn authorization() -> &'static Arc<dyn AuthorizationTrait> {
+    AUTHZ.get().expect(
+        "Authorization service not set; call app_registry::set_authorization(...) first",
+    )
+}
+pub fn jwt_keys() -> &'static Arc<JwtKeys> {
+    JWT_KEYS.get().expect(
+        "JWT key ring not set; call app_registry::set_jwt_keys(...) first",
+    )
+}
+pub fn signing_keys() -> &'static Arc<dyn SigningKeyTrait> {
+    SIGNING_KEYS.get().expect(
+        "Signing key service not set; call app_registry::set_signing_keys(...) first",
+    )
+}
+pub fn tokens() -> &'static Arc<dyn TokenIntrospectionTrait> {
+    TOKENS.get().expect(
+        "Token introspection service not set; call app_registry::set_tokens(...) first",
+    )
+}
+pub fn token_denylist() -> &'static Arc<TokenDenylist> {
+    TOKEN_DENYLIST.get().expect(
+        "Token denylist not set; call app_registry::set_token_denylist(...) first",
+    )
+}
+pub fn token_verifiers() -> &'static Vec<Arc<dyn AccessTokenVerifier>> {
+    TOKEN_VERIFIERS.get().expect(
+        "Token verifiers not set; call app_registry::set_token_verifiers(...) first",
+    )
+}
 pub fn tracer() -> Arc<BoxedTracer> {
     TRACER
         .get()
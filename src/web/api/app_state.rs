@@ -1,12 +1,27 @@
 use log::Log;
-use openidconnect::core::CoreClient;
 use opentelemetry::global::BoxedTracer;
 use std::sync::Arc;
+use tokio::sync::watch;
 
+use crate::config::env_settings::SERVICE_CONFIGURATION;
+use crate::config::reload::SettingsReceiver;
 use crate::database::{DbPool, pool as db_pool};
-use crate::domains::authentication::AuthenticationTrait;
+use crate::domains::account::AccountTrait;
+use crate::domains::api_key::ApiKeyTrait;
+use crate::domains::authentication::{
+    AccessTokenVerifier, AuthenticationTrait, OidcAuthenticationTrait,
+};
+use crate::domains::authorization::AuthorizationTrait;
 use crate::domains::health::HealthcheckTrait;
+use crate::domains::lockout::LockoutTrait;
+use crate::domains::mfa::MfaTrait;
+use crate::domains::session::SessionTrait;
+use crate::domains::signing_key::SigningKeyTrait;
+use crate::domains::token::TokenIntrospectionTrait;
 use crate::infrastructures::cache::local_cache::CacheRegistry;
+use crate::infrastructures::jwt::JwtKeys;
+use crate::infrastructures::jwt::denylist::TokenDenylist;
+use crate::infrastructures::session::cookie::SessionCookie;
 use crate::web::api::app_registry;
 
 #[derive(Debug, Clone, Default)]
@@ -17,10 +32,26 @@ pub struct RequestLogCtx {
 
 #[derive(Clone)]
 pub struct AppState {
+    pub settings: SettingsReceiver,
     pub healthcheck: Arc<dyn HealthcheckTrait>,
     pub authentication: Arc<dyn AuthenticationTrait>,
-
-    // pub oidc: CoreClient,
+    pub mfa: Arc<dyn MfaTrait>,
+    pub lockout: Arc<dyn LockoutTrait>,
+    pub accounts: Arc<dyn AccountTrait>,
+    pub authorization: Arc<dyn AuthorizationTrait>,
+    pub api_keys: Arc<dyn ApiKeyTrait>,
+    /// Present only when `oidc.enabled` is set
+    pub oidc: Option<Arc<dyn OidcAuthenticationTrait>>,
+    pub sessions: Arc<dyn SessionTrait>,
+    pub session_cookie: Arc<SessionCookie>,
+    /// Key ring of the tokens this service issues
+    pub jwt_keys: Arc<JwtKeys>,
+    pub signing_keys: Arc<dyn SigningKeyTrait>,
+    pub tokens: Arc<dyn TokenIntrospectionTrait>,
+    /// Revoked access tokens, checked on every bearer request
+    pub token_denylist: Arc<TokenDenylist>,
+    /// Bearer token verifiers, tried in order
+    pub token_verifiers: Vec<Arc<dyn AccessTokenVerifier>>,
     pub db: &'static DbPool,
     pub tracer: Arc<BoxedTracer>,
     pub logger: &'static dyn Log,
@@ -28,18 +59,38 @@ pub struct AppState {
 }
 
 impl AppState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        healthcheck: Arc<dyn HealthcheckTrait>,
-        authentication: Arc<dyn AuthenticationTrait>,
-        // oidc: CoreClient,
-        db: &'static DbPool,
-        tracer: Arc<BoxedTracer>,
-        caches: Arc<CacheRegistry>,
+        settings: SettingsReceiver, healthcheck: Arc<dyn HealthcheckTrait>,
+        authentication: Arc<dyn AuthenticationTrait>, mfa: Arc<dyn MfaTrait>,
+        lockout: Arc<dyn LockoutTrait>, accounts: Arc<dyn AccountTrait>,
+        authorization: Arc<dyn AuthorizationTrait>,
+        api_keys: Arc<dyn ApiKeyTrait>,
+        oidc: Option<Arc<dyn OidcAuthenticationTrait>>,
+        sessions: Arc<dyn SessionTrait>, session_cookie: Arc<SessionCookie>,
+        jwt_keys: Arc<JwtKeys>, signing_keys: Arc<dyn SigningKeyTrait>,
+        tokens: Arc<dyn TokenIntrospectionTrait>,
+        token_denylist: Arc<TokenDenylist>,
+        token_verifiers: Vec<Arc<dyn AccessTokenVerifier>>, db: &'static DbPool,
+        tracer: Arc<BoxedTracer>, caches: Arc<CacheRegistry>,
     ) -> Self {
         Self {
+            settings,
             healthcheck,
             authentication,
-            // oidc,
+            mfa,
+            lockout,
+            accounts,
+            authorization,
+            api_keys,
+            oidc,
+            sessions,
+            session_cookie,
+            jwt_keys,
+            signing_keys,
+            tokens,
+            token_denylist,
+            token_verifiers,
             db,
             tracer,
             logger: log::logger(),
@@ -51,9 +102,24 @@ impl AppState {
     /// Panics if any dependency was not registered/initialized.
     pub fn from_globals() -> Self {
         Self {
-            // oidc: app_registry::oidc().clone(),
+            settings: watch::channel(Arc::new(SERVICE_CONFIGURATION.clone())).1,
             healthcheck: app_registry::health().clone(),
             authentication: app_registry::auth().clone(),
+            mfa: app_registry::mfa().clone(),
+            lockout: app_registry::lockout().clone(),
+            accounts: app_registry::accounts().clone(),
+            authorization: app_registry::authorization().clone(),
+            api_keys: app_registry::api_keys().clone(),
+            oidc: app_registry::oidc_auth().cloned(),
+            sessions: app_registry::sessions().clone(),
+            session_cookie: Arc::new(SessionCookie::from_config(
+                &SERVICE_CONFIGURATION.session,
+            )),
+            jwt_keys: app_registry::jwt_keys().clone(),
+            signing_keys: app_registry::signing_keys().clone(),
+            tokens: app_registry::tokens().clone(),
+            token_denylist: app_registry::token_denylist().clone(),
+            token_verifiers: app_registry::token_verifiers().clone(),
             db: db_pool(),
             tracer: app_registry::tracer(),
             logger: log::logger(),
@@ -0,0 +1,488 @@
+//! Extractors that reject malformed and invalid input with a
+//! `GenericBadRequest` rendered like every other error, instead of axum's
+//! plain-text rejections.
+
+use crate::common::api_response::Response;
+use crate::common::errors::{AppError, AppResult, CError, FieldViolation};
+use crate::common::validation::{Validate, Validator};
+use crate::constants::http::HEADER_LINK;
+use crate::domains::listing::{
+    Filter, FilterKind, FilterOp, FilterValue, ListParams, Listing, MAX_PAGE,
+    Page, Sort, SortDirection,
+};
+use axum::Json;
+use axum::extract::{
+    FromRequest, FromRequestParts, OriginalUri, Path, Query, Request,
+};
+use axum::response::Response as AxumResponse;
+use chrono::{DateTime, Utc};
+use http::request::Parts;
+use http::{HeaderValue, StatusCode, Uri};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use url::form_urlencoded;
+
+/// JSON body, validated.
+#[derive(Debug, Clone)]
+pub struct ValidatedJson<T>(pub T);
+
+/// Query string, validated.
+#[derive(Debug, Clone)]
+pub struct ValidatedQuery<T>(pub T);
+
+/// Path parameters, validated.
+#[derive(Debug, Clone)]
+pub struct ValidatedPath<T>(pub T);
+
+impl<S, T> FromRequest<S> for ValidatedJson<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, AppError> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        Validator::check(&value)?;
+        Ok(Self(value))
+    }
+}
+
+impl<S, T> FromRequestParts<S> for ValidatedQuery<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts, state: &S,
+    ) -> Result<Self, AppError> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state).await?;
+        Validator::check(&value)?;
+        Ok(Self(value))
+    }
+}
+
+impl<S, T> FromRequestParts<S> for ValidatedPath<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate + Send,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts, state: &S,
+    ) -> Result<Self, AppError> {
+        let Path(value) = Path::<T>::from_request_parts(parts, state).await?;
+        Validator::check(&value)?;
+        Ok(Self(value))
+    }
+}
+
+/// Paging, sorting and filtering of a list endpoint, checked against the
+/// allowlists of `L`, e.g.
+/// `?page=2&per_page=50&sort=-created_at,name&filter[status]=active&created_at[gte]=2025-01-01T00:00:00Z`.
+/// `filter[field]=v` is shorthand for `field[eq]=v`.
+#[derive(Debug, Clone)]
+pub struct ListQuery<L> {
+    pub params: ListParams,
+    /// Full request URI, including the prefixes of nested routers
+    uri: Uri,
+    listing: PhantomData<fn() -> L>,
+}
+
+impl<S, L> FromRequestParts<S> for ListQuery<L>
+where
+    S: Send + Sync,
+    L: Listing,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts, _: &S,
+    ) -> Result<Self, AppError> {
+        let uri = match parts.extensions.get::<OriginalUri>() {
+            Some(OriginalUri(uri)) => uri.clone(),
+            None => parts.uri.clone(),
+        };
+        Ok(Self {
+            params: parse_list_params::<L>(uri.query().unwrap_or_default())?,
+            uri,
+            listing: PhantomData,
+        })
+    }
+}
+
+impl<L> ListQuery<L> {
+    /// `page` in the envelope with `Pagination` meta and a `Link` header
+    /// (RFC 8288) to the first, previous, next and last pages.
+    pub fn respond<T: Serialize>(
+        &self, req_id: String, page: Page<T>,
+    ) -> AxumResponse {
+        let mut resp = Response::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_count(page.items.len() as i32)
+            .with_pagination(self.params.pagination(page.total))
+            .with_data(page.items)
+            .with_status(StatusCode::OK);
+        if let Ok(link) = HeaderValue::from_str(&self.links(page.total)) {
+            resp.headers_mut().insert(HEADER_LINK, link);
+        }
+        resp
+    }
+
+    fn links(&self, total: i64) -> String {
+        let current = i64::from(self.params.page);
+        let last = self.params.total_pages(total).max(1);
+        let mut rels = vec![("first", 1)];
+        if current > 1 {
+            rels.push(("prev", (current - 1).min(last)));
+        }
+        if current < last {
+            rels.push(("next", current + 1));
+        }
+        rels.push(("last", last));
+        rels.into_iter()
+            .map(|(rel, page)| {
+                format!("<{}>; rel=\"{rel}\"", self.page_uri(page))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The request URI with `page` replaced.
+    fn page_uri(&self, page: i64) -> String {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        for (k, v) in form_urlencoded::parse(
+            self.uri.query().unwrap_or_default().as_bytes(),
+        ) {
+            if k != "page" {
+                query.append_pair(&k, &v);
+            }
+        }
+        query.append_pair("page", &page.to_string());
+        format!("{}?{}", self.uri.path(), query.finish())
+    }
+}
+
+fn parse_list_params<L: Listing>(query: &str) -> AppResult<ListParams> {
+    let mut params = ListParams {
+        page: 1,
+        per_page: L::DEFAULT_PER_PAGE,
+        sort: Vec::new(),
+        filters: Vec::new(),
+    };
+    let mut violations = Vec::new();
+    let mut reject = |field: &str, rule: &str, message: String| {
+        violations.push(FieldViolation::new(field, rule, message));
+    };
+
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "page" => match value.parse::<u32>() {
+                Ok(page) if (1..=MAX_PAGE).contains(&page) => params.page = page,
+                _ => reject(
+                    "page",
+                    "range",
+                    format!("page must be between 1 and {MAX_PAGE}"),
+                ),
+            },
+            "per_page" => match value.parse::<u32>() {
+                Ok(n) if (1..=L::MAX_PER_PAGE).contains(&n) => {
+                    params.per_page = n
+                },
+                _ => reject(
+                    "per_page",
+                    "range",
+                    format!("per_page must be between 1 and {}", L::MAX_PER_PAGE),
+                ),
+            },
+            "sort" => {
+                for key in
+                    value.split(',').map(str::trim).filter(|k| !k.is_empty())
+                {
+                    let (name, direction) = match key.strip_prefix('-') {
+                        Some(name) => (name, SortDirection::Desc),
+                        None => (key, SortDirection::Asc),
+                    };
+                    match L::SORTABLE.iter().find(|f| **f == name) {
+                        Some(field) => {
+                            params.sort.push(Sort { field, direction })
+                        },
+                        None => reject(
+                            "sort",
+                            "allowed",
+                            format!(
+                                "sort must be one of {}",
+                                L::SORTABLE.join(", ")
+                            ),
+                        ),
+                    }
+                }
+            },
+            key => {
+                let Some((name, op)) = filter_key(key) else {
+                    continue;
+                };
+                match parse_filter::<L>(name, op, &value) {
+                    Ok(filter) => params.filters.push(filter),
+                    Err((rule, message)) => reject(key, rule, message),
+                }
+            },
+        }
+    }
+
+    if params.sort.is_empty() {
+        params.sort.push(L::DEFAULT_SORT);
+    }
+    match violations.is_empty() {
+        true => Ok(params),
+        false => {
+            Err(AppError::new(CError::GenericBadRequest).with_details(violations))
+        },
+    }
+}
+
+/// `filter[name]` or `name[op]`.
+fn filter_key(key: &str) -> Option<(&str, &str)> {
+    let (head, rest) = key.split_once('[')?;
+    let inner = rest.strip_suffix(']')?;
+    match head {
+        "filter" => Some((inner, "eq")),
+        _ => Some((head, inner)),
+    }
+}
+
+fn parse_filter<L: Listing>(
+    name: &str, op: &str, value: &str,
+) -> Result<Filter, (&'static str, String)> {
+    let Some(field) = L::FILTERABLE.iter().find(|f| f.name == name) else {
+        let names: Vec<_> = L::FILTERABLE.iter().map(|f| f.name).collect();
+        return Err((
+            "allowed",
+            format!("filter must be one of {}", names.join(", ")),
+        ));
+    };
+    let op = FilterOp::parse(op)
+        .filter(|op| field.ops.contains(op))
+        .ok_or(("operator", format!("{name} does not support [{op}]")))?;
+    let value = match field.kind {
+        FilterKind::Text => FilterValue::Text(value.to_string()),
+        FilterKind::Timestamp => DateTime::parse_from_rfc3339(value)
+            .map(|t| FilterValue::Timestamp(t.with_timezone(&Utc)))
+            .map_err(|_| {
+                ("timestamp", format!("{name} must be an RFC 3339 timestamp"))
+            })?,
+        FilterKind::OneOf(allowed) => match allowed.contains(&value) {
+            true => FilterValue::Text(value.to_string()),
+            false => {
+                return Err((
+                    "one_of",
+                    format!("{name} must be one of {}", allowed.join(", ")),
+                ));
+            },
+        },
+    };
+    Ok(Filter {
+        field: field.name,
+        op,
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::listing::FilterField;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode, header};
+    use axum::routing::{get, post};
+    use serde::Deserialize;
+    use tower::ServiceExt;
+
+    #[derive(Deserialize)]
+    struct Payload {
+        name: String,
+    }
+
+    impl Validate for Payload {
+        fn validate(&self, v: &mut Validator) {
+            v.field("name", &self.name).required();
+        }
+    }
+
+    async fn call(body: &'static str) -> (StatusCode, serde_json::Value) {
+        async fn handler(ValidatedJson(body): ValidatedJson<Payload>) -> String {
+            body.name
+        }
+        let res = Router::new()
+            .route("/", post(handler))
+            .oneshot(
+                Request::post("/")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = res.status();
+        let body = axum::body::to_bytes(res.into_body(), 4096).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_rejections_render_as_bad_request() {
+        let (status, body) = call("{\"name\":").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "400000");
+        assert_eq!(body["message"], "bad request error");
+
+        let (status, body) = call("{\"name\":\" \"}").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["meta"]["violations"][0]["field"], "name");
+        assert_eq!(body["meta"]["violations"][0]["rule"], "required");
+
+        let (status, _) = call("{\"name\":\"ok\"}").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    struct Widgets;
+
+    impl Listing for Widgets {
+        const SORTABLE: &'static [&'static str] = &["created_at", "name"];
+        const FILTERABLE: &'static [FilterField] = &[
+            FilterField {
+                name: "status",
+                ops: &[FilterOp::Eq],
+                kind: FilterKind::OneOf(&["active", "retired"]),
+            },
+            FilterField {
+                name: "created_at",
+                ops: &[FilterOp::Gte, FilterOp::Lt],
+                kind: FilterKind::Timestamp,
+            },
+        ];
+        const DEFAULT_SORT: Sort = Sort {
+            field: "created_at",
+            direction: SortDirection::Desc,
+        };
+        const MAX_PER_PAGE: u32 = 50;
+    }
+
+    #[test]
+    fn test_parse_list_params() {
+        let params = parse_list_params::<Widgets>(
+            "page=2&per_page=10&sort=-created_at,name&filter[status]=active\
+             &created_at[gte]=2025-01-01T00:00:00Z&other=1",
+        )
+        .unwrap();
+        assert_eq!((params.page, params.per_page), (2, 10));
+        assert_eq!(params.offset(), 10);
+        assert_eq!(
+            params.sort,
+            [
+                Sort {
+                    field: "created_at",
+                    direction: SortDirection::Desc
+                },
+                Sort {
+                    field: "name",
+                    direction: SortDirection::Asc
+                },
+            ]
+        );
+        assert_eq!(params.filters[0].value, FilterValue::Text("active".into()));
+        assert_eq!(params.filters[1].op, FilterOp::Gte);
+
+        let defaults = parse_list_params::<Widgets>("").unwrap();
+        assert_eq!(defaults.per_page, 20);
+        assert_eq!(defaults.sort, [Widgets::DEFAULT_SORT]);
+
+        let err = parse_list_params::<Widgets>(
+            "page=0&per_page=51&sort=secret&filter[owner]=x&status[ne]=active\
+             &created_at[lt]=yesterday&filter[status]=gone",
+        )
+        .unwrap_err();
+        let rules: Vec<_> = err
+            .details()
+            .iter()
+            .map(|d| (d.field.as_str(), d.rule.as_str()))
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                ("page", "range"),
+                ("per_page", "range"),
+                ("sort", "allowed"),
+                ("filter[owner]", "allowed"),
+                ("status[ne]", "operator"),
+                ("created_at[lt]", "timestamp"),
+                ("filter[status]", "one_of"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_links_keep_query_and_replace_page() {
+        let query = ListQuery::<Widgets> {
+            params: parse_list_params::<Widgets>("page=2&per_page=10").unwrap(),
+            uri: "/api/v1/widgets?sort=name&page=2&per_page=10"
+                .parse()
+                .unwrap(),
+            listing: PhantomData,
+        };
+        assert_eq!(
+            query.links(35),
+            "</api/v1/widgets?sort=name&per_page=10&page=1>; rel=\"first\", \
+             </api/v1/widgets?sort=name&per_page=10&page=1>; rel=\"prev\", \
+             </api/v1/widgets?sort=name&per_page=10&page=3>; rel=\"next\", \
+             </api/v1/widgets?sort=name&per_page=10&page=4>; rel=\"last\""
+        );
+        assert_eq!(query.params.pagination(35).total_pages, 4);
+    }
+
+    #[tokio::test]
+    async fn test_links_keep_the_nest_prefix() {
+        async fn handler(query: ListQuery<Widgets>) -> AxumResponse {
+            let page = Page {
+                items: vec!["w"; 10],
+                total: 25,
+            };
+            query.respond("req-1".into(), page)
+        }
+        let app = Router::new().nest(
+            "/api/v1",
+            Router::new()
+                .nest("/widgets", Router::new().route("/", get(handler))),
+        );
+        let res = app
+            .oneshot(
+                Request::get("/api/v1/widgets?per_page=10")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()[HEADER_LINK],
+            "</api/v1/widgets?per_page=10&page=1>; rel=\"first\", \
+             </api/v1/widgets?per_page=10&page=2>; rel=\"next\", \
+             </api/v1/widgets?per_page=10&page=3>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn test_page_is_capped() {
+        let err = parse_list_params::<Widgets>(&format!("page={}", MAX_PAGE + 1))
+            .unwrap_err();
+        assert_eq!(err.details()[0].rule, "range");
+        let params =
+            parse_list_params::<Widgets>(&format!("page={MAX_PAGE}")).unwrap();
+        assert_eq!(params.pagination(0).page, i32::MAX);
+    }
+}
@@ -1,4 +1,6 @@
-mod app_registry;
+pub mod app_registry;
 pub mod app_state;
+pub mod extract;
 pub mod router;
 pub mod v1;
+pub mod well_known;
@@ -1,32 +1,72 @@
-use crate::common::api_response::{Response, write_problem_json};
+use crate::common::api_response::{Response, render_error};
+use crate::common::client_ip::TrustedProxies;
+use crate::common::errors::{AppError, CError, FieldViolation};
+use crate::config::reload::project;
+use crate::domains::health::HealthcheckTrait;
+use crate::middlewares::authentication_mw::AuthenticationLayer;
 use crate::middlewares::not_found_mw::not_found_middleware;
 use crate::middlewares::recovery_mw::RecoveryLayer;
+use crate::middlewares::request_context::RequestContextLayer;
 use crate::middlewares::request_id_mw::{
-    RequestIdLayer, request_id_from_headers,
+    RequestIdLayer, RequestScope, request_id_from_headers,
 };
 use crate::middlewares::request_logging_mw::RequestLoggingLayer;
 use crate::middlewares::timeout_mw::TimeoutLayer;
 use crate::web::api::app_state::AppState;
-use crate::web::api::v1::register_v1_routers;
-use axum::Router;
-use http::{Method, StatusCode};
+use crate::web::api::v1::{register_v1_routers, register_v1_startup_routers};
+use crate::web::api::well_known::{WellKnownDeps, new_well_known_router};
+use axum::response::IntoResponse;
+use axum::{Extension, Router};
+use http::{HeaderMap, Method, StatusCode, Uri};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowOrigin, CorsLayer};
 
 pub fn register_routers(state: AppState) -> Router {
+    let request_timeout =
+        project(state.settings.clone(), |s| s.server.request_timeout());
+    let cors_origins = project(state.settings.clone(), |s| {
+        s.server.cors_allowed_origins.clone()
+    });
+
     let cors = CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_origin(Any);
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            cors_origins
+                .borrow()
+                .iter()
+                .any(|o| o == "*" || o.as_bytes() == origin.as_bytes())
+        }));
+
+    let trusted_proxies = Arc::new(TrustedProxies::new(
+        &state.settings.borrow().server.trusted_proxies,
+    ));
+
+    let authentication = AuthenticationLayer::new(state.token_verifiers.clone())
+        .with_api_keys(state.api_keys.clone())
+        .with_sessions(state.sessions.clone(), state.session_cookie.clone())
+        .with_token_denylist(state.token_denylist.clone());
 
     let v1_router =
         Router::new().nest("/api/v1", register_v1_routers(state.clone()));
 
+    let well_known_router = Router::new().nest(
+        "/.well-known",
+        new_well_known_router(WellKnownDeps {
+            jwt_keys: state.jwt_keys.clone(),
+        }),
+    );
+
     let app = Router::new()
         .merge(v1_router)
+        .merge(well_known_router)
         .layer(cors)
-        .layer(TimeoutLayer::new(Duration::from_secs(2)))
-        .layer(RequestLoggingLayer::default())
+        .layer(TimeoutLayer::watching(request_timeout))
+        .layer(RequestLoggingLayer::new(trusted_proxies.clone()))
+        .layer(RequestContextLayer::new(trusted_proxies))
+        .layer(Extension(state.authorization.clone()))
+        .layer(authentication)
         .layer(RecoveryLayer::default())
         .layer(RequestIdLayer::default())
         .fallback(not_found_middleware);
@@ -34,6 +74,24 @@ pub fn register_routers(state: AppState) -> Router {
     app
 }
 
+/// Router served while `Application::build` runs. Anything but the health
+/// probes is answered with 503.
+pub fn register_startup_routers(
+    healthcheck: Arc<dyn HealthcheckTrait>,
+) -> Router {
+    Router::new()
+        .nest("/api/v1", register_v1_startup_routers(healthcheck))
+        .layer(RequestIdLayer)
+        .fallback(starting_handler)
+}
+
+async fn starting_handler(mut headers: HeaderMap, uri: Uri) -> impl IntoResponse {
+    let req_id = request_id_from_headers(&mut headers);
+    let scope = RequestScope::new(req_id, &uri, &headers);
+
+    render_error(&scope, &AppError::new(CError::GenericServiceUnavailable))
+}
+
 async fn ok_handler(
     mut headers: http::HeaderMap,
 ) -> impl axum::response::IntoResponse {
@@ -50,14 +108,10 @@ async fn ok_handler(
         .with_status(StatusCode::CREATED)
 }
 
-async fn err_handler() -> impl axum::response::IntoResponse {
-    write_problem_json(
-        StatusCode::BAD_REQUEST,
-        "https://example.com/problems/validation",
-        "Invalid input",
-        "The 'name' field is required.",
-        "/err",
-    )
+async fn err_handler() -> AppError {
+    AppError::new(CError::GenericBadRequest).with_details(vec![
+        FieldViolation::new("name", "required", "The 'name' field is required."),
+    ])
 }
 
 async fn timeout_handler(
@@ -0,0 +1,486 @@
+use crate::common::api_response::Response;
+use crate::common::errors::{AppError, CError, FieldViolation};
+use crate::common::validation::{Validate, Validator};
+use crate::config::effective::EffectiveConfig;
+use crate::config::reload::SettingsReceiver;
+use crate::domains::api_key::{ApiKeyListing, ApiKeyTrait};
+use crate::domains::authentication::AuthenticatedPrincipal;
+use crate::domains::authorization::AuthorizationTrait;
+use crate::domains::lockout::LockoutTrait;
+use crate::domains::session::{SessionInfo, SessionTrait};
+use crate::domains::signing_key::SigningKeyTrait;
+use crate::middlewares::authorization_mw::require_permission;
+use crate::middlewares::request_id_mw::request_id_from_headers;
+use crate::web::api::extract::{ListQuery, ValidatedJson, ValidatedPath};
+use axum::Router;
+use axum::extract::{Path, State};
+use axum::response::{IntoResponse, Response as AxumResponse};
+use axum::routing::{delete, get, post};
+use http::{HeaderMap, StatusCode};
+use log::{error, info};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct AdminDeps {
+    pub settings: SettingsReceiver,
+    pub api_keys: Arc<dyn ApiKeyTrait>,
+    pub sessions: Arc<dyn SessionTrait>,
+    pub signing_keys: Arc<dyn SigningKeyTrait>,
+    pub lockout: Arc<dyn LockoutTrait>,
+    pub authorization: Arc<dyn AuthorizationTrait>,
+}
+
+pub fn new_admin_router(state: AdminDeps) -> Router {
+    let config_router = Router::new()
+        .route("/config", get(effective_config))
+        .route_layer(require_permission("config:read"));
+
+    let api_key_router = Router::new()
+        .route("/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/api-keys/{id}", delete(revoke_api_key))
+        .route_layer(require_permission("api_keys:manage"));
+
+    let session_router = Router::new()
+        .route(
+            "/sessions/{subject}",
+            get(list_user_sessions).delete(revoke_user_sessions),
+        )
+        .route_layer(require_permission("sessions:manage"));
+
+    let signing_key_router = Router::new()
+        .route("/signing-keys", get(list_signing_keys))
+        .route("/signing-keys/rotate", post(rotate_signing_key))
+        .route_layer(require_permission("signing_keys:manage"));
+
+    let user_router = Router::new()
+        .route("/users/{id}/unlock", post(unlock_user))
+        .route_layer(require_permission("users:unlock"));
+
+    config_router
+        .merge(api_key_router)
+        .merge(session_router)
+        .merge(signing_key_router)
+        .merge(user_router)
+        .with_state(state)
+}
+
+/// Return the merged, redacted effective configuration with value origins.
+pub async fn effective_config(
+    mut headers: HeaderMap, State(state): State<AdminDeps>,
+) -> impl IntoResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    let settings = state.settings.borrow().clone();
+    match EffectiveConfig::describe(&settings) {
+        Ok(effective) => Response::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_data(serde_json::to_value(effective).unwrap_or_default())
+            .with_status(StatusCode::OK),
+        Err(e) => {
+            error!("failed to describe effective configuration: {e}");
+            let err = CError::GenericInternalServer;
+            Response::<serde_json::Value>::new_with_request_id(req_id)
+                .with_code(err.code().unwrap_or_default())
+                .with_message(err.message())
+                .with_status(StatusCode::INTERNAL_SERVER_ERROR)
+        },
+    }
+}
+
+/// Longest lifetime a new API key may be given: ten years.
+const MAX_API_KEY_TTL_SECONDS: u64 = 10 * 365 * 24 * 3600;
+
+#[derive(Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// Permissions granted to the key
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Lifetime in seconds; the key never expires when omitted
+    pub expires_in: Option<u64>,
+}
+
+impl Validate for CreateApiKeyRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("name", &self.name).required().length(1, 100);
+        for (i, scope) in self.scopes.iter().enumerate() {
+            v.field(&format!("scopes[{i}]"), scope).required();
+        }
+        if let Some(expires_in) = &self.expires_in {
+            v.field("expires_in", expires_in)
+                .range(1, MAX_API_KEY_TTL_SECONDS);
+        }
+    }
+}
+
+/// Create an API key. The plaintext key is only ever returned here. Callers
+/// can only grant scopes they hold themselves.
+pub async fn create_api_key(
+    mut headers: HeaderMap, State(state): State<AdminDeps>,
+    principal: AuthenticatedPrincipal,
+    ValidatedJson(body): ValidatedJson<CreateApiKeyRequest>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    let granted =
+        match state.authorization.permissions_for(&principal.roles).await {
+            Ok(granted) => granted,
+            Err(err) => return err.into_response(),
+        };
+    let not_held: Vec<FieldViolation> = body
+        .scopes
+        .iter()
+        .enumerate()
+        .filter(|(_, scope)| {
+            !principal.scopes.contains(scope) && !granted.contains(*scope)
+        })
+        .map(|(i, scope)| {
+            FieldViolation::new(
+                format!("scopes[{i}]"),
+                "held_by_caller",
+                format!(
+                    "scopes[{i}] {scope:?} is not a permission of the caller"
+                ),
+            )
+        })
+        .collect();
+    if !not_held.is_empty() {
+        return AppError::new(CError::GenericPermission)
+            .with_details(not_held)
+            .with_context(format!(
+                "api key {:?} requested by {}",
+                body.name, principal.subject
+            ))
+            .into_response();
+    }
+
+    match state
+        .api_keys
+        .create(body.name, body.scopes, body.expires_in, principal.subject)
+        .await
+    {
+        Ok(created) => {
+            info!(
+                "api key {} ({}) created by {}",
+                created.info.id, created.info.prefix, created.info.created_by
+            );
+            Response::new_with_request_id(req_id)
+                .with_code("OK")
+                .with_message("OK")
+                .with_data(created)
+                .with_status(StatusCode::CREATED)
+        },
+        Err(err) => err.into_response(),
+    }
+}
+
+/// List API keys, including revoked and expired ones. Paged, sorted and
+/// filtered as described by `ApiKeyListing`.
+pub async fn list_api_keys(
+    mut headers: HeaderMap, State(state): State<AdminDeps>,
+    query: ListQuery<ApiKeyListing>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.api_keys.list(&query.params).await {
+        Ok(keys) => query.respond(req_id, keys),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Revoke an API key; it stops authenticating immediately.
+pub async fn revoke_api_key(
+    mut headers: HeaderMap, State(state): State<AdminDeps>,
+    principal: AuthenticatedPrincipal, ValidatedPath(id): ValidatedPath<Uuid>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.api_keys.revoke(id).await {
+        Ok(()) => {
+            info!("api key {id} revoked by {}", principal.subject);
+            Response::<serde_json::Value>::new_with_request_id(req_id)
+                .with_code("OK")
+                .with_message("OK")
+                .with_status(StatusCode::OK)
+        },
+        Err(err) => err.into_response(),
+    }
+}
+
+/// List the active sessions of a user.
+pub async fn list_user_sessions(
+    mut headers: HeaderMap, State(state): State<AdminDeps>,
+    Path(subject): Path<String>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.sessions.list(&subject).await {
+        Ok(sessions) => Response::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_data(
+                sessions
+                    .iter()
+                    .map(|s| SessionInfo::from_session(s, None))
+                    .collect::<Vec<_>>(),
+            )
+            .with_status(StatusCode::OK),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Revoke every session of a user.
+pub async fn revoke_user_sessions(
+    mut headers: HeaderMap, State(state): State<AdminDeps>,
+    principal: AuthenticatedPrincipal, Path(subject): Path<String>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.sessions.revoke_all(&subject).await {
+        Ok(revoked) => {
+            info!(
+                "{revoked} session(s) of {subject} revoked by {}",
+                principal.subject
+            );
+            Response::new_with_request_id(req_id)
+                .with_code("OK")
+                .with_message("OK")
+                .with_data(serde_json::json!({ "revoked": revoked }))
+                .with_status(StatusCode::OK)
+        },
+        Err(err) => err.into_response(),
+    }
+}
+
+/// List token signing keys, newest first.
+pub async fn list_signing_keys(
+    mut headers: HeaderMap, State(state): State<AdminDeps>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.signing_keys.list().await {
+        Ok(keys) => Response::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_data(keys)
+            .with_status(StatusCode::OK),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Rotate the token signing key now. Tokens signed by the previous key stay
+/// valid until they expire; other instances pick up the new key on their next
+/// key ring sync.
+pub async fn rotate_signing_key(
+    mut headers: HeaderMap, State(state): State<AdminDeps>,
+    principal: AuthenticatedPrincipal,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.signing_keys.rotate().await {
+        Ok(key) => {
+            info!(
+                "signing key rotated to {} by {}",
+                key.kid, principal.subject
+            );
+            Response::new_with_request_id(req_id)
+                .with_code("OK")
+                .with_message("OK")
+                .with_data(key)
+                .with_status(StatusCode::CREATED)
+        },
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Lift a login lockout and clear the account's failed login count.
+pub async fn unlock_user(
+    mut headers: HeaderMap, State(state): State<AdminDeps>,
+    principal: AuthenticatedPrincipal, ValidatedPath(id): ValidatedPath<Uuid>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.lockout.unlock(id).await {
+        Ok(()) => {
+            info!("user {id} unlocked by {}", principal.subject);
+            Response::<serde_json::Value>::new_with_request_id(req_id)
+                .with_code("OK")
+                .with_message("OK")
+                .with_status(StatusCode::OK)
+        },
+        Err(err) => err.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common;
+    use crate::config::env_settings::Settings;
+    use crate::domains::api_key::{ApiKeyInfo, CreatedApiKey};
+    use crate::domains::listing::{ListParams, Page};
+    use crate::infrastructures::cache::local_cache::CacheRegistry;
+    use crate::infrastructures::database::testing::unconnected_pool;
+    use crate::infrastructures::jwt::JwtKeys;
+    use crate::infrastructures::session::CacheSessionStore;
+    use crate::services::v1::lockout::LockoutService;
+    use crate::services::v1::session::SessionService;
+    use crate::services::v1::signing_key::SigningKeyService;
+    use async_trait::async_trait;
+    use axum::Extension;
+    use axum::body::Body;
+    use http::{Request, header};
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+    use tokio::sync::watch;
+    use tower::ServiceExt;
+
+    /// Records the scopes of every created key.
+    #[derive(Default)]
+    struct FakeApiKeys {
+        created: Mutex<Vec<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl ApiKeyTrait for FakeApiKeys {
+        async fn create(
+            &self, _: String, scopes: Vec<String>, _: Option<u64>, _: String,
+        ) -> common::errors::Result<CreatedApiKey> {
+            self.created.lock().unwrap().push(scopes);
+            Ok(CreatedApiKey::default())
+        }
+
+        async fn list(
+            &self, _: &ListParams,
+        ) -> common::errors::Result<Page<ApiKeyInfo>> {
+            Ok(Page::default())
+        }
+
+        async fn revoke(&self, _: Uuid) -> common::errors::Result<()> {
+            Ok(())
+        }
+
+        async fn authenticate(
+            &self, _: &str,
+        ) -> common::errors::Result<AuthenticatedPrincipal> {
+            Err(CError::GenericUnauthorized)
+        }
+    }
+
+    /// `key-admin` may manage keys and read users.
+    struct StaticAuthorizer;
+
+    #[async_trait]
+    impl AuthorizationTrait for StaticAuthorizer {
+        async fn permissions_for(
+            &self, roles: &[String],
+        ) -> common::errors::Result<HashSet<String>> {
+            Ok(roles
+                .iter()
+                .filter(|r| *r == "key-admin")
+                .flat_map(|_| ["api_keys:manage", "users:read"])
+                .map(str::to_string)
+                .collect())
+        }
+    }
+
+    fn app(api_keys: Arc<FakeApiKeys>) -> Router {
+        let settings = Settings::default();
+        let caches = CacheRegistry::init().clone();
+        let authorization: Arc<dyn AuthorizationTrait> =
+            Arc::new(StaticAuthorizer);
+        let state = AdminDeps {
+            settings: watch::channel(Arc::new(settings.clone())).1,
+            api_keys,
+            sessions: Arc::new(SessionService::new(
+                Arc::new(CacheSessionStore::new(caches.clone())),
+                &settings.session,
+            )),
+            signing_keys: Arc::new(SigningKeyService::new(
+                unconnected_pool(),
+                Arc::new(JwtKeys::from_config(&settings.auth)),
+                &settings.auth.jwt_secret,
+                &settings.auth.signing_keys,
+            )),
+            lockout: Arc::new(LockoutService::new(
+                unconnected_pool(),
+                caches,
+                &settings.auth.lockout,
+            )),
+            authorization: authorization.clone(),
+        };
+        let principal = AuthenticatedPrincipal {
+            subject: "admin-1".to_string(),
+            issuer: "example-service".to_string(),
+            username: None,
+            roles: vec!["key-admin".to_string()],
+            scopes: vec!["audit:read".to_string()],
+            token_id: None,
+            expires_at: None,
+        };
+        new_admin_router(state)
+            .layer(Extension(authorization))
+            .layer(Extension(principal))
+    }
+
+    async fn create(app: &Router, body: &str) -> (StatusCode, String) {
+        let req = Request::post("/api-keys")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let body = axum::body::to_bytes(res.into_body(), 64 * 1024)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_create_api_key_only_grants_scopes_the_caller_holds() {
+        let api_keys = Arc::new(FakeApiKeys::default());
+        let app = app(api_keys.clone());
+
+        let (status, _) = create(
+            &app,
+            r#"{"name":"ci","scopes":["users:read","audit:read"]}"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (status, body) = create(
+            &app,
+            r#"{"name":"ci","scopes":["users:read","users:write"]}"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(body.contains("scopes[1]"), "{body}");
+        assert!(body.contains("held_by_caller"), "{body}");
+
+        assert_eq!(
+            *api_keys.created.lock().unwrap(),
+            [vec!["users:read".to_string(), "audit:read".to_string()]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_api_key_caps_expires_in() {
+        let api_keys = Arc::new(FakeApiKeys::default());
+        let app = app(api_keys.clone());
+
+        let (status, body) = create(
+            &app,
+            &format!(
+                r#"{{"name":"ci","expires_in":{}}}"#,
+                MAX_API_KEY_TTL_SECONDS + 1
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("expires_in"), "{body}");
+        assert!(api_keys.created.lock().unwrap().is_empty());
+    }
+}
@@ -1,30 +1,679 @@
-use crate::domains::authentication::AuthenticationTrait;
-use axum::Router;
-use axum::routing::{get, post};
-use openidconnect::core::CoreClient;
+use crate::common::api_response::Response;
+use crate::common::client_ip::TrustedProxies;
+use crate::common::errors::{AppResult, CError};
+use crate::common::validation::{Validate, Validator};
+use crate::constants::http::{HEADER_RETRY_AFTER, HEADER_SET_COOKIE};
+use crate::domains::account::AccountTrait;
+use crate::domains::authentication::{
+    AuthenticatedPrincipal, AuthenticationTrait, LoginOutcome,
+    OidcAuthenticationTrait,
+};
+use crate::domains::mfa::{MfaSubject, MfaTrait};
+use crate::domains::session::{SessionInfo, SessionTrait};
+use crate::domains::token::TokenIntrospectionTrait;
+use crate::infrastructures::session::cookie::SessionCookie;
+use crate::middlewares::authentication_mw::unauthorized;
+use crate::middlewares::authorization_mw::require_permission;
+use crate::middlewares::request_id_mw::request_id_from_headers;
+use crate::web::api::extract::{ValidatedJson, ValidatedQuery};
+use axum::extract::{ConnectInfo, Path, State};
+use axum::response::{IntoResponse, Redirect, Response as AxumResponse};
+use axum::routing::{delete, get, post};
+use axum::{Extension, Form, Json, Router};
+use http::{HeaderMap, HeaderValue, StatusCode, header};
+use log::info;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::json;
+use std::net::SocketAddr;
 use std::sync::Arc;
 
-async fn x() {}
+static TOTP_CODE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\d{6}$").unwrap());
 
 #[derive(Clone)]
 pub struct AuthenticationDeps {
     pub authentication: Arc<dyn AuthenticationTrait>,
-    // pub oidc: CoreClient,
+    pub mfa: Arc<dyn MfaTrait>,
+    pub accounts: Arc<dyn AccountTrait>,
+    pub tokens: Arc<dyn TokenIntrospectionTrait>,
+    pub oidc: Option<Arc<dyn OidcAuthenticationTrait>>,
+    pub sessions: Arc<dyn SessionTrait>,
+    pub session_cookie: Arc<SessionCookie>,
+    pub trusted_proxies: Arc<TrustedProxies>,
     // pub tracer: Tracer,
     // pub logger: Arc<Logger>,
 }
 
+#[derive(Clone)]
+struct OidcDeps {
+    oidc: Arc<dyn OidcAuthenticationTrait>,
+    sessions: Arc<dyn SessionTrait>,
+    cookie: Arc<SessionCookie>,
+}
+
 pub fn new_authentication_router(state: AuthenticationDeps) -> Router {
-    let oidc_router = Router::new()
-        .route("/oidc/callback", get(x))
-        .route("/oidc/redirect", get(x))
+    let basic_router = Router::new()
+        .route("/login", post(login))
+        .route("/logout", post(logout))
+        .route("/refresh", post(refresh))
+        .route("/mfa/verify", post(verify_mfa))
+        .route("/mfa/totp/enroll", post(enroll_totp))
+        .route("/mfa/totp/activate", post(activate_totp))
+        .route("/mfa/totp/disable", post(disable_totp))
+        .route("/mfa/recovery-codes", post(regenerate_recovery_codes))
+        .route("/password/forgot", post(forgot_password))
+        .route("/password/reset", post(reset_password))
+        .route("/email/verification", post(request_email_verification))
+        .route("/email/verify", post(verify_email))
         .with_state(state.clone());
 
-    let basic_router = Router::new()
-        .route("/login", post(x))
-        .route("/logout", post(x))
-        .route("/refresh", post(x))
+    // Client credentials (an API key) holding these permissions; see
+    // `AuthenticationLayer`.
+    let introspection_router = Router::new()
+        .route("/introspect", post(introspect))
+        .route_layer(require_permission("tokens:introspect"))
+        .with_state(state.clone());
+    let revocation_router = Router::new()
+        .route("/revoke", post(revoke_token))
+        .route_layer(require_permission("tokens:revoke"))
         .with_state(state.clone());
 
-    Router::new().merge(oidc_router).merge(basic_router)
+    let mut router = Router::new()
+        .merge(basic_router)
+        .merge(introspection_router)
+        .merge(revocation_router);
+
+    if let Some(oidc) = state.oidc.clone() {
+        // Sessions are only ever created by the OIDC callback.
+        let oidc_router = Router::new()
+            .route("/oidc/callback", get(oidc_callback))
+            .route("/oidc/redirect", get(oidc_redirect))
+            .route("/sessions", get(list_sessions))
+            .route("/sessions/{handle}", delete(revoke_session))
+            .route("/session/logout", post(session_logout))
+            .with_state(OidcDeps {
+                oidc,
+                sessions: state.sessions.clone(),
+                cookie: state.session_cookie.clone(),
+            });
+        router = router.merge(oidc_router);
+    }
+
+    router
+}
+
+#[derive(Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+impl Validate for LoginRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("username", &self.username)
+            .required()
+            .length(1, 255);
+        v.field("password", &self.password)
+            .required()
+            .length(1, 1024);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+impl Validate for RefreshTokenRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("refresh_token", &self.refresh_token).required();
+    }
+}
+
+#[derive(Deserialize)]
+pub struct VerifyMfaRequest {
+    pub mfa_token: String,
+    /// TOTP code or recovery code
+    pub code: String,
+}
+
+impl Validate for VerifyMfaRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("mfa_token", &self.mfa_token).required();
+        v.field("code", &self.code).required().length(1, 64);
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct EnrollTotpRequest {
+    /// Challenge token of a login that requires enrollment; not needed when
+    /// called with an access token
+    pub mfa_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MfaCodeRequest {
+    pub code: String,
+}
+
+impl Validate for MfaCodeRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("code", &self.code).matches(&TOTP_CODE, "6 digits");
+    }
+}
+
+/// RFC 7662 / RFC 7009 form body.
+#[derive(Deserialize)]
+pub struct TokenRequest {
+    pub token: String,
+    pub token_type_hint: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+impl Validate for ForgotPasswordRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("email", &self.email).required().email();
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub password: String,
+}
+
+/// Password strength is checked by the account service.
+impl Validate for ResetPasswordRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("token", &self.token).required();
+        v.field("password", &self.password)
+            .required()
+            .length(1, 1024);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+impl Validate for VerifyEmailRequest {
+    fn validate(&self, v: &mut Validator) {
+        v.field("token", &self.token).required();
+    }
+}
+
+/// Throttling key of the caller; empty when the peer address is unknown.
+fn client_ip(
+    state: &AuthenticationDeps,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    headers: &HeaderMap,
+) -> String {
+    let peer = connect_info.map(|Extension(ConnectInfo(addr))| addr.ip());
+    state
+        .trusted_proxies
+        .client_ip(peer, headers)
+        .map(|ip| ip.to_string())
+        .unwrap_or_default()
+}
+
+/// Exchange username and password for an access and refresh token pair, or
+/// for an MFA challenge to complete at `/mfa/verify`. Repeated failures are
+/// answered with 429 and `Retry-After`.
+async fn login(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    ValidatedJson(body): ValidatedJson<LoginRequest>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+    let client_ip = client_ip(&state, connect_info, &headers);
+
+    match state
+        .authentication
+        .login(body.username, body.password, client_ip)
+        .await
+    {
+        Ok(LoginOutcome::Tokens(tokens)) => Response::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_data(tokens)
+            .with_status(StatusCode::OK),
+        Ok(LoginOutcome::MfaRequired(challenge)) => {
+            Response::new_with_request_id(req_id)
+                .with_code("MFA_REQUIRED")
+                .with_message("second factor required")
+                .with_data(challenge)
+                .with_status(StatusCode::OK)
+        },
+        Ok(LoginOutcome::Throttled(retry_after)) => {
+            let mut res = CError::TooManyLoginAttempts.into_response();
+            res.headers_mut()
+                .insert(HEADER_RETRY_AFTER, HeaderValue::from(retry_after));
+            res
+        },
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Complete an MFA challenge with a TOTP or recovery code.
+async fn verify_mfa(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    ValidatedJson(body): ValidatedJson<VerifyMfaRequest>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+    let client_ip = client_ip(&state, connect_info, &headers);
+
+    match state
+        .authentication
+        .verify_mfa(body.mfa_token, body.code, client_ip)
+        .await
+    {
+        Ok(login) => Response::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_data(login)
+            .with_status(StatusCode::OK),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Generate a TOTP secret. Enrollment takes effect once activated with a
+/// code from the authenticator app.
+async fn enroll_totp(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    principal: Option<Extension<AuthenticatedPrincipal>>,
+    body: Option<Json<EnrollTotpRequest>>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+    let subject = match (principal, body.mfa_token) {
+        (_, Some(mfa_token)) => MfaSubject::Pending(mfa_token),
+        (Some(Extension(principal)), None) => MfaSubject::Principal(principal),
+        (None, None) => return unauthorized(req_id),
+    };
+    match state.mfa.enroll(subject).await {
+        Ok(enrollment) => Response::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_data(enrollment)
+            .with_status(StatusCode::OK),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Activate a pending enrollment. The recovery codes are only returned here.
+async fn activate_totp(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    principal: AuthenticatedPrincipal,
+    ValidatedJson(body): ValidatedJson<MfaCodeRequest>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    let Some(user_id) = state.mfa.local_user(&principal) else {
+        return CError::GenericPermission.into_response();
+    };
+    match state.mfa.activate(user_id, &body.code).await {
+        Ok(codes) => {
+            info!("totp enabled for {user_id}");
+            recovery_codes_response(req_id, codes)
+        },
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Replace all recovery codes.
+async fn regenerate_recovery_codes(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    principal: AuthenticatedPrincipal,
+    ValidatedJson(body): ValidatedJson<MfaCodeRequest>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    let Some(user_id) = state.mfa.local_user(&principal) else {
+        return CError::GenericPermission.into_response();
+    };
+    match state
+        .mfa
+        .regenerate_recovery_codes(user_id, &body.code)
+        .await
+    {
+        Ok(codes) => recovery_codes_response(req_id, codes),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Remove the caller's second factor.
+async fn disable_totp(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    principal: AuthenticatedPrincipal,
+    ValidatedJson(body): ValidatedJson<MfaCodeRequest>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    let Some(user_id) = state.mfa.local_user(&principal) else {
+        return CError::GenericPermission.into_response();
+    };
+    match state.mfa.disable(user_id, &body.code).await {
+        Ok(()) => {
+            info!("totp disabled for {user_id}");
+            Response::<serde_json::Value>::new_with_request_id(req_id)
+                .with_code("OK")
+                .with_message("OK")
+                .with_status(StatusCode::OK)
+        },
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Token introspection (RFC 7662). Served bare rather than in the response
+/// envelope so standard OAuth clients can consume it.
+async fn introspect(
+    State(state): State<AuthenticationDeps>, Form(body): Form<TokenRequest>,
+) -> AppResult<AxumResponse> {
+    let introspection = state
+        .tokens
+        .introspect(&body.token, body.token_type_hint.as_deref())
+        .await?;
+    Ok((
+        [(header::CACHE_CONTROL, HeaderValue::from_static("no-store"))],
+        Json(introspection),
+    )
+        .into_response())
+}
+
+/// Token revocation (RFC 7009). Succeeds for unknown and already revoked
+/// tokens alike.
+async fn revoke_token(
+    State(state): State<AuthenticationDeps>, principal: AuthenticatedPrincipal,
+    Form(body): Form<TokenRequest>,
+) -> AppResult<StatusCode> {
+    state
+        .tokens
+        .revoke(&body.token, body.token_type_hint.as_deref())
+        .await?;
+    info!("token revocation requested by {}", principal.subject);
+    Ok(StatusCode::OK)
+}
+
+/// Mail a password reset link. Always accepted, whether or not an account
+/// uses the address.
+async fn forgot_password(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    ValidatedJson(body): ValidatedJson<ForgotPasswordRequest>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.accounts.request_password_reset(body.email).await {
+        Ok(()) => Response::<serde_json::Value>::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_status(StatusCode::ACCEPTED),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Choose a new password with the token from a reset link. Signs the account
+/// out everywhere.
+async fn reset_password(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    ValidatedJson(body): ValidatedJson<ResetPasswordRequest>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state
+        .accounts
+        .reset_password(body.token, body.password)
+        .await
+    {
+        Ok(()) => Response::<serde_json::Value>::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_status(StatusCode::OK),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Mail a verification link to the caller's email address.
+async fn request_email_verification(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    principal: AuthenticatedPrincipal,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    let Some(user_id) = state.mfa.local_user(&principal) else {
+        return CError::GenericPermission.into_response();
+    };
+    match state.accounts.request_email_verification(user_id).await {
+        Ok(()) => Response::<serde_json::Value>::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_status(StatusCode::ACCEPTED),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Confirm an email address with the token from a verification link.
+async fn verify_email(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    ValidatedJson(body): ValidatedJson<VerifyEmailRequest>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.accounts.verify_email(body.token).await {
+        Ok(()) => Response::<serde_json::Value>::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_status(StatusCode::OK),
+        Err(err) => err.into_response(),
+    }
+}
+
+fn recovery_codes_response(req_id: String, codes: Vec<String>) -> AxumResponse {
+    Response::new_with_request_id(req_id)
+        .with_code("OK")
+        .with_message("OK")
+        .with_data(json!({ "recovery_codes": codes }))
+        .with_status(StatusCode::OK)
+}
+
+/// Revoke a refresh token. Unknown tokens are accepted silently.
+async fn logout(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    ValidatedJson(body): ValidatedJson<RefreshTokenRequest>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.authentication.logout(body.refresh_token).await {
+        Ok(()) => Response::<serde_json::Value>::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_status(StatusCode::OK),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Issue a new access token for a valid refresh token.
+async fn refresh(
+    mut headers: HeaderMap, State(state): State<AuthenticationDeps>,
+    ValidatedJson(body): ValidatedJson<RefreshTokenRequest>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.authentication.refresh(body.refresh_token).await {
+        Ok(tokens) => Response::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_data(tokens)
+            .with_status(StatusCode::OK),
+        Err(err) => err.into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcRedirectQuery {
+    /// Local path to return to after login
+    pub return_to: Option<String>,
+}
+
+impl Validate for OidcRedirectQuery {
+    fn validate(&self, v: &mut Validator) {
+        if let Some(return_to) = &self.return_to {
+            v.field("return_to", return_to).length(1, 2048);
+        }
+    }
+}
+
+/// Either `code` and `state`, or the `error` the provider redirected with.
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+impl Validate for OidcCallbackQuery {
+    fn validate(&self, v: &mut Validator) {
+        if self.error.is_some() {
+            return;
+        }
+        v.field("code", self.code.as_deref().unwrap_or_default())
+            .required()
+            .length(1, 2048);
+        v.field("state", self.state.as_deref().unwrap_or_default())
+            .required()
+            .length(1, 256);
+    }
+}
+
+/// Start the authorization-code flow by redirecting to the provider.
+async fn oidc_redirect(
+    State(state): State<OidcDeps>,
+    ValidatedQuery(query): ValidatedQuery<OidcRedirectQuery>,
+) -> AxumResponse {
+    match state.oidc.authorize(query.return_to).await {
+        Ok(auth) => Redirect::to(&auth.url).into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Finish the flow: exchange the code, validate the ID token and set the
+/// session cookie.
+async fn oidc_callback(
+    mut headers: HeaderMap, State(state): State<OidcDeps>,
+    ValidatedQuery(query): ValidatedQuery<OidcCallbackQuery>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    if let Some(error) = query.error {
+        let err = CError::GenericUnauthorized;
+        return Response::<serde_json::Value>::new_with_request_id(req_id)
+            .with_code(err.code().unwrap_or_default())
+            .with_message(err.message())
+            .with_meta_kv("error", error)
+            .with_meta_kv(
+                "error_description",
+                query.error_description.unwrap_or_default(),
+            )
+            .with_status(StatusCode::UNAUTHORIZED);
+    }
+    let (Some(code), Some(oidc_state)) = (query.code, query.state) else {
+        return CError::GenericBadRequest.into_response();
+    };
+
+    let current = state.cookie.read(&headers);
+    let login = match state.oidc.complete(code, oidc_state, current).await {
+        Ok(login) => login,
+        Err(err) => return err.into_response(),
+    };
+
+    let cookie = state.cookie.set(&login.session_id);
+
+    let mut resp = match login.return_to {
+        Some(path) => Redirect::to(&path).into_response(),
+        None => Response::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_data(json!({
+                "subject": login.identity.subject,
+                "email": login.identity.email,
+                "name": login.identity.name,
+                "expires_in": login.expires_in,
+            }))
+            .with_status(StatusCode::OK),
+    };
+    if let Ok(v) = HeaderValue::from_str(&cookie) {
+        resp.headers_mut().append(HEADER_SET_COOKIE, v);
+    }
+    resp
+}
+
+/// List the caller's active sessions.
+async fn list_sessions(
+    mut headers: HeaderMap, State(state): State<OidcDeps>,
+    principal: AuthenticatedPrincipal,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+    let current = state.cookie.read(&headers);
+
+    match state.sessions.list(&principal.subject).await {
+        Ok(sessions) => Response::new_with_request_id(req_id)
+            .with_code("OK")
+            .with_message("OK")
+            .with_data(
+                sessions
+                    .iter()
+                    .map(|s| SessionInfo::from_session(s, current.as_deref()))
+                    .collect::<Vec<_>>(),
+            )
+            .with_status(StatusCode::OK),
+        Err(err) => err.into_response(),
+    }
+}
+
+/// Revoke one of the caller's sessions, e.g. on a lost device.
+async fn revoke_session(
+    mut headers: HeaderMap, State(state): State<OidcDeps>,
+    principal: AuthenticatedPrincipal, Path(handle): Path<String>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    match state.sessions.revoke(&principal.subject, &handle).await {
+        Ok(()) => {
+            info!("session {handle} of {} revoked", principal.subject);
+            Response::<serde_json::Value>::new_with_request_id(req_id)
+                .with_code("OK")
+                .with_message("OK")
+                .with_status(StatusCode::OK)
+        },
+        Err(err) => err.into_response(),
+    }
+}
+
+/// End the current session server-side and clear the cookie.
+async fn session_logout(
+    mut headers: HeaderMap, State(state): State<OidcDeps>,
+) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+
+    if let Some(id) = state.cookie.read(&headers)
+        && let Err(err) = state.sessions.end(&id).await
+    {
+        return err.into_response();
+    }
+    let mut resp = Response::<serde_json::Value>::new_with_request_id(req_id)
+        .with_code("OK")
+        .with_message("OK")
+        .with_status(StatusCode::OK);
+    if let Ok(v) = HeaderValue::from_str(&state.cookie.clear()) {
+        resp.headers_mut().append(HEADER_SET_COOKIE, v);
+    }
+    resp
 }
@@ -1,14 +1,16 @@
 use crate::common::api_response::Response;
-use crate::domains::health::HealthcheckTrait;
+use crate::common::errors::AppResult;
+use crate::domains::health::{HealthStatus, HealthcheckTrait, LifecycleState};
 use crate::middlewares::request_id_mw::request_id_from_headers;
 use axum::Router;
 use axum::extract::State;
-use axum::response::IntoResponse;
+use axum::response::Response as AxumResponse;
 use axum::routing::get;
 use http::{HeaderMap, StatusCode};
 use std::sync::Arc;
-use log::Log;
+use log::{Log, warn};
 use opentelemetry::global::BoxedTracer;
+use serde_json::json;
 
 #[derive(Clone)]
 pub struct HealthcheckDeps {
@@ -38,49 +40,87 @@ pub fn new_healthcheck_router(state: HealthcheckDeps) -> Router {
 
 pub async fn health(
     mut headers: HeaderMap, State(state): State<HealthcheckDeps>,
-) -> impl IntoResponse {
+) -> AppResult<AxumResponse> {
     let req_id = request_id_from_headers(&mut headers);
-    let result = state.healthcheck.health().await.unwrap();
+    let result = state.healthcheck.health().await?;
 
-    Response::new_with_request_id(req_id)
+    Ok(Response::new_with_request_id(req_id)
         .with_code(result.code)
         .with_message(result.message)
         .with_data(result.data)
-        .with_status(StatusCode::OK)
+        .with_status(StatusCode::OK))
 }
 
 pub async fn live(
     mut headers: HeaderMap, State(state): State<HealthcheckDeps>,
-) -> impl IntoResponse {
+) -> AppResult<AxumResponse> {
     let req_id = request_id_from_headers(&mut headers);
-    let result = state.healthcheck.live().await.unwrap();
-    Response::new_with_request_id(req_id)
+    let result = state.healthcheck.live().await?;
+    Ok(Response::new_with_request_id(req_id)
         .with_code(result.code)
         .with_message(result.message)
         .with_data(result.data)
-        .with_status(StatusCode::OK)
+        .with_status(StatusCode::OK))
 }
 
+/// Readiness of every registered dependency: 503 when a critical one is
+/// down or the service is not `ready`, 200 otherwise (with status `degraded`
+/// if a non-critical dependency is down).
 pub async fn ready(
     mut headers: HeaderMap, State(state): State<HealthcheckDeps>,
-) -> impl IntoResponse {
+) -> AppResult<AxumResponse> {
     let req_id = request_id_from_headers(&mut headers);
-    let result = state.healthcheck.ready().await.unwrap();
-    Response::new_with_request_id(req_id)
-        .with_code(result.code)
-        .with_message(result.message)
-        .with_data(result.data)
-        .with_status(StatusCode::OK)
+    let result = state.healthcheck.ready().await?;
+    let (code, message, status) = match (result.state, result.status) {
+        (LifecycleState::Ready, HealthStatus::Up) => ("OK", "OK", StatusCode::OK),
+        (LifecycleState::Ready, HealthStatus::Degraded) => (
+            "DEGRADED",
+            "non-critical dependencies unavailable",
+            StatusCode::OK,
+        ),
+        (LifecycleState::Ready, HealthStatus::Down) => (
+            "UNAVAILABLE",
+            "critical dependencies unavailable",
+            StatusCode::SERVICE_UNAVAILABLE,
+        ),
+        (LifecycleState::Starting, _) => (
+            "STARTING",
+            "initialization in progress",
+            StatusCode::SERVICE_UNAVAILABLE,
+        ),
+        _ => ("DRAINING", "shutting down", StatusCode::SERVICE_UNAVAILABLE),
+    };
+    for component in result.components.iter().filter(|c| c.error.is_some()) {
+        warn!(
+            "readiness check {} failed: {}",
+            component.name,
+            component.error.as_deref().unwrap_or_default()
+        );
+    }
+    Ok(Response::new_with_request_id(req_id)
+        .with_code(code)
+        .with_message(message)
+        .with_data(result)
+        .with_status(status))
 }
 
+/// 503 until initialization has finished.
 pub async fn started(
     mut headers: HeaderMap, State(state): State<HealthcheckDeps>,
-) -> impl IntoResponse {
+) -> AppResult<AxumResponse> {
     let req_id = request_id_from_headers(&mut headers);
-    let result = state.healthcheck.started().await.unwrap();
-    Response::new_with_request_id(req_id)
-        .with_code(result.code)
-        .with_message(result.message)
-        .with_data(result.data)
-        .with_status(StatusCode::OK)
+    let lifecycle = state.healthcheck.started().await?;
+    let (code, message, status) = match lifecycle {
+        LifecycleState::Starting => (
+            "STARTING",
+            "initialization in progress",
+            StatusCode::SERVICE_UNAVAILABLE,
+        ),
+        _ => ("OK", "OK", StatusCode::OK),
+    };
+    Ok(Response::new_with_request_id(req_id)
+        .with_code(code)
+        .with_message(message)
+        .with_data(json!({ "state": lifecycle }))
+        .with_status(status))
 }
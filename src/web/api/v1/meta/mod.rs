@@ -0,0 +1,38 @@
+use crate::common::api_response::Response;
+use crate::common::errors::catalog::{ErrorDescriptor, catalog};
+use crate::common::i18n;
+use crate::middlewares::request_id_mw::{RequestScope, request_id_from_headers};
+use axum::Router;
+use axum::response::{IntoResponse, Response as AxumResponse};
+use axum::routing::get;
+use http::{HeaderMap, HeaderValue, StatusCode, header};
+
+/// The catalog only changes with a deploy.
+const CATALOG_MAX_AGE: &str = "public, max-age=3600";
+
+pub fn new_meta_router() -> Router {
+    Router::new().route("/errors", get(errors))
+}
+
+/// Every error code the API can respond with, messages in the negotiated
+/// locale. Problem `type` URIs point at entries of this list by slug.
+pub async fn errors(mut headers: HeaderMap) -> AxumResponse {
+    let req_id = request_id_from_headers(&mut headers);
+    let locale = RequestScope::current_or_new().locale;
+    let mut errors = catalog();
+    for e in &mut errors {
+        e.message = i18n::localize(locale, e.code, e.message);
+    }
+
+    let mut res = Response::<Vec<ErrorDescriptor>>::new_with_request_id(req_id)
+        .with_code("OK")
+        .with_message("OK")
+        .with_count(errors.len() as i32)
+        .with_data(errors)
+        .with_status(StatusCode::OK);
+    res.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(CATALOG_MAX_AGE),
+    );
+    res.into_response()
+}
@@ -1,12 +1,19 @@
+mod admin;
 mod authentication;
 mod healthcheck;
+mod meta;
 
+use crate::common::client_ip::TrustedProxies;
+use crate::domains::health::HealthcheckTrait;
 use crate::web::api::app_state::AppState;
+use crate::web::api::v1::admin::{AdminDeps, new_admin_router};
 use crate::web::api::v1::authentication::{
     AuthenticationDeps, new_authentication_router,
 };
 use crate::web::api::v1::healthcheck::{HealthcheckDeps, new_healthcheck_router};
+use crate::web::api::v1::meta::new_meta_router;
 use axum::Router;
+use std::sync::Arc;
 
 pub fn register_v1_routers(state: AppState) -> Router {
     let healthcheck_state = HealthcheckDeps::new(
@@ -17,10 +24,46 @@ pub fn register_v1_routers(state: AppState) -> Router {
 
     let authentication_state = AuthenticationDeps {
         authentication: state.authentication.clone(),
-        // oidc: state.oidc.clone(),
+        mfa: state.mfa.clone(),
+        accounts: state.accounts.clone(),
+        tokens: state.tokens.clone(),
+        oidc: state.oidc.clone(),
+        sessions: state.sessions.clone(),
+        session_cookie: state.session_cookie.clone(),
+        trusted_proxies: Arc::new(TrustedProxies::new(
+            &state.settings.borrow().server.trusted_proxies,
+        )),
     };
 
-    Router::new()
+    let mut router = Router::new()
         .nest("/health", new_healthcheck_router(healthcheck_state))
         .nest("/auth", new_authentication_router(authentication_state))
+        .nest("/meta", new_meta_router());
+
+    if state.settings.borrow().server.admin_enabled {
+        let admin_state = AdminDeps {
+            settings: state.settings.clone(),
+            api_keys: state.api_keys.clone(),
+            sessions: state.sessions.clone(),
+            signing_keys: state.signing_keys.clone(),
+            lockout: state.lockout.clone(),
+            authorization: state.authorization.clone(),
+        };
+        router = router.nest("/admin", new_admin_router(admin_state));
+    }
+
+    router
+}
+
+/// Routes served while the application initializes: only the health probes,
+/// which report `starting` until the full router takes over.
+pub fn register_v1_startup_routers(
+    healthcheck: Arc<dyn HealthcheckTrait>,
+) -> Router {
+    let healthcheck_state = HealthcheckDeps::new(
+        healthcheck,
+        Arc::new(opentelemetry::global::tracer("api")),
+        log::logger(),
+    );
+    Router::new().nest("/health", new_healthcheck_router(healthcheck_state))
 }
@@ -0,0 +1,35 @@
+use crate::infrastructures::jwt::JwtKeys;
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use http::{HeaderValue, header};
+use std::sync::Arc;
+
+/// Downstream verifiers may cache the key set this long; they are expected to
+/// refetch early when a token names an unknown `kid`.
+const JWKS_MAX_AGE: &str = "public, max-age=300";
+
+#[derive(Clone)]
+pub struct WellKnownDeps {
+    pub jwt_keys: Arc<JwtKeys>,
+}
+
+pub fn new_well_known_router(state: WellKnownDeps) -> Router {
+    Router::new()
+        .route("/jwks.json", get(jwks))
+        .with_state(state)
+}
+
+/// Public keys verifying the access tokens this service issues (RFC 7517).
+/// Served bare rather than in the response envelope so standard JWKS clients
+/// can consume it.
+pub async fn jwks(State(state): State<WellKnownDeps>) -> impl IntoResponse {
+    (
+        [(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(JWKS_MAX_AGE),
+        )],
+        Json(state.jwt_keys.jwks()),
+    )
+}